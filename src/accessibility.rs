@@ -0,0 +1,122 @@
+use bevy::prelude::*;
+
+use crate::gameplay::SpeedAssist;
+use crate::netplay::NetSession;
+use crate::GameState;
+
+pub struct AccessibilityPlugin;
+
+impl Plugin for AccessibilityPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(AccessibilitySettings::default())
+            .add_systems(OnEnter(GameState::Gameplay), apply_speed_assist);
+    }
+}
+
+/// factor applied to text sizes when large text is enabled
+const LARGE_TEXT_SCALE: f32 = 1.35;
+/// factor applied to ball speed when slow-motion assist is enabled
+const SLOW_MOTION_FACTOR: f32 = 0.6;
+/// thickness of contrast outline around ball and dices
+pub const OUTLINE_WIDTH: f32 = 4.;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorVision {
+    #[default]
+    Normal,
+    Deuteranopia,
+    Protanopia,
+    Tritanopia,
+}
+
+impl ColorVision {
+    pub fn next(&self) -> Self {
+        match self {
+            ColorVision::Normal => ColorVision::Deuteranopia,
+            ColorVision::Deuteranopia => ColorVision::Protanopia,
+            ColorVision::Protanopia => ColorVision::Tritanopia,
+            ColorVision::Tritanopia => ColorVision::Normal,
+        }
+    }
+
+    /// Shifts colour information invisible for given deficiency into visible channels
+    /// (daltonization by Fidaner, Lin and Ozguven)
+    pub fn remap(&self, color: Color) -> Color {
+        if *self == ColorVision::Normal {
+            return color;
+        }
+        let [r, g, b, a] = color.as_rgba_f32();
+        let lms = mul(&RGB_TO_LMS, [r, g, b]);
+        let simulated_lms = match self {
+            ColorVision::Protanopia => [2.02344 * lms[1] - 2.52581 * lms[2], lms[1], lms[2]],
+            ColorVision::Deuteranopia => [lms[0], 0.494207 * lms[0] + 1.24827 * lms[2], lms[2]],
+            ColorVision::Tritanopia => [lms[0], lms[1], -0.395913 * lms[0] + 0.801109 * lms[1]],
+            ColorVision::Normal => unreachable!(),
+        };
+        let simulated = mul(&LMS_TO_RGB, simulated_lms);
+        let error = [r - simulated[0], g - simulated[1], b - simulated[2]];
+        Color::rgba(
+            r.clamp(0., 1.),
+            (g + 0.7 * error[0] + error[1]).clamp(0., 1.),
+            (b + 0.7 * error[0] + error[2]).clamp(0., 1.),
+            a,
+        )
+    }
+}
+
+const RGB_TO_LMS: [[f32; 3]; 3] = [
+    [17.8824, 43.5161, 4.11935],
+    [3.45565, 27.1554, 3.86714],
+    [0.0299566, 0.184309, 1.46709],
+];
+
+const LMS_TO_RGB: [[f32; 3]; 3] = [
+    [0.080944, -0.130504, 0.116721],
+    [-0.010249, 0.054019, -0.113615],
+    [-0.000365, -0.004122, 0.693511],
+];
+
+fn mul(matrix: &[[f32; 3]; 3], v: [f32; 3]) -> [f32; 3] {
+    matrix.map(|row| row[0] * v[0] + row[1] * v[1] + row[2] * v[2])
+}
+
+#[derive(Clone, Debug, Default, Resource)]
+pub struct AccessibilitySettings {
+    pub color_vision: ColorVision,
+    /// draw contrasting outline around ball and dices
+    pub outlines: bool,
+    pub large_text: bool,
+    /// slows ball down for players who need more reaction time
+    pub slow_motion: bool,
+}
+
+impl AccessibilitySettings {
+    pub fn text_scale(&self) -> f32 {
+        if self.large_text {
+            LARGE_TEXT_SCALE
+        } else {
+            1.0
+        }
+    }
+
+    pub fn ball_speed_factor(&self) -> f32 {
+        if self.slow_motion {
+            SLOW_MOTION_FACTOR
+        } else {
+            1.0
+        }
+    }
+}
+
+/// Slow-motion assist is local only, networked peers have to simulate same ball speed
+fn apply_speed_assist(
+    settings: Res<AccessibilitySettings>,
+    net_session: Option<Res<NetSession>>,
+    mut speed_assist: ResMut<SpeedAssist>,
+) {
+    speed_assist.0 = if net_session.is_some() {
+        1.0
+    } else {
+        settings.ball_speed_factor()
+    };
+}
@@ -0,0 +1,33 @@
+//! Built-in computer opponent, works on `PongSim` alone.
+
+use crate::sim::{DiceKind, PongSim};
+
+/// Share of full dice speed the AI moves with, steep shots outrun it
+const MAX_INPUT: f32 = 0.3;
+
+/// Share of half dice length off the ball the AI aims to hit with
+const AIM: f32 = 0.9;
+
+/// Dice input of the built-in opponent, follows the ball while it comes
+/// closer on own half and returns to the middle otherwise. Ball is hit off center to
+/// send it away from the other dice.
+pub fn tracking_ai(sim: &PongSim, kind: DiceKind) -> f32 {
+    let ball = &sim.ball;
+    let half_dice = sim.board.dice_length() / 2.;
+    // reacts only once ball is on own half
+    let approaching = match kind {
+        DiceKind::Left => ball.velocity_x < 0. && ball.x < 0.,
+        DiceKind::Right => ball.velocity_x > 0. && ball.x > 0.,
+    };
+    let target_y = if approaching {
+        // ball below dice center bounces downwards and vice versa
+        let aim_down = sim.dice_y(kind.opponent()) >= 0.;
+        let aim = if aim_down { AIM } else { -AIM };
+        ball.y + aim * half_dice
+    } else {
+        0.
+    };
+    let distance = target_y - sim.dice_y(kind);
+    // slows down close to the target instead of jittering around it
+    (distance / half_dice).clamp(-MAX_INPUT, MAX_INPUT)
+}
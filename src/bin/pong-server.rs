@@ -0,0 +1,42 @@
+use std::time::Duration;
+
+use bevy::app::ScheduleRunnerPlugin;
+use bevy::log::LogPlugin;
+use bevy::prelude::*;
+
+use bevy_pong::gameplay::{GameplayPlugin, SIMULATION_HZ};
+use bevy_pong::protocol::DEFAULT_SERVER_PORT;
+use bevy_pong::server::{GameServer, ServerPlugin};
+use bevy_pong::{GameState, LastWinner};
+
+/// Headless match server, `pong-server [port] [--seed <n>]`
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let port = match args.get(1).filter(|arg| !arg.starts_with("--")) {
+        Some(port) => port
+            .parse()
+            .expect("usage: pong-server [port] [--seed <n>]"),
+        None => DEFAULT_SERVER_PORT,
+    };
+    let server = match GameServer::bind(port) {
+        Ok(server) => server,
+        Err(e) => {
+            eprintln!("failed to bind server port {}: {}", port, e);
+            std::process::exit(1);
+        }
+    };
+
+    App::new()
+        .add_state::<GameState>()
+        .insert_resource(LastWinner::default())
+        .insert_resource(server)
+        .add_plugins((
+            MinimalPlugins.set(ScheduleRunnerPlugin::run_loop(Duration::from_secs_f64(
+                1.0 / SIMULATION_HZ,
+            ))),
+            LogPlugin::default(),
+            GameplayPlugin,
+            ServerPlugin,
+        ))
+        .run();
+}
@@ -0,0 +1,161 @@
+use std::fs;
+use std::path::Path;
+
+use bevy::asset::io::file::FileAssetReader;
+use bevy::prelude::*;
+
+use crate::gameplay::{BrickLayout, MatchSim};
+use crate::theme::Theme;
+use crate::utils::load_ron_dir;
+use crate::GameState;
+
+/// Breakout hybrid, versus with destructible bricks in the middle of the board.
+///
+/// Layouts are read from `assets/bricks` and picked in settings, main menu only offers
+/// the mode while one of them is usable.
+pub struct BreakoutPlugin;
+
+impl Plugin for BreakoutPlugin {
+    fn build(&self, app: &mut App) {
+        let library = BrickLibrary::load(&FileAssetReader::get_base_path().join(BRICKS_DIR));
+
+        app.insert_resource(library)
+            // replays bring their own layout, main menu goes back to chosen one
+            .add_systems(OnEnter(GameState::MainMenu), apply_selected_bricks)
+            .add_systems(OnExit(GameState::Gameplay), despawn_bricks)
+            .add_systems(
+                Update,
+                (
+                    apply_selected_bricks.run_if(resource_changed::<BrickLibrary>()),
+                    spawn_bricks.run_if(resource_added::<MatchSim>()),
+                    update_bricks.run_if(resource_exists::<MatchSim>()),
+                ),
+            );
+    }
+}
+
+/// directory with brick layout files, relative to asset base path
+const BRICKS_DIR: &str = "assets/bricks";
+/// share of half board around the centre kept free for the serve
+const SERVE_AREA: f32 = 0.1;
+/// share of half width bricks stay within, dices keep room to move
+const BRICK_AREA: f32 = 0.6;
+/// opacity bricks fade towards while losing hit points
+const DAMAGED_ALPHA: f32 = 0.35;
+
+/// All brick layouts available in settings, the first one is chosen on startup
+#[derive(Resource)]
+pub struct BrickLibrary {
+    layouts: Vec<BrickLayout>,
+    selected: usize,
+}
+
+impl BrickLibrary {
+    /// Reads every `*.ron` file from `dir`, unusable ones are skipped
+    pub fn load(dir: &Path) -> Self {
+        Self {
+            layouts: load_ron_dir(dir, "brick layout", load_layout_file),
+            selected: 0,
+        }
+    }
+
+    /// `None` when there is no usable layout
+    pub fn selected(&self) -> Option<&BrickLayout> {
+        self.layouts.get(self.selected)
+    }
+
+    pub fn select_next(&mut self) {
+        if !self.layouts.is_empty() {
+            self.selected = (self.selected + 1) % self.layouts.len();
+        }
+    }
+}
+
+/// Reads and checks a layout, bricks must have hit points, stay between the dices
+/// and keep the serve spot free
+pub fn load_layout_file(path: &Path) -> Result<BrickLayout, String> {
+    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let layout: BrickLayout = ron::from_str(&content).map_err(|e| e.to_string())?;
+    if layout.bricks.is_empty() {
+        return Err("layout has no bricks".to_string());
+    }
+    for (index, brick) in layout.bricks.iter().enumerate() {
+        let (half_width, half_height) = (brick.width / 2., brick.height / 2.);
+        let fits = brick.width > 0.
+            && brick.height > 0.
+            && brick.x.abs() + half_width <= BRICK_AREA
+            && brick.y.abs() + half_height <= 1.;
+        if !fits {
+            return Err(format!("brick {} is outside of the brick area", index));
+        }
+        let covers_serve =
+            brick.x.abs() - half_width < SERVE_AREA && brick.y.abs() - half_height < SERVE_AREA;
+        if covers_serve {
+            return Err(format!("brick {} covers the serve spot", index));
+        }
+        if brick.hp == 0 {
+            return Err(format!("brick {} has no hit points", index));
+        }
+    }
+    Ok(layout)
+}
+
+/// Sprite of brick `index` in `PongSim::bricks`
+#[derive(Component)]
+struct BrickSprite {
+    index: usize,
+}
+
+fn apply_selected_bricks(library: Res<BrickLibrary>, mut bricks: ResMut<BrickLayout>) {
+    *bricks = library.selected().cloned().unwrap_or_default();
+}
+
+fn spawn_bricks(mut commands: Commands, theme: Res<Theme>, match_sim: Res<MatchSim>) {
+    let sim = &match_sim.0;
+    for (index, brick) in sim.brick_layout.iter().enumerate() {
+        let (x, y) = brick.centre(&sim.board);
+        let (width, height) = brick.size(&sim.board);
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: theme.border,
+                    custom_size: Some(Vec2::new(width, height)),
+                    ..default()
+                },
+                transform: Transform::from_xyz(x, y, 0.),
+                ..default()
+            },
+            BrickSprite { index },
+        ));
+    }
+}
+
+fn despawn_bricks(mut commands: Commands, sprites: Query<Entity, With<BrickSprite>>) {
+    for entity in &sprites {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Fades bricks with their hit points and hides broken ones, all come back with a new round
+fn update_bricks(
+    match_sim: Res<MatchSim>,
+    mut sprites: Query<(&BrickSprite, &mut Sprite, &mut Visibility)>,
+) {
+    let sim = &match_sim.0;
+    for (sprite, mut brick_sprite, mut visibility) in &mut sprites {
+        let (Some(brick), Some(full)) = (
+            sim.bricks.get(sprite.index),
+            sim.brick_layout.get(sprite.index),
+        ) else {
+            continue;
+        };
+        *visibility = if brick.is_broken() {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        };
+        let health = brick.hp as f32 / full.hp as f32;
+        let alpha = DAMAGED_ALPHA + (1. - DAMAGED_ALPHA) * health;
+        brick_sprite.color.set_a(alpha);
+    }
+}
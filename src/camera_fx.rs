@@ -0,0 +1,159 @@
+use bevy::prelude::*;
+
+use crate::gameplay::{DiceHit, PlayerLost};
+use crate::netplay::NetSession;
+use crate::utils::*;
+use crate::GameState;
+
+pub struct CameraFxPlugin;
+
+impl Plugin for CameraFxPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(EffectsSettings::default())
+            .insert_resource(CameraShake::default())
+            .insert_resource(HitStop::default())
+            .add_systems(OnExit(GameState::Gameplay), reset_effects)
+            .add_systems(
+                Update,
+                (trigger_effects, update_camera_shake, update_hit_stop)
+                    .run_if(in_state(GameState::Gameplay)),
+            );
+    }
+}
+
+/// Levels offered in settings for effect strength
+pub const EFFECT_INTENSITIES: [f32; 4] = [0.0, 0.5, 1.0, 1.5];
+
+/// max camera offset in pixels at full trauma
+const MAX_SHAKE_OFFSET: f32 = 18.;
+/// trauma lost per sec
+const SHAKE_DECAY: f32 = 2.5;
+const SCORE_TRAUMA: f32 = 0.8;
+const HARD_HIT_TRAUMA: f32 = 0.45;
+/// hits at or above this share of `MAX_BOUNCE_ANGLE` count as hard
+const HARD_HIT_THRESHOLD: f32 = 0.8;
+/// real secs gameplay is slowed down on hard hit
+const HIT_STOP_SECS: f32 = 0.06;
+/// gameplay time speed during hit-stop
+const HIT_STOP_SPEED: f32 = 0.05;
+
+#[derive(Clone, Debug, Resource)]
+pub struct EffectsSettings {
+    /// multiplier for shake amplitude and hit-stop length, 0 disables both
+    pub intensity: f32,
+    /// accessibility toggle, disables all camera motion and time slowdown
+    pub reduce_motion: bool,
+}
+
+impl Default for EffectsSettings {
+    fn default() -> Self {
+        Self {
+            intensity: 1.0,
+            reduce_motion: false,
+        }
+    }
+}
+
+impl EffectsSettings {
+    pub fn is_enabled(&self) -> bool {
+        !self.reduce_motion && self.intensity > 0.0
+    }
+}
+
+#[derive(Default, Resource)]
+struct CameraShake {
+    /// 0..1, shake strength grows with square of trauma
+    trauma: f32,
+    elapsed: f32,
+}
+
+impl CameraShake {
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).min(1.0);
+    }
+}
+
+#[derive(Default, Resource)]
+struct HitStop {
+    remaining_sec: f32,
+}
+
+fn trigger_effects(
+    settings: Res<EffectsSettings>,
+    mut shake: ResMut<CameraShake>,
+    mut hit_stop: ResMut<HitStop>,
+    mut lost_reader: EventReader<PlayerLost>,
+    mut hit_reader: EventReader<DiceHit>,
+    net_session: Option<Res<NetSession>>,
+) {
+    if !settings.is_enabled() {
+        lost_reader.clear();
+        hit_reader.clear();
+        return;
+    }
+    if lost_reader.read().next().is_some() {
+        shake.add_trauma(SCORE_TRAUMA);
+        lost_reader.clear();
+    }
+    for hit in hit_reader.read() {
+        if hit.bounce_angle >= MAX_BOUNCE_ANGLE * HARD_HIT_THRESHOLD {
+            shake.add_trauma(HARD_HIT_TRAUMA);
+            // slowing local time would only make networked peer wait
+            if net_session.is_none() {
+                hit_stop.remaining_sec = HIT_STOP_SECS * settings.intensity;
+            }
+        }
+    }
+}
+
+fn update_camera_shake(
+    real_time: Res<Time<Real>>,
+    settings: Res<EffectsSettings>,
+    mut shake: ResMut<CameraShake>,
+    mut camera: Query<&mut Transform, With<Camera2d>>,
+) {
+    let dt = real_time.delta().as_secs_f32();
+    shake.trauma = (shake.trauma - SHAKE_DECAY * dt).max(0.0);
+    shake.elapsed += dt;
+
+    let Ok(mut transform) = camera.get_single_mut() else {
+        return;
+    };
+    let strength = if settings.is_enabled() {
+        shake.trauma * shake.trauma * settings.intensity * MAX_SHAKE_OFFSET
+    } else {
+        0.0
+    };
+    // cheap smooth noise: incommensurate frequencies never line up into a visible pattern
+    let t = shake.elapsed;
+    transform.translation.x = strength * ((t * 47.0).sin() + (t * 83.0).sin()) / 2.0;
+    transform.translation.y = strength * ((t * 59.0).sin() + (t * 71.0).cos()) / 2.0;
+}
+
+fn update_hit_stop(
+    real_time: Res<Time<Real>>,
+    mut hit_stop: ResMut<HitStop>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+) {
+    if hit_stop.remaining_sec > 0.0 {
+        hit_stop.remaining_sec -= real_time.delta().as_secs_f32();
+        virtual_time.set_relative_speed(HIT_STOP_SPEED);
+    } else if virtual_time.relative_speed() != 1.0 {
+        virtual_time.set_relative_speed(1.0);
+    }
+}
+
+fn reset_effects(
+    mut shake: ResMut<CameraShake>,
+    mut hit_stop: ResMut<HitStop>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    mut camera: Query<&mut Transform, With<Camera2d>>,
+) {
+    *shake = CameraShake::default();
+    *hit_stop = HitStop::default();
+    virtual_time.set_relative_speed(1.0);
+    if let Ok(mut transform) = camera.get_single_mut() {
+        transform.translation.x = 0.0;
+        transform.translation.y = 0.0;
+    }
+}
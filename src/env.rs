@@ -0,0 +1,127 @@
+//! Gym-style environment to train and benchmark dice agents against the
+//! built-in AI, steps `PongSim` directly without window or engine so it runs
+//! as fast as the CPU allows.
+
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+use crate::ai::tracking_ai;
+use crate::gameplay::SIMULATION_HZ;
+use crate::sim::{BoardConfig, DiceKind, PongSim, SimEvent};
+
+/// Episode is cut off after 10 mins of game time
+const DEFAULT_MAX_TICKS: u32 = 10 * 60 * SIMULATION_HZ as u32;
+
+/// What the agent sees, from its own point of view: own goal line is at
+/// `ball_x` -1, opponent's at 1, walls at `ball_y` -1 and 1
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Observation {
+    pub ball_x: f32,
+    pub ball_y: f32,
+    /// direction of ball travel, length 1 while in play and 0 while serving
+    pub ball_velocity_x: f32,
+    pub ball_velocity_y: f32,
+    /// center of agent's dice
+    pub dice_y: f32,
+    pub opponent_dice_y: f32,
+}
+
+impl Observation {
+    pub fn to_array(&self) -> [f32; 6] {
+        [
+            self.ball_x,
+            self.ball_y,
+            self.ball_velocity_x,
+            self.ball_velocity_y,
+            self.dice_y,
+            self.opponent_dice_y,
+        ]
+    }
+}
+
+/// Match of an agent against the built-in AI, one step is one simulation tick
+pub struct PongEnv {
+    sim: PongSim,
+    rng: ChaCha8Rng,
+    side: DiceKind,
+    ticks: u32,
+    /// steps after which an undecided episode is done
+    pub max_ticks: u32,
+}
+
+impl PongEnv {
+    /// Agent plays `side`, call `reset` to start an episode
+    pub fn new(board: BoardConfig, side: DiceKind) -> Self {
+        Self {
+            sim: PongSim::new(board),
+            rng: ChaCha8Rng::seed_from_u64(0),
+            side,
+            ticks: 0,
+            max_ticks: DEFAULT_MAX_TICKS,
+        }
+    }
+
+    /// Starts a new match, `seed` decides all serves
+    pub fn reset(&mut self, seed: u64) -> Observation {
+        self.sim = PongSim::new(self.sim.board.clone());
+        self.rng = ChaCha8Rng::seed_from_u64(seed);
+        self.ticks = 0;
+        self.observe()
+    }
+
+    /// Moves agent's dice with `action` in -1..1, down to up. Reward is 1 for
+    /// every point won and -1 for every point lost, episode is done once the
+    /// match is decided or `max_ticks` are reached.
+    pub fn step(&mut self, action: f32) -> (Observation, f32, bool) {
+        let action = if action.is_finite() {
+            action.clamp(-1., 1.)
+        } else {
+            0.
+        };
+        let opponent = tracking_ai(&self.sim, self.side.opponent());
+        let inputs = match self.side {
+            DiceKind::Left => (action, opponent),
+            DiceKind::Right => (opponent, action),
+        };
+        let dt = (1. / SIMULATION_HZ) as f32;
+        let events = self.sim.step(dt, inputs, &mut self.rng);
+        self.ticks += 1;
+
+        let mut reward = 0.;
+        for event in events {
+            if let SimEvent::PlayerLost { is_right } = event {
+                let agent_lost = is_right == (self.side == DiceKind::Right);
+                reward += if agent_lost { -1. } else { 1. };
+            }
+        }
+        let done = self.sim.winner().is_some() || self.ticks >= self.max_ticks;
+        (self.observe(), reward, done)
+    }
+
+    pub fn side(&self) -> DiceKind {
+        self.side
+    }
+
+    /// Full state of the match, e.g. to draw it or for scripted agents
+    pub fn sim(&self) -> &PongSim {
+        &self.sim
+    }
+
+    pub fn observe(&self) -> Observation {
+        let half_width = self.sim.board.width() / 2.;
+        let half_height = self.sim.board.height() / 2.;
+        let mirror = match self.side {
+            DiceKind::Left => 1.,
+            DiceKind::Right => -1.,
+        };
+        let ball = &self.sim.ball;
+        Observation {
+            ball_x: ball.x / half_width * mirror,
+            ball_y: ball.y / half_height,
+            ball_velocity_x: ball.velocity_x * mirror,
+            ball_velocity_y: ball.velocity_y,
+            dice_y: self.sim.dice_y(self.side) / half_height,
+            opponent_dice_y: self.sim.dice_y(self.side.opponent()) / half_height,
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use std::fs;
+use std::path::Path;
+
+use bevy::asset::io::file::FileAssetReader;
+use bevy::prelude::*;
+
+use crate::gameplay::{Forces, MatchSim};
+use crate::theme::Theme;
+use crate::utils::{load_ron_dir, select_next_or_off};
+use crate::GameState;
+
+/// Gravity, wind gusts and wells bending the ball in flight.
+///
+/// Presets are read from `assets/forces` and picked in settings, the chosen one is
+/// used by every local match. A grid of faint strokes shows where forces pull.
+pub struct ForcesPlugin;
+
+impl Plugin for ForcesPlugin {
+    fn build(&self, app: &mut App) {
+        let library = ForcesLibrary::load(&FileAssetReader::get_base_path().join(FORCES_DIR));
+
+        app.insert_resource(library)
+            // replays and netplay bring their own forces, main menu goes back to chosen ones
+            .add_systems(OnEnter(GameState::MainMenu), apply_selected_forces)
+            .add_systems(OnExit(GameState::Gameplay), despawn_indicators)
+            .add_systems(
+                Update,
+                (
+                    apply_selected_forces.run_if(resource_changed::<ForcesLibrary>()),
+                    spawn_indicators.run_if(resource_added::<MatchSim>()),
+                    update_indicators.run_if(resource_exists::<MatchSim>()),
+                ),
+            );
+    }
+}
+
+/// directory with forces preset files, relative to asset base path
+const FORCES_DIR: &str = "assets/forces";
+/// more wells would not fit the lobby handshake
+pub const MAX_WELLS: usize = 4;
+/// strongest gravity, wind or well pull, in ball velocity per sec
+pub const MAX_STRENGTH: f32 = 3.;
+/// indicator strokes across and along the board
+const INDICATOR_COLUMNS: usize = 15;
+const INDICATOR_ROWS: usize = 9;
+/// stroke length at `FULL_INDICATOR_FORCE`
+const MAX_INDICATOR_LENGTH: f32 = 36.;
+const INDICATOR_THICKNESS: f32 = 3.;
+/// acceleration drawn with full length and opacity
+const FULL_INDICATOR_FORCE: f32 = 1.5;
+/// opacity of strokes at full force, they stay in the background
+const MAX_INDICATOR_ALPHA: f32 = 0.3;
+
+/// All forces presets available in settings, none is chosen on startup
+#[derive(Resource)]
+pub struct ForcesLibrary {
+    presets: Vec<Forces>,
+    selected: Option<usize>,
+}
+
+impl ForcesLibrary {
+    /// Reads every `*.ron` file from `dir`, unusable ones are skipped
+    pub fn load(dir: &Path) -> Self {
+        Self {
+            presets: load_ron_dir(dir, "forces", load_forces_file),
+            selected: None,
+        }
+    }
+
+    pub fn selected(&self) -> Option<&Forces> {
+        self.selected.map(|index| &self.presets[index])
+    }
+
+    /// Cycles through presets, off comes after the last one
+    pub fn select_next(&mut self) {
+        self.selected = select_next_or_off(self.selected, self.presets.len());
+    }
+}
+
+/// Reads and checks a preset with `check_forces`
+pub fn load_forces_file(path: &Path) -> Result<Forces, String> {
+    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let forces: Forces = ron::from_str(&content).map_err(|e| e.to_string())?;
+    check_forces(&forces)?;
+    Ok(forces)
+}
+
+/// A preset needs some force, none stronger than `MAX_STRENGTH`, wind needs a period
+/// and wells a size on the board. Values that are not finite are never usable.
+pub fn check_forces(forces: &Forces) -> Result<(), String> {
+    let within = |value: f32, limit: f32| value.is_finite() && value.abs() <= limit;
+    if forces.is_calm() {
+        return Err("preset has no forces".to_string());
+    }
+    if !within(forces.gravity, MAX_STRENGTH) {
+        return Err(format!("gravity must be within {}", MAX_STRENGTH));
+    }
+    if let Some(wind) = &forces.wind {
+        if !within(wind.strength, MAX_STRENGTH) {
+            return Err(format!("wind strength must be within {}", MAX_STRENGTH));
+        }
+        if !(wind.period.is_finite() && wind.period > 0.) {
+            return Err("wind period must be positive".to_string());
+        }
+    }
+    if forces.wells.len() > MAX_WELLS {
+        return Err(format!("preset has more than {} wells", MAX_WELLS));
+    }
+    for (index, well) in forces.wells.iter().enumerate() {
+        let on_board =
+            within(well.x, 1.) && within(well.y, 1.) && well.radius.is_finite() && well.radius > 0.;
+        if !on_board {
+            return Err(format!("well {} is outside of the board", index));
+        }
+        if !within(well.strength, MAX_STRENGTH) {
+            return Err(format!(
+                "well {} strength must be within {}",
+                index, MAX_STRENGTH
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Stroke pointing along the acceleration at its position
+#[derive(Component)]
+struct ForceIndicator;
+
+fn apply_selected_forces(library: Res<ForcesLibrary>, mut forces: ResMut<Forces>) {
+    *forces = library.selected().cloned().unwrap_or_default();
+}
+
+fn spawn_indicators(mut commands: Commands, theme: Res<Theme>, match_sim: Res<MatchSim>) {
+    let sim = &match_sim.0;
+    if sim.forces.is_calm() {
+        return;
+    }
+    let (width, height) = (sim.board.width(), sim.board.height());
+    for column in 0..INDICATOR_COLUMNS {
+        for row in 0..INDICATOR_ROWS {
+            // cell centres, none on the border
+            let x = width * ((column as f32 + 0.5) / INDICATOR_COLUMNS as f32 - 0.5);
+            let y = height * ((row as f32 + 0.5) / INDICATOR_ROWS as f32 - 0.5);
+            commands.spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: theme.border.with_a(0.),
+                        custom_size: Some(Vec2::new(1., INDICATOR_THICKNESS)),
+                        ..default()
+                    },
+                    // below everything else on the board
+                    transform: Transform::from_xyz(x, y, -0.8),
+                    ..default()
+                },
+                ForceIndicator,
+            ));
+        }
+    }
+}
+
+fn despawn_indicators(mut commands: Commands, indicators: Query<Entity, With<ForceIndicator>>) {
+    for entity in &indicators {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Turns and scales strokes with the current forces, wind changes them all the time
+fn update_indicators(
+    match_sim: Res<MatchSim>,
+    mut indicators: Query<(&mut Transform, &mut Sprite), With<ForceIndicator>>,
+) {
+    let sim = &match_sim.0;
+    for (mut transform, mut sprite) in &mut indicators {
+        let (x, y) = (transform.translation.x, transform.translation.y);
+        let acceleration = Vec2::from(sim.forces.acceleration(&sim.board, sim.elapsed_secs, x, y));
+        let share = (acceleration.length() / FULL_INDICATOR_FORCE).min(1.);
+        transform.rotation = Quat::from_rotation_z(acceleration.y.atan2(acceleration.x));
+        sprite.custom_size = Some(Vec2::new(share * MAX_INDICATOR_LENGTH, INDICATOR_THICKNESS));
+        sprite.color.set_a(share * MAX_INDICATOR_ALPHA);
+    }
+}
@@ -1,5 +1,13 @@
 use bevy::prelude::*;
 
+use crate::gameplay::TargetBonus;
+use crate::history::{save_match, LastMatch, MatchRecord};
+use crate::practice::PracticeRun;
+use crate::profile::Profiles;
+use crate::replay::{LastReplay, ReplayButton};
+use crate::survival::{spawn_leaderboard_panel, Leaderboard, SurvivalRun};
+use crate::theme::Theme;
+use crate::tournament::{BackToBracketButton, TournamentMatch};
 use crate::utils::*;
 use crate::{GameState, LastWinner};
 
@@ -16,28 +24,112 @@ pub struct MainMenuButton;
 
 impl Plugin for GameOverPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(GameState::GameOver), spawn_menu)
+        app.add_systems(OnEnter(GameState::GameOver), spawn_menu.after(save_match))
             .add_systems(OnExit(GameState::GameOver), despawn_menu)
             .add_systems(Update, (restart_button_clicked, main_menu_button_clicked));
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn spawn_menu(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
+    theme: Res<Theme>,
     last_winner: Res<LastWinner>,
+    last_replay: Option<Res<LastReplay>>,
+    last_match: Option<Res<LastMatch>>,
+    survival_run: Option<Res<SurvivalRun>>,
+    practice_run: Option<Res<PracticeRun>>,
+    target_bonus: Option<Res<TargetBonus>>,
+    (leaderboard, profiles, tournament_match): (
+        Res<Leaderboard>,
+        Res<Profiles>,
+        Option<Res<TournamentMatch>>,
+    ),
 ) {
-    let restart_button = spawn_button(&mut commands, &asset_server, "Restart", Color::GRAY);
-    commands.entity(restart_button).insert(RestartButton);
+    // summary takes room of the large buttons
+    let size = if last_match.is_some() || survival_run.is_some() || practice_run.is_some() {
+        ButtonSize::Compact
+    } else {
+        ButtonSize::Large
+    };
+    // tournament goes on with the next pairing instead
+    let restart_button = if tournament_match.is_some() {
+        let bracket_button = spawn_sized_button(
+            &mut commands,
+            &asset_server,
+            &theme,
+            "Bracket",
+            theme.button_primary,
+            size,
+        );
+        commands.entity(bracket_button).insert(BackToBracketButton);
+        bracket_button
+    } else {
+        let restart_button = spawn_sized_button(
+            &mut commands,
+            &asset_server,
+            &theme,
+            "Restart",
+            theme.button,
+            size,
+        );
+        commands.entity(restart_button).insert(RestartButton);
+        restart_button
+    };
 
-    let main_menu_button = spawn_button(&mut commands, &asset_server, "Main Menu", Color::GRAY);
+    let main_menu_button = spawn_sized_button(
+        &mut commands,
+        &asset_server,
+        &theme,
+        "Main Menu",
+        theme.button,
+        size,
+    );
     commands.entity(main_menu_button).insert(MainMenuButton);
 
-    let player_name = match &last_winner.player {
-        Some(player) => {
-            format!("{:?}", player)
-        }
-        _ => unreachable!(),
+    let replay_button = last_replay.map(|_| {
+        let replay_button = spawn_sized_button(
+            &mut commands,
+            &asset_server,
+            &theme,
+            "Replay",
+            theme.button,
+            size,
+        );
+        commands.entity(replay_button).insert(ReplayButton);
+        replay_button
+    });
+
+    let summary = last_match
+        .map(|last_match| spawn_match_summary(&mut commands, &asset_server, &theme, &last_match.0));
+
+    let leaderboard_panel = survival_run.as_ref().map(|run| {
+        spawn_leaderboard_panel(
+            &mut commands,
+            &asset_server,
+            &theme,
+            &leaderboard,
+            run,
+            &profiles,
+        )
+    });
+
+    let practice_summary = practice_run
+        .as_ref()
+        .map(|run| spawn_practice_summary(&mut commands, &asset_server, &theme, run));
+
+    let mut children = Vec::from_iter(summary.or(leaderboard_panel).or(practice_summary));
+    children.push(restart_button);
+    children.extend(replay_button);
+    children.push(main_menu_button);
+
+    // networked match left by both players has no winner
+    let title = match (&survival_run, &practice_run, &last_winner.player) {
+        (Some(run), _, _) => format!("{} Returns", run.returns),
+        (None, Some(run), _) => run.title(),
+        (None, None, Some(player)) => format!("{:?} Player Won", player),
+        (None, None, None) => "Match Abandoned".to_string(),
     };
 
     commands
@@ -60,18 +152,208 @@ fn spawn_menu(
                     ..default()
                 },
                 text: Text::from_section(
-                    format!("{} Player Won", player_name),
+                    title,
                     TextStyle {
                         font: asset_server.load(FONT_PATH),
-                        font_size: 60.0,
-                        color: Color::BLACK,
+                        font_size: 60.0 * theme.font_scale,
+                        color: theme.text,
                     },
                 ),
                 ..default()
             });
+            // bonus is kept apart from the score deciding the match
+            if let Some(bonus) = target_bonus {
+                commands.spawn(TextBundle {
+                    style: Style {
+                        align_self: AlignSelf::Center,
+                        margin: UiRect::bottom(Val::Percent(1.0)),
+                        ..default()
+                    },
+                    text: Text::from_section(
+                        bonus.as_text(),
+                        TextStyle {
+                            font: asset_server.load(FONT_PATH),
+                            font_size: 32.0 * theme.font_scale,
+                            color: theme.text,
+                        },
+                    ),
+                    ..default()
+                });
+            }
+        })
+        .push_children(&children);
+}
+
+fn spawn_practice_summary(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    theme: &Theme,
+    run: &PracticeRun,
+) -> Entity {
+    commands
+        .spawn(TextBundle {
+            style: Style {
+                align_self: AlignSelf::Center,
+                margin: UiRect::all(Val::Percent(1.0)),
+                ..default()
+            },
+            text: Text::from_section(
+                run.summary(),
+                TextStyle {
+                    font: asset_server.load(FONT_PATH),
+                    font_size: 28.0 * theme.font_scale,
+                    color: theme.text,
+                },
+            ),
+            ..default()
+        })
+        .id()
+}
+
+fn format_duration(secs: f32) -> String {
+    let secs = secs.round() as u32;
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
+
+/// Stats of a finished match with a chart of the score lead after every point
+fn spawn_match_summary(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    theme: &Theme,
+    record: &MatchRecord,
+) -> Entity {
+    let (left, right) = record.score();
+    let offset = |kind| {
+        record
+            .average_hit_offset(kind)
+            .map_or("-".to_string(), |offset| format!("{:.0}%", offset * 100.))
+    };
+    let lines = format!(
+        "Score {}:{}   Duration {}   Longest Rally {} hits\n\
+         Hits {} : {}   Avg Hit Offset {} : {}",
+        left,
+        right,
+        format_duration(record.duration_secs),
+        record.longest_rally(),
+        record.hits.0,
+        record.hits.1,
+        offset(DiceKind::Left),
+        offset(DiceKind::Right),
+    );
+
+    // lead of left player after each point, above the axis for left and below for right
+    let mut lead = 0i32;
+    let leads: Vec<i32> = record
+        .points
+        .iter()
+        .map(|point| {
+            lead += match point.scorer {
+                DiceKind::Left => 1,
+                DiceKind::Right => -1,
+            };
+            lead
+        })
+        .collect();
+    let max_lead = leads
+        .iter()
+        .map(|lead| lead.abs())
+        .max()
+        .unwrap_or(0)
+        .max(1);
+
+    let half = |lead: i32, color: Color, upper: bool| {
+        let share = lead.abs() as f32 / max_lead as f32 * 100.;
+        let bar = NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(share),
+                ..default()
+            },
+            background_color: color.into(),
+            ..default()
+        };
+        let container = NodeBundle {
+            style: Style {
+                height: Val::Percent(50.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: if upper {
+                    JustifyContent::FlexEnd
+                } else {
+                    JustifyContent::FlexStart
+                },
+                ..default()
+            },
+            ..default()
+        };
+        (container, bar)
+    };
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                align_self: AlignSelf::Center,
+                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::Column,
+                width: Val::Percent(65.0),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|commands| {
+            commands.spawn(TextBundle {
+                style: Style {
+                    margin: UiRect::all(Val::Percent(1.0)),
+                    ..default()
+                },
+                text: Text::from_section(
+                    lines,
+                    TextStyle {
+                        font: asset_server.load(FONT_PATH),
+                        font_size: 28.0 * theme.font_scale,
+                        color: theme.text,
+                    },
+                ),
+                ..default()
+            });
+            commands
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Percent(100.0),
+                        height: Val::Px(120.0),
+                        margin: UiRect::all(Val::Percent(1.0)),
+                        border: UiRect::vertical(Val::Px(2.0)),
+                        ..default()
+                    },
+                    border_color: theme.text.into(),
+                    ..default()
+                })
+                .with_children(|chart| {
+                    for &lead in &leads {
+                        chart
+                            .spawn(NodeBundle {
+                                style: Style {
+                                    flex_grow: 1.0,
+                                    flex_direction: FlexDirection::Column,
+                                    margin: UiRect::horizontal(Val::Px(1.0)),
+                                    ..default()
+                                },
+                                ..default()
+                            })
+                            .with_children(|column| {
+                                let (upper, upper_bar) = half(lead.max(0), theme.left_trail, true);
+                                column.spawn(upper).with_children(|upper| {
+                                    upper.spawn(upper_bar);
+                                });
+                                let (lower, lower_bar) =
+                                    half(lead.min(0), theme.right_trail, false);
+                                column.spawn(lower).with_children(|lower| {
+                                    lower.spawn(lower_bar);
+                                });
+                            });
+                    }
+                });
         })
-        .add_child(restart_button)
-        .add_child(main_menu_button);
+        .id()
 }
 
 fn despawn_menu(mut commands: Commands, menu_root: Query<Entity, With<GameOverUIRoot>>) {
@@ -1,550 +1,682 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::ecs::schedule::ScheduleLabel;
+use bevy::ecs::system::RunSystemOnce;
 use bevy::prelude::*;
-use bevy::sprite::MaterialMesh2dBundle;
-use num::clamp;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
 
+use crate::ai::tracking_ai;
+use crate::practice::PracticeOpponent;
+use crate::sim::{PongSim, SimEvent};
 use crate::utils::*;
 use crate::{GameState, LastWinner};
 
+mod client;
+
+pub use crate::sim::{BoardConfig, BrickLayout, Forces, TargetLayout};
+pub use client::GameplayClientPlugin;
+
+/// Headless gameplay simulation, shared by game client and dedicated server.
+///
+/// Thin adapter running `PongSim` in `SimulationStep` and mirroring it into board entities.
 pub struct GameplayPlugin;
 
 impl Plugin for GameplayPlugin {
     fn build(&self, app: &mut App) {
-        let board = BoardConfig {
-            ball_speed: 400.,
-            width: 1000.,
-            height: 700.,
-            dice_width: 40.,
-            dice_length: 150.,
-            dice_offset: 100.,
-            ball_radius: 20.,
-            border_width: 20.,
-            winning_score: 10,
-            start_delay: 1.5,
-        };
+        let board = BoardConfig::default();
+
+        let args: Vec<String> = std::env::args().collect();
+        let mut seeds = SeedSequence::from_args(&args);
+        let first_seed = seeds.next_seed();
 
         app.insert_resource(board)
+            .insert_resource(TargetLayout::default())
+            .insert_resource(BrickLayout::default())
+            .insert_resource(Forces::default())
             .insert_resource(Score::default())
+            .insert_resource(GameRng::from_seed(first_seed))
+            .insert_resource(MatchSeed(first_seed))
+            .insert_resource(seeds)
+            .insert_resource(SpeedAssist(1.0))
+            .insert_resource(SimulationDriver::default())
+            .insert_resource(GameMode::default())
+            .insert_resource(Time::<Fixed>::from_hz(SIMULATION_HZ))
             .add_event::<PlayerLost>()
-            .add_systems(OnEnter(GameState::Gameplay), spawn_board)
+            .add_event::<DiceHit>()
+            .add_event::<TargetHit>()
+            .init_schedule(SimulationStep)
+            .add_systems(
+                SimulationStep,
+                (step_match_sim, sync_board_entities)
+                    .chain()
+                    .in_set(MatchSimSet)
+                    .run_if(resource_exists::<MatchSim>()),
+            )
+            .add_systems(
+                OnEnter(GameState::Gameplay),
+                (
+                    spawn_board,
+                    reseed_match_rng.run_if(
+                        resource_equals(SimulationDriver::Local)
+                            .and_then(not(resource_exists::<ResumedMatch>())),
+                    ),
+                ),
+            )
             .add_systems(OnExit(GameState::Gameplay), despawn_board)
             .add_systems(
                 Update,
-                (
-                    handle_input,
-                    update_dices,
-                    update_ball,
-                    update_dice_animation,
-                    next_round,
-                    update_delayed_ball_start,
+                steer_ai_dices.run_if(
+                    in_state(GameState::Gameplay)
+                        .and_then(resource_equals(SimulationDriver::Local))
+                        .and_then(resource_exists::<AiDices>())
+                        .and_then(resource_exists::<MatchSim>()),
+                ),
+            )
+            .add_systems(
+                FixedUpdate,
+                run_simulation_step.run_if(
+                    in_state(GameState::Gameplay)
+                        .and_then(resource_equals(SimulationDriver::Local)),
                 ),
             );
     }
 }
 
-#[derive(Component)]
-struct BoardTag;
+/// Ticks per sec of the gameplay simulation
+pub const SIMULATION_HZ: f64 = 60.;
 
-#[derive(Component)]
-struct ScoreTextTag;
+/// One deterministic tick of gameplay simulation, ran with fixed time step
+#[derive(ScheduleLabel, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SimulationStep;
 
-#[derive(Component)]
-struct Dice {
-    axis_input: f32,
-    kind: DiceKind,
+/// Stepping of `MatchSim` within `SimulationStep`, systems reading its events run after it
+#[derive(SystemSet, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MatchSimSet;
+
+/// Present while an external driver simulates frames again, e.g. netplay after
+/// rollback. Events of those frames were sent on their first run, so steps skip them.
+#[derive(Resource)]
+pub struct Resimulating;
+
+/// Decides who runs `SimulationStep`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Resource)]
+pub enum SimulationDriver {
+    /// stepped once per fixed update
+    #[default]
+    Local,
+    /// stepped by other plugin, e.g. netplay with rollback
+    External,
 }
 
-#[derive(Component)]
-struct AnimatedDiceBounce {
-    speed: f32,
-    original_x: f32,
-    max_offset: f32,
-    offset_reached: bool,
+/// Rules of local matches, chosen in main menu
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Resource, Serialize, Deserialize)]
+pub enum GameMode {
+    #[default]
+    Versus,
+    /// left dice alone against a wall, score is the number of returns
+    Survival,
+    /// left dice alone against a wall or ball launcher, drills end it
+    Practice(PracticeOpponent),
+    /// versus with bricks in the middle, breaking one scores for the last dice that hit the ball
+    Breakout,
 }
 
-impl AnimatedDiceBounce {
-    pub fn with_dice(dice_x: f32) -> Self {
-        Self {
-            speed: 300.0,
-            original_x: dice_x,
-            max_offset: 5.0,
-            offset_reached: false,
+impl GameMode {
+    /// Side closed by a wall instead of a dice
+    pub fn wall(&self) -> Option<DiceKind> {
+        match self {
+            GameMode::Versus | GameMode::Breakout => None,
+            GameMode::Survival | GameMode::Practice(_) => Some(DiceKind::Right),
+        }
+    }
+
+    pub fn is_practice(&self) -> bool {
+        matches!(self, GameMode::Practice(_))
+    }
+
+    pub fn new_sim(&self, board: BoardConfig, bricks: &BrickLayout) -> PongSim {
+        match (self, self.wall()) {
+            (GameMode::Practice(opponent), Some(wall)) => {
+                PongSim::practice(board, wall, opponent.launcher())
+            }
+            (GameMode::Breakout, _) => PongSim::breakout(board, bricks.bricks.clone()),
+            (_, Some(wall)) => PongSim::survival(board, wall),
+            (_, None) => PongSim::new(board),
         }
     }
 }
 
-#[derive(Component)]
-struct DelayedBallStart {
-    remaining_sec: f32,
+/// Source of every random decision in gameplay, seeded to keep simulation reproducible
+#[derive(Clone, Resource)]
+pub struct GameRng(pub ChaCha8Rng);
+
+impl GameRng {
+    pub fn from_seed(seed: u64) -> Self {
+        Self(ChaCha8Rng::seed_from_u64(seed))
+    }
+
+    /// Exact position in the random stream, to continue it later
+    pub fn state(&self) -> RngState {
+        RngState {
+            seed: self.0.get_seed(),
+            stream: self.0.get_stream(),
+            word_pos: self.0.get_word_pos(),
+        }
+    }
+
+    pub fn from_state(state: &RngState) -> Self {
+        let mut rng = ChaCha8Rng::from_seed(state.seed);
+        rng.set_stream(state.stream);
+        rng.set_word_pos(state.word_pos);
+        Self(rng)
+    }
 }
 
-impl DelayedBallStart {
-    pub fn new(remaining_sec: f32) -> Self {
-        Self { remaining_sec }
+/// Serializable `GameRng`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RngState {
+    seed: [u8; 32],
+    stream: u64,
+    word_pos: u128,
+}
+
+/// Match continued from a save instead of starting a new one, present until
+/// gameplay is left
+#[derive(Clone, Resource)]
+pub struct ResumedMatch {
+    pub sim: PongSim,
+    pub rng: GameRng,
+    pub seed: u64,
+}
+
+/// Seed `GameRng` was created from for the current match, logged to reproduce it
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Resource)]
+pub struct MatchSeed(pub u64);
+
+/// Produces seed of every match in this session, so a whole session replays
+/// from a single `--seed <n>` argument
+#[derive(Resource)]
+pub struct SeedSequence(ChaCha8Rng);
+
+impl SeedSequence {
+    pub fn new(seed: u64) -> Self {
+        Self(ChaCha8Rng::seed_from_u64(seed))
+    }
+
+    /// Seed from `--seed <n>`, otherwise derived from current time
+    pub fn from_args(args: &[String]) -> Self {
+        let from_args = args
+            .iter()
+            .position(|arg| arg == "--seed")
+            .and_then(|position| args.get(position + 1));
+        let seed = match from_args.map(|seed| seed.parse()) {
+            Some(Ok(seed)) => seed,
+            Some(Err(_)) => {
+                error!("usage: --seed <u64>, using time based seed");
+                time_seed()
+            }
+            None => time_seed(),
+        };
+        info!(
+            "session seed {}, rerun with `--seed {}` to reproduce",
+            seed, seed
+        );
+        Self::new(seed)
+    }
+
+    pub fn next_seed(&mut self) -> u64 {
+        self.0.gen()
     }
 }
 
-#[derive(Default, Resource)]
-struct Score {
-    left: usize,
-    right: usize,
+fn time_seed() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_nanos() as u64)
 }
 
-impl Score {
-    pub fn reset(&mut self) {
-        self.left = 0;
-        self.right = 0;
+/// Starts the match with fresh `GameRng` from `SeedSequence`
+pub fn reseed_match_rng(
+    mut seeds: ResMut<SeedSequence>,
+    mut rng: ResMut<GameRng>,
+    mut match_seed: ResMut<MatchSeed>,
+) {
+    let seed = seeds.next_seed();
+    info!("match seed {}", seed);
+    *rng = GameRng::from_seed(seed);
+    match_seed.0 = seed;
+}
+
+/// Starts the match with `GameRng` from seed agreed on elsewhere, e.g. with netplay peer
+pub fn seed_match_rng(world: &mut World, seed: u64) {
+    info!("match seed {}", seed);
+    world.insert_resource(GameRng::from_seed(seed));
+    world.insert_resource(MatchSeed(seed));
+}
+
+/// Dices played by `tracking_ai` instead of keyboard, set for one match
+#[derive(Clone, Debug, Default, PartialEq, Resource)]
+pub struct AiDices(pub Vec<DiceKind>);
+
+impl AiDices {
+    pub fn contains(&self, kind: DiceKind) -> bool {
+        self.0.contains(&kind)
     }
+}
+
+/// Multiplier of ball speed, lowered by slow-motion accessibility assist
+#[derive(Resource)]
+pub struct SpeedAssist(pub f32);
 
+impl Resource for BoardConfig {}
+
+impl Resource for TargetLayout {}
+
+impl Resource for BrickLayout {}
+
+impl Resource for Forces {}
+
+/// Bonus points from target zones, present while the current or last match has targets
+#[derive(Clone, Debug, Default, PartialEq, Resource)]
+pub struct TargetBonus {
+    pub left: usize,
+    pub right: usize,
+    /// against a wall only the other side can score bonus
+    pub wall: Option<DiceKind>,
+}
+
+impl TargetBonus {
     pub fn as_text(&self) -> String {
-        format!("{}:{}", self.left, self.right)
+        match self.wall {
+            Some(DiceKind::Left) => format!("Bonus {}", self.right),
+            Some(DiceKind::Right) => format!("Bonus {}", self.left),
+            None => format!("Bonus {}:{}", self.left, self.right),
+        }
     }
 }
 
-const INPUT_FACTOR: f32 = 1000.;
-const BALL_COLOR: Color = Color::RED;
-
+/// Simulation of the current match, present while board is spawned.
+/// Board entities mirror it after every step.
 #[derive(Resource)]
-struct BoardConfig {
-    /// board dimension from left to right
-    width: f32,
-    /// board dimensions from top to bottom
-    height: f32,
-    /// dice visual thickness
-    dice_width: f32,
-    /// area of dice hit surface
-    dice_length: f32,
-    /// offset from board edge to dice axis
-    dice_offset: f32,
-    /// speed of ball travel
-    ball_speed: f32,
-    /// visual radius of ball
-    ball_radius: f32,
-    /// visual width of surrounding walls
-    border_width: f32,
-    /// score for one player to win the game
-    winning_score: usize,
-    /// secs before ball is launched
-    start_delay: f32,
-}
-
-impl BoardConfig {
-    pub fn max_dice_position(&self, is_top: bool) -> f32 {
-        let offset = self.height / 2. - self.dice_length / 2.;
-        if is_top {
-            offset
-        } else {
-            -offset
+pub struct MatchSim(pub PongSim);
+
+#[derive(Component)]
+struct BoardTag;
+
+#[derive(Clone, Component)]
+struct Dice {
+    axis_input: f32,
+    kind: DiceKind,
+}
+
+/// Ball waits in the middle for the next serve
+#[derive(Component)]
+struct DelayedBallStart;
+
+#[derive(Clone, Default, PartialEq, Resource)]
+struct Score {
+    left: usize,
+    right: usize,
+    /// against a wall only returns of the other side count
+    wall: Option<DiceKind>,
+}
+
+impl Score {
+    pub fn as_text(&self) -> String {
+        match self.wall {
+            Some(DiceKind::Left) => self.right.to_string(),
+            Some(DiceKind::Right) => self.left.to_string(),
+            None => format!("{}:{}", self.left, self.right),
         }
     }
 }
 
-#[derive(Default, Component)]
+/// Ball of `MatchSim` as far as visuals care
+#[derive(Clone, Default, Component)]
 struct Ball {
-    is_colliding_y: bool,
-    is_colliding_x: bool,
-    is_lost: bool,
     velocity_x: f32,
     velocity_y: f32,
+    /// dice that touched the ball last in current round
+    last_hit: Option<DiceKind>,
 }
 
-impl Ball {
-    pub fn reset(&mut self) {
-        *self = Ball::default();
-    }
+#[derive(Clone, Debug, PartialEq, Event)]
+pub struct PlayerLost {
+    pub is_right: bool,
 }
 
-#[derive(Event)]
-struct PlayerLost {
-    is_right: bool,
+/// Sent when the ball bounces off a dice
+#[derive(Clone, Debug, PartialEq, Event)]
+pub struct DiceHit {
+    pub kind: DiceKind,
+    /// absolute angle of the outgoing ball relative to horizontal axis
+    pub bounce_angle: f32,
 }
 
-fn spawn_border(commands: &mut Commands, width: f32, height: f32, position: Vec2) -> Entity {
-    commands
-        .spawn(SpriteBundle {
-            sprite: Sprite {
-                color: Color::BLACK,
-                custom_size: Some(Vec2::new(width, height)),
-                ..default()
-            },
-            transform: Transform::from_translation(Vec3::new(position.x, position.y, 0.)),
-            ..default()
-        })
-        .id()
+/// Sent when a ball passes through a target zone after a dice hit
+#[derive(Clone, Debug, PartialEq, Event)]
+pub struct TargetHit {
+    pub kind: DiceKind,
+    /// index in `PongSim::targets`
+    pub zone: usize,
+    pub points: usize,
 }
 
-fn spawn_dice(commands: &mut Commands, kind: DiceKind, board: &BoardConfig) -> Entity {
-    let position_x = match kind {
-        DiceKind::Left => {
-            -board.width / 2. + board.dice_offset - board.ball_radius - board.dice_width / 2.
-        }
-        DiceKind::Right => {
-            board.width / 2. - board.dice_offset + board.ball_radius + board.dice_width / 2.
-        }
-    };
-    commands
-        .spawn((
-            SpriteBundle {
-                sprite: Sprite {
-                    color: Color::rgb(0.25, 0.25, 0.75),
-                    custom_size: Some(Vec2::new(board.dice_width, board.dice_length)),
-                    ..default()
-                },
-                transform: Transform::from_translation(Vec3::new(position_x, 0., 0.)),
-                ..default()
-            },
-            Dice {
-                axis_input: 0.0,
-                kind,
-            },
-        ))
-        .id()
-}
-
-fn spawn_ball(
-    commands: &mut Commands,
-    meshes: &mut ResMut<Assets<Mesh>>,
-    materials: &mut ResMut<Assets<ColorMaterial>>,
-    board: &BoardConfig,
-) -> Entity {
-    commands
-        .spawn((
-            MaterialMesh2dBundle {
-                mesh: meshes
-                    .add(shape::Circle::new(board.ball_radius).into())
-                    .into(),
-                material: materials.add(ColorMaterial::from(BALL_COLOR)),
-                transform: Transform::from_translation(Vec3::new(0., 0., 0.)),
-                ..default()
-            },
-            Ball::default(),
-            DelayedBallStart::new(board.start_delay),
-        ))
-        .id()
-}
-
-fn spawn_score_text(commands: &mut Commands, asset_server: &mut AssetServer, score: &Score) {
-    commands
-        .spawn(NodeBundle {
-            style: Style {
-                width: Val::Percent(100.0),
-                height: Val::Percent(15.0),
-                justify_content: JustifyContent::Center,
-                flex_direction: FlexDirection::Column,
-                ..default()
-            },
-            ..default()
-        })
-        .insert(BoardTag)
-        .with_children(|parent| {
-            parent
-                .spawn(TextBundle {
-                    style: Style {
-                        align_self: AlignSelf::Center,
-                        ..default()
-                    },
-                    text: Text::from_section(
-                        &score.as_text(),
-                        TextStyle {
-                            font: asset_server.load(FONT_PATH),
-                            font_size: 96.0,
-                            color: Color::DARK_GRAY,
-                        },
-                    ),
-                    ..default()
-                })
-                .insert(ScoreTextTag);
-        });
-}
-
-fn spawn_board(
-    mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-    mut asset_server: ResMut<AssetServer>,
-    mut score: ResMut<Score>,
-    board: Res<BoardConfig>,
-) {
-    score.reset();
-    let border_width = board.border_width;
-    let border_top = spawn_border(
-        &mut commands,
-        board.width + border_width * 2.,
-        border_width,
-        Vec2::new(0., board.height / 2. + border_width / 2.),
-    );
-    commands.entity(border_top).insert(BoardTag);
-    let border_bottom = spawn_border(
-        &mut commands,
-        board.width + border_width * 2.,
-        border_width,
-        Vec2::new(0., -board.height / 2. - border_width / 2.),
-    );
-    commands.entity(border_bottom).insert(BoardTag);
-    let border_right = spawn_border(
-        &mut commands,
-        border_width,
-        board.height + border_width * 2.,
-        Vec2::new(board.width / 2. + border_width / 2., 0.),
-    );
-    commands.entity(border_right).insert(BoardTag);
-    let border_left = spawn_border(
-        &mut commands,
-        border_width,
-        board.height + border_width * 2.,
-        Vec2::new(-board.width / 2. - border_width / 2., 0.),
-    );
-    commands.entity(border_left).insert(BoardTag);
-
-    let left_dice = spawn_dice(&mut commands, DiceKind::Left, &board);
-    let right_dice = spawn_dice(&mut commands, DiceKind::Right, &board);
-    let ball = spawn_ball(&mut commands, &mut meshes, &mut materials, &board);
-    commands.entity(left_dice).insert(BoardTag);
-    commands.entity(right_dice).insert(BoardTag);
-    commands.entity(ball).insert(BoardTag);
-    spawn_score_text(&mut commands, &mut asset_server, &score);
+fn run_simulation_step(world: &mut World) {
+    world.run_schedule(SimulationStep);
 }
 
-fn despawn_board(mut commands: Commands, entities: Query<Entity, With<BoardTag>>) {
-    for entity in &entities {
-        commands.entity(entity).despawn_recursive();
+/// Sets dice inputs which are used by the next `SimulationStep`
+pub fn apply_dice_inputs(world: &mut World, left: f32, right: f32) {
+    let mut dices = world.query::<&mut Dice>();
+    for mut dice in dices.iter_mut(world) {
+        dice.axis_input = match dice.kind {
+            DiceKind::Left => left,
+            DiceKind::Right => right,
+        };
     }
 }
 
-fn next_round(
-    mut commands: Commands,
-    mut score: ResMut<Score>,
-    board: Res<BoardConfig>,
-    mut event_reader: EventReader<PlayerLost>,
-    mut ball: Query<(Entity, &mut Ball, &mut Transform)>,
-    mut next_state: ResMut<NextState<GameState>>,
-    mut last_winner: ResMut<LastWinner>,
-    mut score_text: Query<&mut Text, With<ScoreTextTag>>,
-) {
-    if let Some(PlayerLost { is_right }) = event_reader.read().next() {
-        if *is_right {
-            score.left += 1;
-        } else {
-            score.right += 1;
-        }
+/// Dice inputs `(left, right)` the next `SimulationStep` will use
+pub fn read_dice_inputs(world: &mut World) -> (f32, f32) {
+    let mut dices = world.query::<&Dice>();
+    dice_inputs(dices.iter(world))
+}
 
-        let (entity, mut ball, mut transform) = ball.single_mut();
-        transform.translation.x = 0.0;
-        transform.translation.y = 0.0;
-        ball.reset();
-        commands
-            .entity(entity)
-            .insert(DelayedBallStart::new(board.start_delay));
-
-        if score.left >= board.winning_score {
-            last_winner.player = Some(DiceKind::Left);
-            next_state.set(GameState::GameOver)
-        } else if score.right >= board.winning_score {
-            last_winner.player = Some(DiceKind::Right);
-            next_state.set(GameState::GameOver)
+fn dice_inputs<'a>(dices: impl Iterator<Item = &'a Dice>) -> (f32, f32) {
+    let mut inputs = (0.0, 0.0);
+    for dice in dices {
+        match dice.kind {
+            DiceKind::Left => inputs.0 = dice.axis_input,
+            DiceKind::Right => inputs.1 = dice.axis_input,
         }
-        score_text.single_mut().sections[0].value = score.as_text();
-        event_reader.clear();
     }
+    inputs
+}
+
+/// Complete simulation state between two steps, used to roll simulation back
+#[derive(Clone)]
+pub struct SimSnapshot {
+    sim: PongSim,
+    inputs: (f32, f32),
+    rng: GameRng,
 }
 
-fn handle_input(mut dices: Query<&mut Dice>, keyboard: Res<Input<KeyCode>>) {
-    let mut left_input = 0.0;
-    if keyboard.pressed(KeyCode::W) {
-        left_input += 1.0;
+impl SimSnapshot {
+    /// Returns `None` when board is not spawned
+    pub fn capture(world: &mut World) -> Option<Self> {
+        let sim = world.get_resource::<MatchSim>()?.0.clone();
+        Some(Self {
+            sim,
+            inputs: read_dice_inputs(world),
+            rng: world.resource::<GameRng>().clone(),
+        })
     }
-    if keyboard.pressed(KeyCode::S) {
-        left_input -= 1.0;
+
+    pub fn restore(&self, world: &mut World) {
+        let Some(mut match_sim) = world.get_resource_mut::<MatchSim>() else {
+            return;
+        };
+        match_sim.0 = self.sim.clone();
+        apply_dice_inputs(world, self.inputs.0, self.inputs.1);
+        *world.resource_mut::<GameRng>() = self.rng.clone();
+        world.run_system_once(sync_board_entities);
     }
+}
+
+/// Everything a remote client needs to draw the board, broadcast by dedicated server
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct BoardState {
+    pub ball_x: f32,
+    pub ball_y: f32,
+    pub ball_velocity_x: f32,
+    pub ball_velocity_y: f32,
+    /// ball waits in the middle for the next serve
+    pub serving: bool,
+    pub last_hit: Option<DiceKind>,
+    pub left_dice_y: f32,
+    pub right_dice_y: f32,
+    pub score_left: u32,
+    pub score_right: u32,
+}
 
-    let mut right_input = 0.0;
-    if keyboard.pressed(KeyCode::Up) {
-        right_input += 1.0;
+impl BoardState {
+    /// Returns `None` when board is not spawned
+    pub fn read(world: &World) -> Option<Self> {
+        let sim = &world.get_resource::<MatchSim>()?.0;
+        Some(Self {
+            ball_x: sim.ball.x,
+            ball_y: sim.ball.y,
+            ball_velocity_x: sim.ball.velocity_x,
+            ball_velocity_y: sim.ball.velocity_y,
+            serving: sim.ball.serve_delay.is_some(),
+            last_hit: sim.ball.last_hit,
+            left_dice_y: sim.left_dice_y,
+            right_dice_y: sim.right_dice_y,
+            score_left: sim.score_left as u32,
+            score_right: sim.score_right as u32,
+        })
     }
-    if keyboard.pressed(KeyCode::Down) {
-        right_input -= 1.0;
+
+    /// Overwrites the board with this state, the simulation is not stepped
+    pub fn write(&self, world: &mut World) {
+        let Some(mut match_sim) = world.get_resource_mut::<MatchSim>() else {
+            return;
+        };
+        let sim = &mut match_sim.0;
+        sim.ball.x = self.ball_x;
+        sim.ball.y = self.ball_y;
+        sim.ball.velocity_x = self.ball_velocity_x;
+        sim.ball.velocity_y = self.ball_velocity_y;
+        sim.ball.last_hit = self.last_hit;
+        if !self.serving {
+            sim.ball.serve_delay = None;
+        } else if sim.ball.serve_delay.is_none() {
+            sim.ball.serve_delay = Some(0.0);
+        }
+        sim.left_dice_y = self.left_dice_y;
+        sim.right_dice_y = self.right_dice_y;
+        sim.score_left = self.score_left as usize;
+        sim.score_right = self.score_right as usize;
+        world.run_system_once(sync_board_entities);
     }
 
-    for mut dice in &mut dices {
-        match dice.kind {
-            DiceKind::Right => dice.axis_input = right_input,
-            DiceKind::Left => dice.axis_input = left_input,
+    /// Positions between `self` and `other`, discrete fields are taken from `other`
+    pub fn lerp(&self, other: &Self, t: f32) -> Self {
+        let lerp = |a: f32, b: f32| a + (b - a) * t;
+        // no smoothing across a serve, ball jumps back to the middle
+        let (ball_x, ball_y) = if other.serving && !self.serving {
+            (other.ball_x, other.ball_y)
+        } else {
+            (
+                lerp(self.ball_x, other.ball_x),
+                lerp(self.ball_y, other.ball_y),
+            )
+        };
+        Self {
+            ball_x,
+            ball_y,
+            left_dice_y: lerp(self.left_dice_y, other.left_dice_y),
+            right_dice_y: lerp(self.right_dice_y, other.right_dice_y),
+            ..other.clone()
         }
     }
 }
 
-fn update_dices(
-    time: Res<Time>,
+#[allow(clippy::too_many_arguments)]
+fn spawn_board(
+    mut commands: Commands,
+    mut score: ResMut<Score>,
     board: Res<BoardConfig>,
-    mut dices: Query<(&Dice, &mut Transform)>,
+    targets: Res<TargetLayout>,
+    bricks: Res<BrickLayout>,
+    forces: Res<Forces>,
+    mode: Res<GameMode>,
+    resumed: Option<Res<ResumedMatch>>,
 ) {
-    let dt = time.delta().as_secs_f32();
-    let top_y_limit = board.max_dice_position(true);
-    let bottom_y_limit = board.max_dice_position(false);
-
-    for (dice, mut transform) in &mut dices {
-        transform.translation.y += dt * dice.axis_input * INPUT_FACTOR;
-        transform.translation.y = clamp(transform.translation.y, bottom_y_limit, top_y_limit);
+    let sim = match resumed {
+        Some(resumed) => {
+            info!("match seed {}, resumed", resumed.seed);
+            commands.insert_resource(resumed.rng.clone());
+            commands.insert_resource(MatchSeed(resumed.seed));
+            resumed.sim.clone()
+        }
+        None => PongSim {
+            targets: targets.zones.clone(),
+            forces: forces.clone(),
+            ..mode.new_sim(board.clone(), &bricks)
+        },
+    };
+    *score = Score {
+        left: sim.score_left,
+        right: sim.score_right,
+        wall: sim.wall,
+    };
+    if sim.targets.is_empty() {
+        commands.remove_resource::<TargetBonus>();
+    } else {
+        commands.insert_resource(TargetBonus {
+            left: sim.bonus_left,
+            right: sim.bonus_right,
+            wall: sim.wall,
+        });
     }
+    // wall side has no dice
+    for kind in [DiceKind::Left, DiceKind::Right] {
+        if mode.wall() == Some(kind) {
+            continue;
+        }
+        let position_x = board.dice_position_x(kind);
+        commands.spawn((
+            TransformBundle::from_transform(Transform::from_translation(Vec3::new(
+                position_x,
+                sim.dice_y(kind),
+                0.,
+            ))),
+            Dice {
+                axis_input: 0.0,
+                kind,
+            },
+            BoardTag,
+        ));
+    }
+    // resumed ball may be in flight
+    let mut ball = commands.spawn((
+        TransformBundle::from_transform(Transform::from_xyz(sim.ball.x, sim.ball.y, 0.)),
+        Ball {
+            velocity_x: sim.ball.velocity_x,
+            velocity_y: sim.ball.velocity_y,
+            last_hit: sim.ball.last_hit,
+        },
+        BoardTag,
+    ));
+    if sim.ball.serve_delay.is_some() {
+        ball.insert(DelayedBallStart);
+    }
+    commands.insert_resource(MatchSim(sim));
 }
 
-// returns normalized ball velocity
-fn calculate_dice_collision(
-    ball_y: f32,
-    quarter_ball: f32,
-    dice_y: f32,
-    half_dice: f32,
-    kind: DiceKind,
-) -> Option<Vec2> {
-    let upper_edge = dice_y + half_dice + quarter_ball;
-    let lower_edge = dice_y - half_dice - quarter_ball;
-    if ball_y <= upper_edge && ball_y >= lower_edge {
-        let ball_shift = (dice_y - ball_y) / (half_dice + quarter_ball);
-        let bounce_angle = ball_shift * MAX_BOUNCE_ANGLE;
-        let new_v = match kind {
-            DiceKind::Right => Vec2::new(-bounce_angle.cos(), -bounce_angle.sin()),
-            DiceKind::Left => Vec2::new(bounce_angle.cos(), -bounce_angle.sin()),
-        };
-        return Some(new_v);
+fn despawn_board(mut commands: Commands, entities: Query<Entity, With<BoardTag>>) {
+    commands.remove_resource::<MatchSim>();
+    commands.remove_resource::<ResumedMatch>();
+    commands.remove_resource::<AiDices>();
+    for entity in &entities {
+        commands.entity(entity).despawn_recursive();
     }
-    None
 }
 
-fn update_ball(
-    mut commands: Commands,
+/// Steps `MatchSim` with current dice inputs and forwards what happened
+#[allow(clippy::too_many_arguments)]
+fn step_match_sim(
     time: Res<Time>,
-    board: Res<BoardConfig>,
-    mut ball: Query<(&mut Ball, &mut Transform), Without<Dice>>,
-    dices: Query<(Entity, &Transform, &Dice), With<Dice>>,
-    mut event_writer: EventWriter<PlayerLost>,
+    speed_assist: Res<SpeedAssist>,
+    mut rng: ResMut<GameRng>,
+    mut match_sim: ResMut<MatchSim>,
+    dices: Query<&Dice>,
+    mut lost_writer: EventWriter<PlayerLost>,
+    mut hit_writer: EventWriter<DiceHit>,
+    mut target_writer: EventWriter<TargetHit>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut last_winner: ResMut<LastWinner>,
+    resimulating: Option<Res<Resimulating>>,
 ) {
+    let silent = resimulating.is_some();
+    let sim = &mut match_sim.0;
+    sim.ball_speed_factor = speed_assist.0;
     let dt = time.delta().as_secs_f32();
-
-    if let Ok((mut ball, mut transform)) = ball.get_single_mut() {
-        transform.translation.x += ball.velocity_x * dt * board.ball_speed;
-        transform.translation.y += ball.velocity_y * dt * board.ball_speed;
-        let (ball_x, ball_y) = (transform.translation.x, transform.translation.y);
-
-        let top_y = board.height / 2. - board.ball_radius;
-        let bottom_y = -board.height / 2. + board.ball_radius;
-        let dice_axis_left = -board.width / 2. + board.dice_offset;
-        let dice_axis_right = board.width / 2. - board.dice_offset;
-        if !ball.is_colliding_x {
-            let half_dice = board.dice_length / 2.;
-            let over_right_axis = ball_x > dice_axis_right;
-            let over_left_axis = ball_x < dice_axis_left;
-            if (over_left_axis || over_right_axis) && !ball.is_lost {
-                let check_dice = match (over_right_axis, over_left_axis) {
-                    (true, false) => DiceKind::Right,
-                    (false, true) => DiceKind::Left,
-                    _ => unreachable!(),
-                };
-                for (entity, transform, dice) in dices.iter() {
-                    if dice.kind == check_dice {
-                        let dice_x = transform.translation.x;
-                        let dice_y = transform.translation.y;
-                        let quarter_ball = board.ball_radius / 2.;
-                        if let Some(new_v) = calculate_dice_collision(
-                            ball_y,
-                            quarter_ball,
-                            dice_y,
-                            half_dice,
-                            dice.kind,
-                        ) {
-                            ball.velocity_x = new_v.x;
-                            ball.velocity_y = new_v.y;
-                            ball.is_colliding_x = true;
-                            commands
-                                .entity(entity)
-                                .insert(AnimatedDiceBounce::with_dice(dice_x));
-                        } else {
-                            ball.is_lost = true;
-                        }
-                    }
-                }
-            }
-        } else {
-            if ball_x > dice_axis_left && ball_x < dice_axis_right {
-                ball.is_colliding_x = false;
+    let mut scored = false;
+    for event in sim.step(dt, dice_inputs(dices.iter()), &mut rng.0) {
+        match event {
+            SimEvent::DiceHit { kind, bounce_angle } if !silent => {
+                hit_writer.send(DiceHit { kind, bounce_angle })
             }
-        }
-        if !ball.is_colliding_y {
-            if ball_y > top_y || ball_y < bottom_y {
-                ball.velocity_y *= -1.;
-                ball.is_colliding_y = true;
-            }
-        } else {
-            if ball_y < top_y && ball_y > bottom_y {
-                ball.is_colliding_y = false;
-            }
-        }
-        if ball.is_lost {
-            if ball_x > board.width / 2. {
-                event_writer.send(PlayerLost { is_right: true });
+            SimEvent::PlayerLost { is_right } => {
+                if !silent {
+                    lost_writer.send(PlayerLost { is_right });
+                }
+                scored = true;
             }
-            if ball_x < -board.width / 2. {
-                event_writer.send(PlayerLost { is_right: false });
+            SimEvent::TargetHit { kind, zone, points } if !silent => {
+                target_writer.send(TargetHit { kind, zone, points })
             }
+            SimEvent::DiceHit { .. } | SimEvent::TargetHit { .. } => {}
+            SimEvent::BrickHit { broken, .. } => scored |= broken,
+            SimEvent::BricksCleared => {}
         }
     }
+    if let Some(winner) = sim.winner().filter(|_| scored) {
+        last_winner.player = Some(winner);
+        next_state.set(GameState::GameOver);
+    }
 }
 
-fn update_dice_animation(
-    mut commands: Commands,
-    time: Res<Time>,
-    mut query: Query<(Entity, &mut Transform, &Dice, &mut AnimatedDiceBounce)>,
-) {
-    let dt = time.delta().as_secs_f32();
-    for (entity, mut transform, dice, mut anim) in query.iter_mut() {
-        let dice_x = &mut transform.translation.x;
-        if !anim.offset_reached {
-            match dice.kind {
-                DiceKind::Right => {
-                    *dice_x += dt * anim.speed;
-                    if *dice_x > anim.original_x + anim.max_offset {
-                        anim.offset_reached = true;
-                    }
-                }
-                DiceKind::Left => {
-                    *dice_x -= dt * anim.speed;
-                    if *dice_x < anim.original_x - anim.max_offset {
-                        anim.offset_reached = true;
-                    }
-                }
-            }
-        } else {
-            match dice.kind {
-                DiceKind::Right => {
-                    *dice_x -= dt * anim.speed;
-                    if *dice_x <= anim.original_x {
-                        *dice_x = anim.original_x;
-                        commands.entity(entity).remove::<AnimatedDiceBounce>();
-                    }
-                }
-                DiceKind::Left => {
-                    *dice_x += dt * anim.speed;
-                    if *dice_x >= anim.original_x {
-                        *dice_x = anim.original_x;
-                        commands.entity(entity).remove::<AnimatedDiceBounce>();
-                    }
-                }
-            }
+fn steer_ai_dices(ai_dices: Res<AiDices>, match_sim: Res<MatchSim>, mut dices: Query<&mut Dice>) {
+    for mut dice in &mut dices {
+        if ai_dices.contains(dice.kind) {
+            dice.axis_input = tracking_ai(&match_sim.0, dice.kind);
         }
     }
 }
 
-fn update_delayed_ball_start(
-    time: Res<Time>,
+/// Moves board entities to where `MatchSim` has them
+fn sync_board_entities(
     mut commands: Commands,
-    mut query: Query<(Entity, &mut Ball, &mut DelayedBallStart)>,
+    match_sim: Res<MatchSim>,
+    mut score: ResMut<Score>,
+    bonus: Option<ResMut<TargetBonus>>,
+    mut balls: Query<(Entity, &mut Ball, &mut Transform, Has<DelayedBallStart>)>,
+    mut dices: Query<(&Dice, &mut Transform), Without<Ball>>,
 ) {
-    if let Ok((entity, mut ball, mut delay)) = query.get_single_mut() {
-        let dt = time.delta().as_secs_f32();
-        delay.remaining_sec -= dt;
-        if delay.remaining_sec < 0.0 {
-            let angle = get_random_starting_angle();
-            ball.velocity_x = angle.x;
-            ball.velocity_y = angle.y;
+    let sim = &match_sim.0;
+    if let Ok((entity, mut ball, mut transform, was_serving)) = balls.get_single_mut() {
+        ball.velocity_x = sim.ball.velocity_x;
+        ball.velocity_y = sim.ball.velocity_y;
+        ball.last_hit = sim.ball.last_hit;
+        transform.translation.x = sim.ball.x;
+        transform.translation.y = sim.ball.y;
+        let serving = sim.ball.serve_delay.is_some();
+        if serving && !was_serving {
+            commands.entity(entity).insert(DelayedBallStart);
+        } else if !serving && was_serving {
             commands.entity(entity).remove::<DelayedBallStart>();
         }
     }
+    for (dice, mut transform) in &mut dices {
+        // x is left to bounce animation, it does not affect simulation
+        transform.translation.y = sim.dice_y(dice.kind);
+    }
+    score.set_if_neq(Score {
+        left: sim.score_left,
+        right: sim.score_right,
+        wall: sim.wall,
+    });
+    if let Some(mut bonus) = bonus {
+        bonus.set_if_neq(TargetBonus {
+            left: sim.bonus_left,
+            right: sim.bonus_right,
+            wall: sim.wall,
+        });
+    }
 }
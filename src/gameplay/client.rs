@@ -0,0 +1,490 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+use bevy::sprite::{MaterialMesh2dBundle, Mesh2dHandle};
+use bevy::window::PrimaryWindow;
+
+use super::*;
+use crate::accessibility::OUTLINE_WIDTH;
+use crate::profile::{Controls, Profiles};
+use crate::theme::Theme;
+
+/// Local player side of gameplay: keyboard input and everything drawn on screen.
+/// Simulation entities spawned by `GameplayPlugin` get their visuals attached here.
+pub struct GameplayClientPlugin;
+
+impl Plugin for GameplayClientPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Gameplay), spawn_board_visuals)
+            .add_systems(
+                Update,
+                (
+                    // networked dices are driven by their session, never by local keys
+                    handle_input.run_if(resource_equals(SimulationDriver::Local)),
+                    decorate_dices,
+                    decorate_ball,
+                    reset_trail_on_serve,
+                    update_ball_trail.run_if(resource_exists::<MatchSim>()),
+                    start_dice_bounce,
+                    update_dice_animation,
+                    update_score_text.run_if(resource_changed::<Score>()),
+                    update_score_area_layout,
+                ),
+            );
+    }
+}
+
+/// max number of ghost circles drawn behind the ball
+const TRAIL_LENGTH: usize = 12;
+/// secs between two recorded trail positions
+const TRAIL_SAMPLE_INTERVAL: f32 = 0.016;
+/// ball speed at which the whole trail is shown
+const TRAIL_FULL_SPEED: f32 = 800.;
+/// opacity of the ghost closest to the ball
+const TRAIL_MAX_ALPHA: f32 = 0.5;
+
+#[derive(Component)]
+struct ScoreTextTag;
+
+#[derive(Component)]
+struct ScoreAreaTag;
+
+#[derive(Component)]
+struct AnimatedDiceBounce {
+    speed: f32,
+    original_x: f32,
+    max_offset: f32,
+    offset_reached: bool,
+}
+
+impl AnimatedDiceBounce {
+    pub fn with_dice(dice_x: f32) -> Self {
+        Self {
+            speed: 300.0,
+            original_x: dice_x,
+            max_offset: 5.0,
+            offset_reached: false,
+        }
+    }
+}
+
+/// Recent ball positions, newest first
+#[derive(Default, Component)]
+struct BallTrail {
+    samples: VecDeque<Vec2>,
+    since_last_sample: f32,
+}
+
+impl BallTrail {
+    pub fn reset(&mut self) {
+        *self = BallTrail::default();
+    }
+}
+
+#[derive(Component)]
+struct TrailGhost {
+    index: usize,
+}
+
+fn spawn_border(
+    commands: &mut Commands,
+    theme: &Theme,
+    width: f32,
+    height: f32,
+    position: Vec2,
+) -> Entity {
+    commands
+        .spawn(SpriteBundle {
+            sprite: Sprite {
+                color: theme.border,
+                custom_size: Some(Vec2::new(width, height)),
+                ..default()
+            },
+            transform: Transform::from_translation(Vec3::new(position.x, position.y, 0.)),
+            ..default()
+        })
+        .id()
+}
+
+fn decorate_dices(
+    mut commands: Commands,
+    theme: Res<Theme>,
+    board: Res<BoardConfig>,
+    dices: Query<(Entity, &Dice), Added<Dice>>,
+) {
+    for (entity, dice) in &dices {
+        let color = match dice.kind {
+            DiceKind::Left => theme.left_dice,
+            DiceKind::Right => theme.right_dice,
+        };
+        let mut dice = commands.entity(entity);
+        dice.insert((
+            Sprite {
+                color,
+                custom_size: Some(Vec2::new(board.dice_width(), board.dice_length())),
+                ..default()
+            },
+            Handle::<Image>::default(),
+            VisibilityBundle::default(),
+        ));
+        if let Some(outline) = theme.outline {
+            dice.with_children(|parent| {
+                parent.spawn(SpriteBundle {
+                    sprite: Sprite {
+                        color: outline,
+                        custom_size: Some(Vec2::new(
+                            board.dice_width() + OUTLINE_WIDTH * 2.,
+                            board.dice_length() + OUTLINE_WIDTH * 2.,
+                        )),
+                        ..default()
+                    },
+                    transform: Transform::from_translation(Vec3::new(0., 0., -0.05)),
+                    ..default()
+                });
+            });
+        }
+    }
+}
+
+fn decorate_ball(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    theme: Res<Theme>,
+    board: Res<BoardConfig>,
+    balls: Query<Entity, Added<Ball>>,
+) {
+    for entity in &balls {
+        let mut ball = commands.entity(entity);
+        ball.insert((
+            Mesh2dHandle(meshes.add(shape::Circle::new(board.ball_radius()).into())),
+            materials.add(ColorMaterial::from(theme.ball)),
+            VisibilityBundle::default(),
+            BallTrail::default(),
+        ));
+        if let Some(outline) = theme.outline {
+            let outline_mesh =
+                meshes.add(shape::Circle::new(board.ball_radius() + OUTLINE_WIDTH).into());
+            let outline_material = materials.add(ColorMaterial::from(outline));
+            ball.with_children(|parent| {
+                parent.spawn(MaterialMesh2dBundle {
+                    mesh: outline_mesh.into(),
+                    material: outline_material,
+                    // above trail ghosts, below the ball
+                    transform: Transform::from_translation(Vec3::new(0., 0., -0.05)),
+                    ..default()
+                });
+            });
+        }
+    }
+}
+
+fn spawn_trail_ghosts(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<ColorMaterial>>,
+    theme: &Theme,
+    board: &BoardConfig,
+) {
+    let mesh = meshes.add(shape::Circle::new(board.ball_radius()).into());
+    for index in 0..TRAIL_LENGTH {
+        commands.spawn((
+            MaterialMesh2dBundle {
+                mesh: mesh.clone().into(),
+                material: materials.add(ColorMaterial::from(theme.ball)),
+                // keep ghosts below the ball, older ones further back
+                transform: Transform::from_translation(Vec3::new(
+                    0.,
+                    0.,
+                    -0.1 - index as f32 * 0.01,
+                )),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            TrailGhost { index },
+            BoardTag,
+        ));
+    }
+}
+
+fn spawn_score_text(
+    commands: &mut Commands,
+    asset_server: &mut AssetServer,
+    theme: &Theme,
+    score: &Score,
+) {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            ..default()
+        })
+        .insert((BoardTag, ScoreAreaTag))
+        .with_children(|parent| {
+            parent
+                .spawn(TextBundle {
+                    style: Style {
+                        align_self: AlignSelf::Center,
+                        ..default()
+                    },
+                    text: Text::from_section(
+                        score.as_text(),
+                        TextStyle {
+                            font: asset_server.load(FONT_PATH),
+                            font_size: 96.0 * theme.font_scale,
+                            color: theme.score_text,
+                        },
+                    ),
+                    ..default()
+                })
+                .insert(ScoreTextTag);
+        });
+}
+
+/// Keeps score text inside the upper score area of letterboxed board,
+/// px values are in world units since `UiScale` matches camera scaling
+fn update_score_area_layout(
+    board: Res<BoardConfig>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut score_area: Query<&mut Style, With<ScoreAreaTag>>,
+) {
+    let (Ok(window), Ok(mut style)) = (windows.get_single(), score_area.get_single_mut()) else {
+        return;
+    };
+    let world_size = Vec2::from(board.world_size());
+    let scale = world_scale(window, world_size);
+    let letterbox_top = (window.height() / scale - world_size.y) / 2.;
+    style.top = Val::Px(letterbox_top);
+    style.height = Val::Px(board.score_area_height());
+}
+
+#[allow(clippy::too_many_arguments)]
+fn spawn_board_visuals(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut asset_server: ResMut<AssetServer>,
+    score: Res<Score>,
+    theme: Res<Theme>,
+    board: Res<BoardConfig>,
+    mode: Res<GameMode>,
+) {
+    let border_width = board.border_width();
+    let border_top = spawn_border(
+        &mut commands,
+        &theme,
+        board.width() + border_width * 2.,
+        border_width,
+        Vec2::new(0., board.height() / 2. + border_width / 2.),
+    );
+    commands.entity(border_top).insert(BoardTag);
+    let border_bottom = spawn_border(
+        &mut commands,
+        &theme,
+        board.width() + border_width * 2.,
+        border_width,
+        Vec2::new(0., -board.height() / 2. - border_width / 2.),
+    );
+    commands.entity(border_bottom).insert(BoardTag);
+    let border_right = spawn_border(
+        &mut commands,
+        &theme,
+        border_width,
+        board.height() + border_width * 2.,
+        Vec2::new(board.width() / 2. + border_width / 2., 0.),
+    );
+    commands.entity(border_right).insert(BoardTag);
+    let border_left = spawn_border(
+        &mut commands,
+        &theme,
+        border_width,
+        board.height() + border_width * 2.,
+        Vec2::new(-board.width() / 2. - border_width / 2., 0.),
+    );
+    commands.entity(border_left).insert(BoardTag);
+    if let Some(wall) = mode.wall() {
+        let wall = spawn_border(
+            &mut commands,
+            &theme,
+            board.dice_width(),
+            board.height(),
+            Vec2::new(board.dice_position_x(wall), 0.),
+        );
+        commands.entity(wall).insert(BoardTag);
+    }
+
+    spawn_trail_ghosts(&mut commands, &mut meshes, &mut materials, &theme, &board);
+    spawn_score_text(&mut commands, &mut asset_server, &theme, &score);
+}
+
+fn update_score_text(score: Res<Score>, mut score_text: Query<&mut Text, With<ScoreTextTag>>) {
+    if let Ok(mut text) = score_text.get_single_mut() {
+        text.sections[0].value = score.as_text();
+    }
+}
+
+/// Keys follow the active profile, without profiles left dice is W/S and right one Up/Down.
+/// Dices in `AiDices` are left to the AI.
+fn handle_input(
+    mut dices: Query<&mut Dice>,
+    keyboard: Res<Input<KeyCode>>,
+    profiles: Option<Res<Profiles>>,
+    ai_dices: Option<Res<AiDices>>,
+) {
+    for mut dice in &mut dices {
+        if ai_dices
+            .as_ref()
+            .is_some_and(|ai_dices| ai_dices.contains(dice.kind))
+        {
+            continue;
+        }
+        let controls = match (&profiles, dice.kind) {
+            (Some(profiles), kind) => profiles.controls(kind),
+            (None, DiceKind::Left) => Controls::WS,
+            (None, DiceKind::Right) => Controls::Arrows,
+        };
+        let (up, down) = controls.keys();
+        let mut input = 0.0;
+        if keyboard.pressed(up) {
+            input += 1.0;
+        }
+        if keyboard.pressed(down) {
+            input -= 1.0;
+        }
+        dice.axis_input = input;
+    }
+}
+
+/// Trail from previous rally must not connect to the ball waiting for serve
+fn reset_trail_on_serve(mut trails: Query<&mut BallTrail, Added<DelayedBallStart>>) {
+    for mut trail in &mut trails {
+        trail.reset();
+    }
+}
+
+fn update_ball_trail(
+    time: Res<Time>,
+    theme: Res<Theme>,
+    match_sim: Res<MatchSim>,
+    mut ball: Query<(&Ball, &mut BallTrail, &Transform)>,
+    mut ghosts: Query<
+        (
+            &TrailGhost,
+            &mut Transform,
+            &mut Visibility,
+            &Handle<ColorMaterial>,
+        ),
+        Without<Ball>,
+    >,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let Ok((ball, mut trail, ball_transform)) = ball.get_single_mut() else {
+        return;
+    };
+    let speed = match_sim.0.ball_speed();
+
+    trail.since_last_sample += time.delta().as_secs_f32();
+    if speed == 0.0 {
+        trail.samples.clear();
+    } else if trail.since_last_sample >= TRAIL_SAMPLE_INTERVAL {
+        trail.since_last_sample = 0.0;
+        trail
+            .samples
+            .push_front(ball_transform.translation.truncate());
+        trail.samples.truncate(TRAIL_LENGTH);
+    }
+
+    let visible_len = (TRAIL_LENGTH as f32 * (speed / TRAIL_FULL_SPEED).min(1.0)).round() as usize;
+    let color = match ball.last_hit {
+        Some(DiceKind::Left) => theme.left_trail,
+        Some(DiceKind::Right) => theme.right_trail,
+        None => theme.ball,
+    };
+    for (ghost, mut transform, mut visibility, material) in &mut ghosts {
+        let Some(position) = trail
+            .samples
+            .get(ghost.index)
+            .filter(|_| ghost.index < visible_len)
+        else {
+            *visibility = Visibility::Hidden;
+            continue;
+        };
+        // fade and shrink towards the tail
+        let fade = 1.0 - ghost.index as f32 / visible_len as f32;
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
+        transform.scale = Vec3::splat(0.5 + 0.5 * fade);
+        *visibility = Visibility::Visible;
+        if let Some(material) = materials.get_mut(material) {
+            material.color = color.with_a(TRAIL_MAX_ALPHA * fade);
+        }
+    }
+}
+
+fn start_dice_bounce(
+    mut commands: Commands,
+    board: Res<BoardConfig>,
+    mut hit_reader: EventReader<DiceHit>,
+    dices: Query<(Entity, &Dice)>,
+) {
+    for hit in hit_reader.read() {
+        for (entity, dice) in &dices {
+            if dice.kind == hit.kind {
+                // always bounce from rest position, hit can come in mid-animation
+                let dice_x = board.dice_position_x(dice.kind);
+                commands
+                    .entity(entity)
+                    .insert(AnimatedDiceBounce::with_dice(dice_x));
+            }
+        }
+    }
+}
+
+fn update_dice_animation(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut Transform, &Dice, &mut AnimatedDiceBounce)>,
+) {
+    let dt = time.delta().as_secs_f32();
+    for (entity, mut transform, dice, mut anim) in query.iter_mut() {
+        let dice_x = &mut transform.translation.x;
+        if !anim.offset_reached {
+            match dice.kind {
+                DiceKind::Right => {
+                    *dice_x += dt * anim.speed;
+                    if *dice_x > anim.original_x + anim.max_offset {
+                        anim.offset_reached = true;
+                    }
+                }
+                DiceKind::Left => {
+                    *dice_x -= dt * anim.speed;
+                    if *dice_x < anim.original_x - anim.max_offset {
+                        anim.offset_reached = true;
+                    }
+                }
+            }
+        } else {
+            match dice.kind {
+                DiceKind::Right => {
+                    *dice_x -= dt * anim.speed;
+                    if *dice_x <= anim.original_x {
+                        *dice_x = anim.original_x;
+                        commands.entity(entity).remove::<AnimatedDiceBounce>();
+                    }
+                }
+                DiceKind::Left => {
+                    *dice_x += dt * anim.speed;
+                    if *dice_x >= anim.original_x {
+                        *dice_x = anim.original_x;
+                        commands.entity(entity).remove::<AnimatedDiceBounce>();
+                    }
+                }
+            }
+        }
+    }
+}
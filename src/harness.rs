@@ -0,0 +1,133 @@
+use std::time::Duration;
+
+use bevy::ecs::event::ManualEventReader;
+use bevy::prelude::*;
+
+use crate::gameplay::{
+    apply_dice_inputs, seed_match_rng, BoardConfig, BoardState, DiceHit, GameplayPlugin,
+    PlayerLost, SimulationDriver, SimulationStep, SIMULATION_HZ,
+};
+use crate::resume::SavedMatch;
+use crate::{GameState, LastWinner};
+
+/// Gameplay without window, rendering or assets, stepped manually by tests.
+///
+/// Every `step` is one `SimulationStep` with fixed time delta, so same seed and
+/// inputs always give same results.
+pub struct HeadlessGame {
+    pub app: App,
+    lost_reader: ManualEventReader<PlayerLost>,
+    hit_reader: ManualEventReader<DiceHit>,
+    /// events of the last `step`
+    lost: Vec<PlayerLost>,
+    hits: Vec<DiceHit>,
+}
+
+impl HeadlessGame {
+    /// Builds app and enters `GameState::Gameplay` with serves from `seed`
+    pub fn new(seed: u64) -> Self {
+        let mut game = Self::build();
+        game.set_state(GameState::Gameplay);
+        seed_match_rng(&mut game.app.world, seed);
+        game
+    }
+
+    /// Builds app and enters `GameState::Gameplay` continuing `saved`
+    pub fn resume(saved: SavedMatch) -> Self {
+        let mut game = Self::build();
+        saved.restore(&mut game.app.world);
+        game.set_state(GameState::Gameplay);
+        game
+    }
+
+    fn build() -> Self {
+        let mut app = App::new();
+        app.add_state::<GameState>()
+            .insert_resource(LastWinner::default())
+            .add_plugins((MinimalPlugins, GameplayPlugin))
+            .insert_resource(SimulationDriver::External);
+        app.finish();
+        app.cleanup();
+
+        Self {
+            app,
+            lost_reader: ManualEventReader::default(),
+            hit_reader: ManualEventReader::default(),
+            lost: Vec::new(),
+            hits: Vec::new(),
+        }
+    }
+
+    /// Current match as it would be saved on leaving, `None` when board is not spawned
+    pub fn save(&self) -> Option<SavedMatch> {
+        SavedMatch::capture(&self.app.world)
+    }
+
+    pub fn board_config(&self) -> &BoardConfig {
+        self.app.world.resource::<BoardConfig>()
+    }
+
+    /// Ball, dices and score, `None` when board is not spawned
+    pub fn board(&self) -> Option<BoardState> {
+        BoardState::read(&self.app.world)
+    }
+
+    /// Overwrites ball, dices and score, e.g. to set up a scenario
+    pub fn set_board(&mut self, board: &BoardState) {
+        board.write(&mut self.app.world);
+    }
+
+    pub fn state(&self) -> GameState {
+        *self.app.world.resource::<State<GameState>>().get()
+    }
+
+    pub fn set_state(&mut self, state: GameState) {
+        self.app
+            .world
+            .resource_mut::<NextState<GameState>>()
+            .set(state);
+        self.app.world.run_schedule(StateTransition);
+    }
+
+    pub fn winner(&self) -> Option<crate::utils::DiceKind> {
+        self.app.world.resource::<LastWinner>().player()
+    }
+
+    /// Runs one simulation tick with dice inputs in -1..1 and applies state changes it requested
+    pub fn step(&mut self, left: f32, right: f32) {
+        let world = &mut self.app.world;
+        world.resource_mut::<Events<PlayerLost>>().update();
+        world.resource_mut::<Events<DiceHit>>().update();
+
+        apply_dice_inputs(world, left, right);
+        let delta = Duration::from_secs_f64(1.0 / SIMULATION_HZ);
+        world.resource_mut::<Time>().advance_by(delta);
+        world.run_schedule(SimulationStep);
+        world.run_schedule(StateTransition);
+
+        let lost_events = world.resource::<Events<PlayerLost>>();
+        self.lost = self.lost_reader.read(lost_events).cloned().collect();
+        let hit_events = world.resource::<Events<DiceHit>>();
+        self.hits = self.hit_reader.read(hit_events).cloned().collect();
+    }
+
+    /// Steps `ticks` times with same inputs, returns every `PlayerLost` sent meanwhile
+    pub fn run(&mut self, ticks: u32, left: f32, right: f32) -> Vec<PlayerLost> {
+        let mut lost = Vec::new();
+        for _ in 0..ticks {
+            self.step(left, right);
+            lost.append(&mut self.lost);
+        }
+        lost
+    }
+
+    /// `PlayerLost` events sent by the last `step`
+    pub fn lost_events(&self) -> &[PlayerLost] {
+        &self.lost
+    }
+
+    /// `DiceHit` events sent by the last `step`
+    pub fn hit_events(&self) -> &[DiceHit] {
+        &self.hits
+    }
+}
@@ -0,0 +1,572 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::asset::io::file::FileAssetReader;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::gameplay::{
+    reseed_match_rng, DiceHit, GameMode, MatchSeed, MatchSim, MatchSimSet, PlayerLost,
+    ResumedMatch, SimulationDriver, SimulationStep,
+};
+use crate::profile::Profiles;
+use crate::sim::{PongSim, SimEvent};
+use crate::theme::Theme;
+use crate::tournament::TournamentMatch;
+use crate::utils::*;
+use crate::GameState;
+
+/// Point by point record of finished local versus matches outside of tournaments, kept
+/// in `history.ron` and browsable from the main menu.
+pub struct HistoryPlugin;
+
+impl Plugin for HistoryPlugin {
+    fn build(&self, app: &mut App) {
+        let history = MatchHistory::load(&history_path());
+
+        app.insert_resource(history)
+            .insert_resource(HistoryView::default())
+            .add_systems(
+                OnEnter(GameState::Gameplay),
+                (
+                    forget_last_match,
+                    start_recording.after(reseed_match_rng).run_if(
+                        resource_equals(SimulationDriver::Local)
+                            .and_then(resource_equals(GameMode::Versus))
+                            .and_then(not(resource_exists::<ResumedMatch>()))
+                            // bracket entrants are not the active profile and a guest
+                            .and_then(not(resource_exists::<TournamentMatch>())),
+                    ),
+                ),
+            )
+            .add_systems(
+                SimulationStep,
+                record_tick.after(MatchSimSet).run_if(
+                    resource_exists::<MatchRecorder>().and_then(resource_exists::<MatchSim>()),
+                ),
+            )
+            .add_systems(
+                OnEnter(GameState::GameOver),
+                save_match.run_if(resource_exists::<MatchRecorder>()),
+            )
+            .add_systems(OnEnter(GameState::History), spawn_menu)
+            .add_systems(OnExit(GameState::History), (despawn_menu, reset_view))
+            .add_systems(
+                Update,
+                (
+                    match_button_clicked,
+                    page_button_clicked,
+                    back_button_clicked,
+                    (despawn_menu, spawn_menu).chain().run_if(
+                        in_state(GameState::History).and_then(
+                            resource_changed::<HistoryView>().or_else(resource_changed::<Theme>()),
+                        ),
+                    ),
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// history file, relative to asset base path
+const HISTORY_FILE: &str = "history.ron";
+/// oldest matches are dropped beyond this
+const MAX_MATCHES: usize = 100;
+/// matches listed on one page of history screen
+const PAGE_SIZE: usize = 8;
+
+fn history_path() -> PathBuf {
+    FileAssetReader::get_base_path().join(HISTORY_FILE)
+}
+
+/// One point from serve until the ball crossed a goal line
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PointRecord {
+    pub scorer: DiceKind,
+    /// dice hits during the rally
+    pub rally_hits: u32,
+    /// secs from serve until the point
+    pub rally_secs: f32,
+    /// side the ball was served towards
+    pub serve_to: DiceKind,
+    /// serve angle above horizontal axis in radians, negative downwards
+    pub serve_angle: f32,
+    /// in world units per sec when the point was decided
+    pub final_speed: f32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct MatchRecord {
+    /// secs since unix epoch
+    pub finished_at: u64,
+    pub seed: u64,
+    /// as (left, right)
+    pub names: (String, String),
+    pub winner: Option<DiceKind>,
+    /// game time including serve delays
+    pub duration_secs: f32,
+    pub points: Vec<PointRecord>,
+    /// dice hits as (left, right)
+    #[serde(default)]
+    pub hits: (u32, u32),
+    /// summed offsets of hits from dice centre as (left, right), 0 at the
+    /// centre and 1 at the edge
+    #[serde(default)]
+    pub hit_offsets: (f32, f32),
+}
+
+impl MatchRecord {
+    /// `(left, right)` points
+    pub fn score(&self) -> (usize, usize) {
+        let left = self
+            .points
+            .iter()
+            .filter(|point| point.scorer == DiceKind::Left)
+            .count();
+        (left, self.points.len() - left)
+    }
+
+    /// Most dice hits within one point
+    pub fn longest_rally(&self) -> u32 {
+        self.points
+            .iter()
+            .map(|point| point.rally_hits)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Mean offset of `kind` dice hits from its centre, `None` without hits
+    pub fn average_hit_offset(&self, kind: DiceKind) -> Option<f32> {
+        let (hits, offsets) = match kind {
+            DiceKind::Left => (self.hits.0, self.hit_offsets.0),
+            DiceKind::Right => (self.hits.1, self.hit_offsets.1),
+        };
+        (hits > 0).then(|| offsets / hits as f32)
+    }
+}
+
+/// Builds `MatchRecord` by observing the simulation after every step
+#[derive(Clone, Debug, Default, Resource, Serialize, Deserialize)]
+pub struct MatchRecorder {
+    pub record: MatchRecord,
+    hits: u32,
+    rally_secs: f32,
+    /// `(towards, angle)` of the serve of the rally in play
+    serve: Option<(DiceKind, f32)>,
+    /// ball speed of the last step, ball is already reset once the point is known
+    speed: f32,
+}
+
+impl MatchRecorder {
+    pub fn new(seed: u64, names: (String, String)) -> Self {
+        Self {
+            record: MatchRecord {
+                seed,
+                names,
+                ..MatchRecord::default()
+            },
+            ..Self::default()
+        }
+    }
+
+    /// Takes in one step of `dt` secs which ended in `sim` with `events`
+    pub fn observe(&mut self, sim: &PongSim, dt: f32, events: &[SimEvent]) {
+        self.record.duration_secs += dt;
+        let mut scorer = None;
+        for event in events {
+            match *event {
+                SimEvent::DiceHit { kind, .. } => {
+                    self.hits += 1;
+                    let offset = sim.ball.hit_offset.unwrap_or(0.).abs();
+                    let record = &mut self.record;
+                    match kind {
+                        DiceKind::Left => {
+                            record.hits.0 += 1;
+                            record.hit_offsets.0 += offset;
+                        }
+                        DiceKind::Right => {
+                            record.hits.1 += 1;
+                            record.hit_offsets.1 += offset;
+                        }
+                    }
+                }
+                SimEvent::PlayerLost { is_right } => {
+                    scorer = Some(if is_right {
+                        DiceKind::Left
+                    } else {
+                        DiceKind::Right
+                    });
+                }
+                SimEvent::TargetHit { .. }
+                | SimEvent::BrickHit { .. }
+                | SimEvent::BricksCleared => {}
+            }
+        }
+
+        let ball = &sim.ball;
+        if let Some(scorer) = scorer {
+            let (serve_to, serve_angle) = self.serve.take().unwrap_or((scorer.opponent(), 0.));
+            self.record.points.push(PointRecord {
+                scorer,
+                rally_hits: std::mem::take(&mut self.hits),
+                rally_secs: std::mem::take(&mut self.rally_secs) + dt,
+                serve_to,
+                serve_angle,
+                final_speed: self.speed,
+            });
+            self.record.winner = sim.winner();
+        } else if ball.serve_delay.is_none() {
+            if self.serve.is_none() {
+                let towards = if ball.velocity_x < 0. {
+                    DiceKind::Left
+                } else {
+                    DiceKind::Right
+                };
+                let angle = ball.velocity_y.atan2(ball.velocity_x.abs());
+                self.serve = Some((towards, angle));
+            } else {
+                self.rally_secs += dt;
+            }
+            let velocity = Vec2::new(ball.velocity_x, ball.velocity_y);
+            self.speed = velocity.length() * sim.board.ball_speed() * sim.ball_speed_factor;
+        }
+    }
+}
+
+/// Finished matches, newest last
+#[derive(Clone, Debug, Default, PartialEq, Resource, Serialize, Deserialize)]
+pub struct MatchHistory {
+    matches: Vec<MatchRecord>,
+}
+
+impl MatchHistory {
+    /// Reads `path`, missing or broken file gives empty history
+    pub fn load(path: &Path) -> Self {
+        if !path.exists() {
+            return Self::default();
+        }
+        match Self::read(path) {
+            Ok(history) => history,
+            Err(e) => {
+                warn!("failed to load match history {}: {}", path.display(), e);
+                Self::default()
+            }
+        }
+    }
+
+    fn read(path: &Path) -> Result<Self, String> {
+        let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        ron::from_str(&content).map_err(|e| e.to_string())
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let content = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|e| e.to_string())?;
+        fs::write(path, content).map_err(|e| e.to_string())
+    }
+
+    pub fn matches(&self) -> &[MatchRecord] {
+        &self.matches
+    }
+
+    /// Appends `record`, dropping the oldest beyond `MAX_MATCHES`
+    pub fn push(&mut self, record: MatchRecord) {
+        self.matches.push(record);
+        let excess = self.matches.len().saturating_sub(MAX_MATCHES);
+        self.matches.drain(..excess);
+    }
+}
+
+fn start_recording(mut commands: Commands, seed: Res<MatchSeed>, profiles: Res<Profiles>) {
+    let profile = profiles.active();
+    let names = match profile.side {
+        DiceKind::Left => (profile.name.clone(), "Guest".to_string()),
+        DiceKind::Right => ("Guest".to_string(), profile.name.clone()),
+    };
+    commands.insert_resource(MatchRecorder::new(seed.0, names));
+}
+
+fn record_tick(
+    time: Res<Time>,
+    match_sim: Res<MatchSim>,
+    mut recorder: ResMut<MatchRecorder>,
+    mut hits: EventReader<DiceHit>,
+    mut lost: EventReader<PlayerLost>,
+) {
+    let mut events: Vec<SimEvent> = hits
+        .read()
+        .map(|hit| SimEvent::DiceHit {
+            kind: hit.kind,
+            bounce_angle: hit.bounce_angle,
+        })
+        .collect();
+    events.extend(lost.read().map(|lost| SimEvent::PlayerLost {
+        is_right: lost.is_right,
+    }));
+    let dt = time.delta().as_secs_f32();
+    recorder.observe(&match_sim.0, dt, &events);
+}
+
+/// Record of the match that just ended, only present after local matches
+#[derive(Resource)]
+pub struct LastMatch(pub MatchRecord);
+
+/// Only local matches are recorded, others must not show summary of an older one
+fn forget_last_match(mut commands: Commands) {
+    commands.remove_resource::<LastMatch>();
+}
+
+/// Stores finished match in history and keeps it as `LastMatch`
+pub fn save_match(
+    mut commands: Commands,
+    recorder: Res<MatchRecorder>,
+    mut history: ResMut<MatchHistory>,
+) {
+    commands.remove_resource::<MatchRecorder>();
+    let mut record = recorder.record.clone();
+    record.finished_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    history.push(record.clone());
+    commands.insert_resource(LastMatch(record));
+
+    let path = history_path();
+    if let Err(e) = history.save(&path) {
+        error!("failed to save match history {}: {}", path.display(), e);
+    }
+}
+
+/// What history screen shows
+#[derive(Clone, Default, PartialEq, Resource)]
+struct HistoryView {
+    /// page of the match list, 0 is the newest
+    page: usize,
+    /// index into `MatchHistory` of the match broken down by point
+    opened: Option<usize>,
+}
+
+#[derive(Component)]
+pub struct HistoryUIRoot;
+
+/// Opens breakdown of the match at this index of `MatchHistory`
+#[derive(Component)]
+pub struct MatchButton(usize);
+
+#[derive(Clone, Copy, Component)]
+pub enum PageButton {
+    Newer,
+    Older,
+}
+
+#[derive(Component)]
+pub struct BackButton;
+
+/// "5 min ago" style age of a unix timestamp
+fn format_age(timestamp: u64) -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    let secs = now.saturating_sub(timestamp);
+    match secs {
+        0..=59 => "just now".to_string(),
+        60..=3599 => format!("{} min ago", secs / 60),
+        3600..=86399 => format!("{} h ago", secs / 3600),
+        _ => format!("{} days ago", secs / 86400),
+    }
+}
+
+fn match_label(record: &MatchRecord) -> String {
+    let (left, right) = record.score();
+    format!(
+        "{} {}:{} {}, {}",
+        record.names.0,
+        left,
+        right,
+        record.names.1,
+        format_age(record.finished_at)
+    )
+}
+
+fn point_lines(record: &MatchRecord) -> String {
+    let mut score = (0, 0);
+    let lines: Vec<String> = record
+        .points
+        .iter()
+        .map(|point| {
+            match point.scorer {
+                DiceKind::Left => score.0 += 1,
+                DiceKind::Right => score.1 += 1,
+            }
+            format!(
+                "{}:{}  {:?} scored, {} hits in {:.1}s, served {:?} at {:.0}°, ball {:.0}",
+                score.0,
+                score.1,
+                point.scorer,
+                point.rally_hits,
+                point.rally_secs,
+                point.serve_to,
+                point.serve_angle.to_degrees(),
+                point.final_speed,
+            )
+        })
+        .collect();
+    lines.join("\n")
+}
+
+fn spawn_menu(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    theme: Res<Theme>,
+    history: Res<MatchHistory>,
+    view: Res<HistoryView>,
+) {
+    let text_style = |font_size: f32| TextStyle {
+        font: asset_server.load(FONT_PATH),
+        font_size: font_size * theme.font_scale,
+        color: theme.text,
+    };
+
+    let opened = view.opened.and_then(|index| history.matches.get(index));
+    let (title, content) = match opened {
+        Some(record) => (match_label(record), point_lines(record)),
+        None if history.matches.is_empty() => {
+            ("HISTORY".to_string(), "No matches played yet".to_string())
+        }
+        None => ("HISTORY".to_string(), String::new()),
+    };
+
+    let mut buttons = Vec::new();
+    if opened.is_none() {
+        // newest first
+        let newest = history.matches.len().saturating_sub(view.page * PAGE_SIZE);
+        let oldest = newest.saturating_sub(PAGE_SIZE);
+        for index in (oldest..newest).rev() {
+            let button = spawn_sized_button(
+                &mut commands,
+                &asset_server,
+                &theme,
+                &match_label(&history.matches[index]),
+                theme.button,
+                ButtonSize::Compact,
+            );
+            commands.entity(button).insert(MatchButton(index));
+            buttons.push(button);
+        }
+        let pages = [
+            (PageButton::Newer, "Newer", view.page > 0),
+            (PageButton::Older, "Older", oldest > 0),
+        ];
+        for (page_button, label, _) in pages.into_iter().filter(|(_, _, shown)| *shown) {
+            let button = spawn_sized_button(
+                &mut commands,
+                &asset_server,
+                &theme,
+                label,
+                theme.button,
+                ButtonSize::Compact,
+            );
+            commands.entity(button).insert(page_button);
+            buttons.push(button);
+        }
+    }
+
+    let back_button = spawn_sized_button(
+        &mut commands,
+        &asset_server,
+        &theme,
+        "Back",
+        theme.button_secondary,
+        ButtonSize::Compact,
+    );
+    commands.entity(back_button).insert(BackButton);
+    buttons.push(back_button);
+
+    let title_size = if opened.is_some() { 48.0 } else { 96.0 };
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            ..default()
+        })
+        .insert(HistoryUIRoot)
+        .with_children(|commands| {
+            commands.spawn(TextBundle {
+                style: Style {
+                    align_self: AlignSelf::Center,
+                    margin: UiRect::all(Val::Percent(2.0)),
+                    ..default()
+                },
+                text: Text::from_section(title, text_style(title_size)),
+                ..default()
+            });
+            if !content.is_empty() {
+                commands.spawn(TextBundle {
+                    style: Style {
+                        align_self: AlignSelf::Center,
+                        margin: UiRect::all(Val::Percent(1.0)),
+                        ..default()
+                    },
+                    text: Text::from_section(content, text_style(20.0)),
+                    ..default()
+                });
+            }
+        })
+        .push_children(&buttons);
+}
+
+fn despawn_menu(mut commands: Commands, menu_root: Query<Entity, With<HistoryUIRoot>>) {
+    let root_entity = menu_root.single();
+    commands.entity(root_entity).despawn_recursive();
+}
+
+fn reset_view(mut view: ResMut<HistoryView>) {
+    view.set_if_neq(HistoryView::default());
+}
+
+fn match_button_clicked(
+    interactions: Query<(&Interaction, &MatchButton), Changed<Interaction>>,
+    mut view: ResMut<HistoryView>,
+) {
+    for (interaction, button) in &interactions {
+        if matches!(interaction, Interaction::Pressed) {
+            view.opened = Some(button.0);
+        }
+    }
+}
+
+fn page_button_clicked(
+    interactions: Query<(&Interaction, &PageButton), Changed<Interaction>>,
+    mut view: ResMut<HistoryView>,
+) {
+    for (interaction, button) in &interactions {
+        if matches!(interaction, Interaction::Pressed) {
+            match button {
+                PageButton::Newer => view.page = view.page.saturating_sub(1),
+                PageButton::Older => view.page += 1,
+            }
+        }
+    }
+}
+
+/// Leaves breakdown back to the list, or the list back to main menu
+fn back_button_clicked(
+    interactions: Query<&Interaction, (With<BackButton>, Changed<Interaction>)>,
+    mut view: ResMut<HistoryView>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for interaction in &interactions {
+        if matches!(interaction, Interaction::Pressed) {
+            if view.opened.is_some() {
+                view.opened = None;
+            } else {
+                next_state.set(GameState::MainMenu);
+            }
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use bevy::prelude::*;
+
+use crate::utils::DiceKind;
+
+pub mod accessibility;
+pub mod ai;
+pub mod breakout;
+pub mod camera_fx;
+pub mod env;
+pub mod forces;
+pub mod game_over;
+pub mod gameplay;
+pub mod harness;
+pub mod history;
+pub mod lobby;
+pub mod main_menu;
+pub mod netplay;
+pub mod practice;
+pub mod profile;
+pub mod protocol;
+pub mod remote;
+pub mod replay;
+pub mod resume;
+pub mod server;
+pub mod settings_menu;
+pub mod sim;
+pub mod survival;
+pub mod targets;
+pub mod text_input;
+pub mod theme;
+pub mod tournament;
+pub mod utils;
+
+#[derive(Debug, Clone, Copy, Eq, Default, PartialEq, Hash, States)]
+pub enum GameState {
+    #[default]
+    MainMenu,
+    Settings,
+    /// waiting for another player on the local network
+    Host,
+    /// browsing games hosted on the local network
+    Join,
+    Gameplay,
+    GameOver,
+    /// profiles and their lifetime stats
+    Stats,
+    /// list of finished matches
+    History,
+    /// entering players of a new tournament
+    TournamentSetup,
+    /// tournament bracket between its matches
+    Bracket,
+    /// choosing drill and opponent of a practice
+    Practice,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Resource)]
+pub struct LastWinner {
+    player: Option<DiceKind>,
+}
+
+impl LastWinner {
+    pub fn player(&self) -> Option<DiceKind> {
+        self.player
+    }
+}
@@ -0,0 +1,724 @@
+use std::io::ErrorKind;
+use std::net::{Ipv4Addr, SocketAddr, UdpSocket};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::forces::check_forces;
+use crate::gameplay::{BoardConfig, Forces, SeedSequence, SimulationDriver, TargetLayout};
+use crate::netplay::{is_hello_packet, NetSession};
+use crate::protocol::{decode, encode, sanitize_name, MAX_PACKET_SIZE};
+use crate::targets::check_layout;
+use crate::theme::Theme;
+use crate::utils::*;
+use crate::GameState;
+
+/// Host and Join screens for matches on the local network.
+///
+/// Host announces its game by UDP broadcast, joiner lists announced games with ping.
+/// Once host's `BoardConfig` is accepted both sides continue as netplay peers
+/// over the socket used for the handshake.
+pub struct LobbyPlugin;
+
+impl Plugin for LobbyPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(PlayerName::from_env())
+            .add_systems(OnEnter(GameState::Host), (start_hosting, spawn_host_menu))
+            .add_systems(OnExit(GameState::Host), (stop_hosting, despawn_menu))
+            .add_systems(OnEnter(GameState::Join), (start_browsing, spawn_join_menu))
+            .add_systems(OnExit(GameState::Join), (stop_browsing, despawn_menu))
+            .add_systems(
+                Update,
+                (host_lobby, update_host_status)
+                    .chain()
+                    .run_if(in_state(GameState::Host).and_then(resource_exists::<LobbyHost>())),
+            )
+            .add_systems(
+                Update,
+                (browse_lobbies, update_lobby_list)
+                    .chain()
+                    .run_if(in_state(GameState::Join).and_then(resource_exists::<LobbyBrowser>())),
+            )
+            .add_systems(
+                Update,
+                (
+                    host_button_clicked,
+                    lobby_button_clicked,
+                    back_button_clicked,
+                ),
+            );
+    }
+}
+
+/// port joiners listen on for lobby announcements
+const DISCOVERY_PORT: u16 = 7101;
+/// bumped whenever lobby or netplay packets change, other versions are not listed
+const LOBBY_VERSION: u16 = 3;
+/// first byte of every lobby packet, keeps them apart from netplay packets
+const LOBBY_PACKET: u8 = 0x4C;
+/// secs between announcements and handshake retries
+const ANNOUNCE_INTERVAL: f32 = 0.5;
+/// secs between pings of listed lobbies
+const PING_INTERVAL: f32 = 1.0;
+/// secs without announcement after which lobby disappears from list
+const LOBBY_TIMEOUT: f32 = 3.0;
+
+/// Name shown to other players, taken from `--name <name>` or the OS user
+#[derive(Clone, Debug, Resource)]
+pub struct PlayerName(pub String);
+
+impl PlayerName {
+    fn from_env() -> Self {
+        let args: Vec<String> = std::env::args().collect();
+        let name = args
+            .iter()
+            .position(|arg| arg == "--name")
+            .and_then(|position| args.get(position + 1).cloned())
+            .or_else(|| std::env::var("USER").ok())
+            .or_else(|| std::env::var("USERNAME").ok())
+            .map(|name| sanitize_name(&name))
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| "Player".to_string());
+        Self(name)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+enum LobbyMessage {
+    /// broadcast by host, sender address is where to join
+    Announce {
+        version: u16,
+        name: String,
+    },
+    /// `sent_at` is echoed back to measure round trip
+    Ping {
+        sent_at: f64,
+    },
+    Pong {
+        sent_at: f64,
+    },
+    Join {
+        name: String,
+    },
+    /// host's board, targets and forces are used by both sides, joiner confirms by starting netplay
+    Accept {
+        board: BoardConfig,
+        targets: TargetLayout,
+        forces: Forces,
+    },
+    /// somebody else is already joining
+    Busy,
+}
+
+fn send_message(socket: &UdpSocket, addr: SocketAddr, message: &LobbyMessage) {
+    let mut packet = vec![LOBBY_PACKET];
+    packet.extend(encode(message));
+    if let Err(e) = socket.send_to(&packet, addr) {
+        debug!("lobby send to {} failed: {}", addr, e);
+    }
+}
+
+/// Reads one packet, `None` when nothing is pending
+fn receive_packet(
+    socket: &UdpSocket,
+    buf: &mut [u8; MAX_PACKET_SIZE],
+) -> Option<(usize, SocketAddr)> {
+    loop {
+        match socket.recv_from(buf) {
+            Ok(received) => return Some(received),
+            Err(e) if e.kind() == ErrorKind::WouldBlock => return None,
+            // e.g. ICMP port unreachable of a peer that went away
+            Err(e) => debug!("lobby receive failed: {}", e),
+        }
+    }
+}
+
+fn parse_message(packet: &[u8]) -> Option<LobbyMessage> {
+    match packet.split_first() {
+        Some((&LOBBY_PACKET, message)) => decode(message),
+        _ => None,
+    }
+}
+
+fn start_netplay(
+    commands: &mut Commands,
+    next_state: &mut NextState<GameState>,
+    socket: &UdpSocket,
+    peer: SocketAddr,
+    side: DiceKind,
+    seed: u64,
+) {
+    let session = socket
+        .try_clone()
+        .and_then(|socket| NetSession::from_socket(socket, peer, side, seed));
+    match session {
+        Ok(session) => {
+            info!("netplay as {:?} player, peer {}", side, peer);
+            commands.insert_resource(session);
+            commands.insert_resource(SimulationDriver::External);
+            next_state.set(GameState::Gameplay);
+        }
+        Err(e) => error!("failed to start netplay: {}", e),
+    }
+}
+
+struct Joiner {
+    addr: SocketAddr,
+    name: String,
+    since_last_packet: f32,
+}
+
+#[derive(Resource)]
+struct LobbyHost {
+    socket: UdpSocket,
+    joiner: Option<Joiner>,
+    since_announce: f32,
+}
+
+fn start_hosting(mut commands: Commands) {
+    let host = UdpSocket::bind(("0.0.0.0", 0)).and_then(|socket| {
+        socket.set_nonblocking(true)?;
+        socket.set_broadcast(true)?;
+        Ok(LobbyHost {
+            socket,
+            joiner: None,
+            since_announce: ANNOUNCE_INTERVAL,
+        })
+    });
+    match host {
+        Ok(host) => commands.insert_resource(host),
+        Err(e) => error!("failed to host game: {}", e),
+    }
+}
+
+fn stop_hosting(mut commands: Commands) {
+    commands.remove_resource::<LobbyHost>();
+}
+
+#[allow(clippy::too_many_arguments)]
+fn host_lobby(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    name: Res<PlayerName>,
+    board: Res<BoardConfig>,
+    targets: Res<TargetLayout>,
+    forces: Res<Forces>,
+    mut host: ResMut<LobbyHost>,
+    mut seeds: ResMut<SeedSequence>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let dt = time.delta_seconds();
+    let host = &mut *host;
+    host.since_announce += dt;
+    let resend = host.since_announce >= ANNOUNCE_INTERVAL;
+    if resend {
+        host.since_announce = 0.0;
+        let announce = LobbyMessage::Announce {
+            version: LOBBY_VERSION,
+            name: name.0.clone(),
+        };
+        // loopback as well, broadcast does not reach a joiner on this machine everywhere
+        for ip in [Ipv4Addr::BROADCAST, Ipv4Addr::LOCALHOST] {
+            send_message(&host.socket, (ip, DISCOVERY_PORT).into(), &announce);
+        }
+    }
+    if let Some(joiner) = &mut host.joiner {
+        joiner.since_last_packet += dt;
+        if joiner.since_last_packet > LOBBY_TIMEOUT {
+            info!("{} did not finish joining", joiner.name);
+            host.joiner = None;
+        } else if resend {
+            let accept = LobbyMessage::Accept {
+                board: board.clone(),
+                targets: targets.clone(),
+                forces: forces.clone(),
+            };
+            send_message(&host.socket, joiner.addr, &accept);
+        }
+    }
+
+    let mut buf = [0u8; MAX_PACKET_SIZE];
+    while let Some((len, from)) = receive_packet(&host.socket, &mut buf) {
+        let packet = &buf[..len];
+        let from_joiner = host.joiner.as_mut().filter(|joiner| joiner.addr == from);
+        if let Some(joiner) = from_joiner {
+            joiner.since_last_packet = 0.0;
+            // joiner got the board and already runs its session
+            if is_hello_packet(packet) {
+                start_netplay(
+                    &mut commands,
+                    &mut next_state,
+                    &host.socket,
+                    from,
+                    DiceKind::Left,
+                    seeds.next_seed(),
+                );
+                return;
+            }
+        }
+        match parse_message(packet) {
+            Some(LobbyMessage::Ping { sent_at }) => {
+                send_message(&host.socket, from, &LobbyMessage::Pong { sent_at });
+            }
+            Some(LobbyMessage::Join { name }) => {
+                if host
+                    .joiner
+                    .as_ref()
+                    .is_some_and(|joiner| joiner.addr != from)
+                {
+                    send_message(&host.socket, from, &LobbyMessage::Busy);
+                    continue;
+                }
+                if host.joiner.is_none() {
+                    let name = sanitize_name(&name);
+                    info!("{} is joining from {}", name, from);
+                    host.joiner = Some(Joiner {
+                        addr: from,
+                        name,
+                        since_last_packet: 0.0,
+                    });
+                }
+                let accept = LobbyMessage::Accept {
+                    board: board.clone(),
+                    targets: targets.clone(),
+                    forces: forces.clone(),
+                };
+                send_message(&host.socket, from, &accept);
+            }
+            _ => {}
+        }
+    }
+}
+
+#[derive(Clone, PartialEq)]
+struct DiscoveredLobby {
+    addr: SocketAddr,
+    name: String,
+    /// round trip in millis, `None` until first pong
+    ping_ms: Option<u32>,
+    since_seen: f32,
+}
+
+impl DiscoveredLobby {
+    fn label(&self) -> String {
+        match self.ping_ms {
+            Some(ping) => format!("{} {}ms", self.name, ping),
+            None => format!("{} ...", self.name),
+        }
+    }
+}
+
+#[derive(Resource)]
+struct LobbyBrowser {
+    /// `None` when discovery port is taken, e.g. by another joiner on this machine
+    discovery: Option<UdpSocket>,
+    socket: UdpSocket,
+    lobbies: Vec<DiscoveredLobby>,
+    /// lobby that was asked to join
+    joining: Option<SocketAddr>,
+    /// set when host refused
+    rejected_by: Option<String>,
+    since_ping: f32,
+    since_join: f32,
+}
+
+impl LobbyBrowser {
+    fn status(&self) -> String {
+        if let Some(addr) = self.joining {
+            let name = self.lobbies.iter().find(|lobby| lobby.addr == addr);
+            return format!("Joining {}", name.map_or("game", |lobby| &lobby.name));
+        }
+        if let Some(host) = &self.rejected_by {
+            return format!("{} is busy", host);
+        }
+        if self.discovery.is_none() {
+            return "Can not search games".to_string();
+        }
+        if self.lobbies.is_empty() {
+            "Searching games".to_string()
+        } else {
+            "Select game".to_string()
+        }
+    }
+}
+
+fn start_browsing(mut commands: Commands) {
+    let discovery = UdpSocket::bind(("0.0.0.0", DISCOVERY_PORT)).and_then(|socket| {
+        socket.set_nonblocking(true)?;
+        Ok(socket)
+    });
+    let discovery = match discovery {
+        Ok(socket) => Some(socket),
+        Err(e) => {
+            warn!(
+                "failed to listen for games on port {}: {}",
+                DISCOVERY_PORT, e
+            );
+            None
+        }
+    };
+    let socket = UdpSocket::bind(("0.0.0.0", 0)).and_then(|socket| {
+        socket.set_nonblocking(true)?;
+        Ok(socket)
+    });
+    match socket {
+        Ok(socket) => commands.insert_resource(LobbyBrowser {
+            discovery,
+            socket,
+            lobbies: Vec::new(),
+            joining: None,
+            rejected_by: None,
+            since_ping: PING_INTERVAL,
+            since_join: 0.0,
+        }),
+        Err(e) => error!("failed to browse games: {}", e),
+    }
+}
+
+fn stop_browsing(mut commands: Commands) {
+    commands.remove_resource::<LobbyBrowser>();
+}
+
+fn browse_lobbies(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    name: Res<PlayerName>,
+    mut browser: ResMut<LobbyBrowser>,
+    mut seeds: ResMut<SeedSequence>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let dt = time.delta_seconds();
+    let now = time.elapsed_seconds_f64();
+    let browser = &mut *browser;
+    let mut buf = [0u8; MAX_PACKET_SIZE];
+
+    let mut announced = Vec::new();
+    if let Some(discovery) = &browser.discovery {
+        while let Some((len, from)) = receive_packet(discovery, &mut buf) {
+            if let Some(LobbyMessage::Announce { version, name }) = parse_message(&buf[..len]) {
+                if version == LOBBY_VERSION {
+                    announced.push((from, sanitize_name(&name)));
+                }
+            }
+        }
+    }
+    for (addr, name) in announced {
+        match browser.lobbies.iter_mut().find(|lobby| lobby.addr == addr) {
+            Some(lobby) => lobby.since_seen = 0.0,
+            None => browser.lobbies.push(DiscoveredLobby {
+                addr,
+                name,
+                ping_ms: None,
+                since_seen: 0.0,
+            }),
+        }
+    }
+    for lobby in &mut browser.lobbies {
+        lobby.since_seen += dt;
+    }
+    browser
+        .lobbies
+        .retain(|lobby| lobby.since_seen <= LOBBY_TIMEOUT);
+
+    while let Some((len, from)) = receive_packet(&browser.socket, &mut buf) {
+        match parse_message(&buf[..len]) {
+            Some(LobbyMessage::Pong { sent_at }) => {
+                if let Some(lobby) = browser.lobbies.iter_mut().find(|l| l.addr == from) {
+                    lobby.ping_ms = Some(((now - sent_at) * 1000.0).round() as u32);
+                }
+            }
+            Some(LobbyMessage::Accept {
+                board,
+                targets,
+                forces,
+            }) if browser.joining == Some(from) => {
+                // host's targets and forces are off or one of its presets, anything else is garbage
+                let checked_targets = if targets.zones.is_empty() {
+                    Ok(())
+                } else {
+                    check_layout(&targets)
+                };
+                let checked_forces = if forces.is_calm() {
+                    Ok(())
+                } else {
+                    check_forces(&forces)
+                };
+                let checked = board.check().and(checked_targets).and(checked_forces);
+                if let Err(e) = checked {
+                    warn!("ignoring lobby accept from {}: {}", from, e);
+                    continue;
+                }
+                commands.insert_resource(board);
+                commands.insert_resource(targets);
+                commands.insert_resource(forces);
+                start_netplay(
+                    &mut commands,
+                    &mut next_state,
+                    &browser.socket,
+                    from,
+                    DiceKind::Right,
+                    // replaced by host's seed in netplay handshake
+                    seeds.next_seed(),
+                );
+                return;
+            }
+            Some(LobbyMessage::Busy) if browser.joining == Some(from) => {
+                let host = browser.lobbies.iter().find(|lobby| lobby.addr == from);
+                browser.rejected_by = Some(host.map_or("Game".to_string(), |l| l.name.clone()));
+                browser.joining = None;
+            }
+            _ => {}
+        }
+    }
+
+    browser.since_ping += dt;
+    if browser.since_ping >= PING_INTERVAL {
+        browser.since_ping = 0.0;
+        for lobby in &browser.lobbies {
+            send_message(
+                &browser.socket,
+                lobby.addr,
+                &LobbyMessage::Ping { sent_at: now },
+            );
+        }
+    }
+    if let Some(addr) = browser.joining {
+        browser.since_join += dt;
+        if browser.since_join >= ANNOUNCE_INTERVAL {
+            browser.since_join = 0.0;
+            let join = LobbyMessage::Join {
+                name: name.0.clone(),
+            };
+            send_message(&browser.socket, addr, &join);
+        }
+    }
+}
+
+#[derive(Component)]
+struct LobbyUIRoot;
+
+#[derive(Component)]
+struct LobbyStatusText;
+
+/// Container of discovered lobby buttons
+#[derive(Component)]
+struct LobbyList {
+    /// labels currently shown, list is rebuilt when they change
+    labels: Vec<String>,
+}
+
+#[derive(Component)]
+struct LobbyButton(SocketAddr);
+
+#[derive(Component)]
+struct HostButton;
+
+#[derive(Component)]
+struct BackButton;
+
+fn spawn_lobby_root(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    theme: &Theme,
+    title: &str,
+) -> Entity {
+    let text_style = |font_size: f32| TextStyle {
+        font: asset_server.load(FONT_PATH),
+        font_size: font_size * theme.font_scale,
+        color: theme.text,
+    };
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            ..default()
+        })
+        .insert(LobbyUIRoot)
+        .with_children(|commands| {
+            commands.spawn(TextBundle {
+                style: Style {
+                    align_self: AlignSelf::Center,
+                    margin: UiRect::all(Val::Percent(3.0)),
+                    ..default()
+                },
+                text: Text::from_section(title, text_style(96.0)),
+                ..default()
+            });
+            commands.spawn((
+                TextBundle {
+                    style: Style {
+                        align_self: AlignSelf::Center,
+                        margin: UiRect::all(Val::Percent(2.0)),
+                        ..default()
+                    },
+                    text: Text::from_section("", text_style(40.0)),
+                    ..default()
+                },
+                LobbyStatusText,
+            ));
+        })
+        .id()
+}
+
+fn spawn_back_button(commands: &mut Commands, asset_server: &AssetServer, theme: &Theme) -> Entity {
+    let back_button = spawn_sized_button(
+        commands,
+        asset_server,
+        theme,
+        "Back",
+        theme.button_secondary,
+        ButtonSize::Compact,
+    );
+    commands.entity(back_button).insert(BackButton);
+    back_button
+}
+
+fn spawn_host_menu(mut commands: Commands, asset_server: Res<AssetServer>, theme: Res<Theme>) {
+    let root = spawn_lobby_root(&mut commands, &asset_server, &theme, "HOST");
+    let back_button = spawn_back_button(&mut commands, &asset_server, &theme);
+    commands.entity(root).add_child(back_button);
+}
+
+fn spawn_join_menu(mut commands: Commands, asset_server: Res<AssetServer>, theme: Res<Theme>) {
+    let root = spawn_lobby_root(&mut commands, &asset_server, &theme, "JOIN");
+    let host_button = spawn_sized_button(
+        &mut commands,
+        &asset_server,
+        &theme,
+        "Host Game",
+        theme.button_primary,
+        ButtonSize::Compact,
+    );
+    commands.entity(host_button).insert(HostButton);
+    let list = commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            ..default()
+        })
+        .insert(LobbyList { labels: Vec::new() })
+        .id();
+    let back_button = spawn_back_button(&mut commands, &asset_server, &theme);
+    commands
+        .entity(root)
+        .add_child(host_button)
+        .add_child(list)
+        .add_child(back_button);
+}
+
+fn despawn_menu(mut commands: Commands, menu_root: Query<Entity, With<LobbyUIRoot>>) {
+    let root_entity = menu_root.single();
+    commands.entity(root_entity).despawn_recursive();
+}
+
+fn set_status(status_texts: &mut Query<&mut Text, With<LobbyStatusText>>, status: String) {
+    if let Ok(mut text) = status_texts.get_single_mut() {
+        if text.sections[0].value != status {
+            text.sections[0].value = status;
+        }
+    }
+}
+
+fn update_host_status(
+    name: Res<PlayerName>,
+    host: Res<LobbyHost>,
+    mut status_texts: Query<&mut Text, With<LobbyStatusText>>,
+) {
+    let status = match &host.joiner {
+        Some(joiner) => format!("{} is joining", joiner.name),
+        None => format!("{} waits for player", name.0),
+    };
+    set_status(&mut status_texts, status);
+}
+
+fn update_lobby_list(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    theme: Res<Theme>,
+    browser: Res<LobbyBrowser>,
+    mut lists: Query<(Entity, &mut LobbyList)>,
+    mut status_texts: Query<&mut Text, With<LobbyStatusText>>,
+) {
+    set_status(&mut status_texts, browser.status());
+
+    let Ok((list_entity, mut list)) = lists.get_single_mut() else {
+        return;
+    };
+    let labels: Vec<String> = browser.lobbies.iter().map(|lobby| lobby.label()).collect();
+    if list.labels == labels {
+        return;
+    }
+    let buttons: Vec<Entity> = browser
+        .lobbies
+        .iter()
+        .map(|lobby| {
+            let button = spawn_sized_button(
+                &mut commands,
+                &asset_server,
+                &theme,
+                &lobby.label(),
+                theme.button,
+                ButtonSize::Compact,
+            );
+            commands.entity(button).insert(LobbyButton(lobby.addr));
+            button
+        })
+        .collect();
+    commands
+        .entity(list_entity)
+        .despawn_descendants()
+        .push_children(&buttons);
+    list.labels = labels;
+}
+
+fn host_button_clicked(
+    interactions: Query<&Interaction, (With<HostButton>, Changed<Interaction>)>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for interaction in &interactions {
+        if matches!(interaction, Interaction::Pressed) {
+            next_state.set(GameState::Host);
+        }
+    }
+}
+
+fn lobby_button_clicked(
+    interactions: Query<(&Interaction, &LobbyButton), Changed<Interaction>>,
+    browser: Option<ResMut<LobbyBrowser>>,
+) {
+    let Some(mut browser) = browser else {
+        return;
+    };
+    for (interaction, lobby) in &interactions {
+        if matches!(interaction, Interaction::Pressed) && browser.joining.is_none() {
+            browser.joining = Some(lobby.0);
+            browser.rejected_by = None;
+            // send right away
+            browser.since_join = ANNOUNCE_INTERVAL;
+        }
+    }
+}
+
+fn back_button_clicked(
+    interactions: Query<&Interaction, (With<BackButton>, Changed<Interaction>)>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for interaction in &interactions {
+        if matches!(interaction, Interaction::Pressed) {
+            let previous = match state.get() {
+                GameState::Host => GameState::Join,
+                _ => GameState::MainMenu,
+            };
+            next_state.set(previous);
+        }
+    }
+}
@@ -1,11 +1,32 @@
 use bevy::prelude::*;
+use bevy::render::camera::ScalingMode;
 use bevy::render::settings::{Backends, RenderCreation, WgpuSettings};
 use bevy::render::RenderPlugin;
+use bevy::window::{PrimaryWindow, WindowMode};
 
-use crate::game_over::GameOverPlugin;
-use crate::gameplay::GameplayPlugin;
-use crate::main_menu::MainMenuPlugin;
-use crate::utils::*;
+use bevy_pong::accessibility::AccessibilityPlugin;
+use bevy_pong::breakout::BreakoutPlugin;
+use bevy_pong::camera_fx::CameraFxPlugin;
+use bevy_pong::forces::ForcesPlugin;
+use bevy_pong::game_over::GameOverPlugin;
+use bevy_pong::gameplay::{BoardConfig, GameplayClientPlugin, GameplayPlugin};
+use bevy_pong::history::HistoryPlugin;
+use bevy_pong::lobby::LobbyPlugin;
+use bevy_pong::main_menu::MainMenuPlugin;
+use bevy_pong::netplay::NetplayPlugin;
+use bevy_pong::practice::PracticePlugin;
+use bevy_pong::profile::ProfilePlugin;
+use bevy_pong::remote::RemotePlugin;
+use bevy_pong::replay::ReplayPlugin;
+use bevy_pong::resume::ResumePlugin;
+use bevy_pong::settings_menu::SettingsMenuPlugin;
+use bevy_pong::survival::SurvivalPlugin;
+use bevy_pong::targets::TargetsPlugin;
+use bevy_pong::text_input::TextInputPlugin;
+use bevy_pong::theme::ThemePlugin;
+use bevy_pong::tournament::TournamentPlugin;
+use bevy_pong::utils::*;
+use bevy_pong::{GameState, LastWinner};
 
 // TODO
 // v bug: hit on dice can lead to ball leaking through wall
@@ -14,35 +35,16 @@ use crate::utils::*;
 // v add delayed ball start
 // v add player lost screen
 // v add scores ui
-// ? add window scaling
-// ? add screen margin and fix window size
+// v add window scaling
+// v add screen margin and fix window size
 // * add ball loose effect (scale down)
-// * add ai player
+// v add ai player
 // * add sound effects
 // * add mouse play mode
 // * add difficulty selector
 
-mod game_over;
-mod gameplay;
-mod main_menu;
-mod utils;
-
-#[derive(Debug, Clone, Eq, Default, PartialEq, Hash, States)]
-pub enum GameState {
-    #[default]
-    MainMenu,
-    Gameplay,
-    GameOver,
-}
-
-#[derive(Clone, Debug, Default, Eq, PartialEq, Resource)]
-pub struct LastWinner {
-    player: Option<DiceKind>,
-}
-
 fn main() {
     App::new()
-        .insert_resource(ClearColor(Color::rgb(0.6, 0.6, 0.6)))
         .add_state::<GameState>()
         .insert_resource(LastWinner::default())
         .add_plugins((
@@ -57,22 +59,106 @@ fn main() {
                     primary_window: Some(Window {
                         resolution: (1100.0, 1000.0).into(),
                         resize_constraints: WindowResizeConstraints {
-                            min_width: 1100.0,
-                            min_height: 1000.0,
+                            min_width: 440.0,
+                            min_height: 400.0,
                             ..default()
                         },
                         ..default()
                     }),
                     ..default()
                 }),
+            AccessibilityPlugin,
+            ThemePlugin,
             MainMenuPlugin,
+            SettingsMenuPlugin,
             GameplayPlugin,
+            GameplayClientPlugin,
             GameOverPlugin,
+            CameraFxPlugin,
+            NetplayPlugin,
+            RemotePlugin,
+            LobbyPlugin,
+            ReplayPlugin,
+            (
+                ProfilePlugin,
+                HistoryPlugin,
+                TextInputPlugin,
+                SurvivalPlugin,
+                ResumePlugin,
+                TournamentPlugin,
+                PracticePlugin,
+                TargetsPlugin,
+                BreakoutPlugin,
+                ForcesPlugin,
+            ),
         ))
         .add_systems(Startup, global_setup)
+        .add_systems(
+            Update,
+            (
+                fit_camera_to_board.run_if(resource_changed::<BoardConfig>()),
+                update_ui_scale,
+                toggle_fullscreen,
+            ),
+        )
         .run();
 }
 
-fn global_setup(mut commands: Commands) {
-    commands.spawn(Camera2dBundle::default());
+fn global_setup(mut commands: Commands, board: Res<BoardConfig>) {
+    let world_size = Vec2::from(board.world_size());
+    let mut camera = Camera2dBundle::default();
+    // whole board always fits, spare window space is left as letterbox
+    camera.projection.scaling_mode = ScalingMode::AutoMin {
+        min_width: world_size.x,
+        min_height: world_size.y,
+    };
+    commands.spawn(camera);
+}
+
+/// Board received from LAN host may differ from the local one
+fn fit_camera_to_board(
+    board: Res<BoardConfig>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut projections: Query<&mut OrthographicProjection, With<Camera2d>>,
+    mut ui_scale: ResMut<UiScale>,
+) {
+    let world_size = Vec2::from(board.world_size());
+    for mut projection in &mut projections {
+        projection.scaling_mode = ScalingMode::AutoMin {
+            min_width: world_size.x,
+            min_height: world_size.y,
+        };
+    }
+    if let Ok(window) = windows.get_single() {
+        ui_scale.0 = world_scale(window, world_size) as f64;
+    }
+}
+
+fn update_ui_scale(
+    board: Res<BoardConfig>,
+    windows: Query<&Window, (With<PrimaryWindow>, Changed<Window>)>,
+    mut ui_scale: ResMut<UiScale>,
+) {
+    if let Ok(window) = windows.get_single() {
+        let scale = world_scale(window, Vec2::from(board.world_size())) as f64;
+        if ui_scale.0 != scale {
+            ui_scale.0 = scale;
+        }
+    }
+}
+
+fn toggle_fullscreen(
+    keyboard: Res<Input<KeyCode>>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    let alt_enter = keyboard.pressed(KeyCode::AltLeft) && keyboard.just_pressed(KeyCode::Return);
+    if !(keyboard.just_pressed(KeyCode::F11) || alt_enter) {
+        return;
+    }
+    if let Ok(mut window) = windows.get_single_mut() {
+        window.mode = match window.mode {
+            WindowMode::Windowed => WindowMode::BorderlessFullscreen,
+            _ => WindowMode::Windowed,
+        };
+    }
 }
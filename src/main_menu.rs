@@ -1,6 +1,11 @@
 use bevy::app::AppExit;
 use bevy::prelude::*;
 
+use crate::breakout::BrickLibrary;
+use crate::gameplay::GameMode;
+use crate::resume::{continue_saved_match, saved_match_path};
+use crate::theme::Theme;
+use crate::tournament::Tournament;
 use crate::utils::*;
 use crate::GameState;
 
@@ -12,6 +17,33 @@ pub struct MenuUIRoot;
 #[derive(Component)]
 pub struct StartButton;
 
+#[derive(Component)]
+pub struct ContinueButton;
+
+#[derive(Component)]
+pub struct SurvivalButton;
+
+#[derive(Component)]
+pub struct TournamentButton;
+
+#[derive(Component)]
+pub struct BreakoutButton;
+
+#[derive(Component)]
+pub struct PracticeButton;
+
+#[derive(Component)]
+pub struct LanButton;
+
+#[derive(Component)]
+pub struct StatsButton;
+
+#[derive(Component)]
+pub struct HistoryButton;
+
+#[derive(Component)]
+pub struct SettingsButton;
+
 #[derive(Component)]
 pub struct QuitButton;
 
@@ -19,17 +51,166 @@ impl Plugin for MainMenuPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(OnEnter(GameState::MainMenu), spawn_menu)
             .add_systems(OnExit(GameState::MainMenu), despawn_menu)
-            .add_systems(Update, (play_button_clicked, quit_button_clicked));
+            .add_systems(
+                Update,
+                (
+                    play_button_clicked,
+                    continue_button_clicked,
+                    survival_button_clicked,
+                    tournament_button_clicked,
+                    breakout_button_clicked,
+                    practice_button_clicked,
+                    lan_button_clicked,
+                    stats_button_clicked,
+                    history_button_clicked,
+                    settings_button_clicked,
+                    quit_button_clicked,
+                ),
+            );
     }
 }
 
-fn spawn_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
-    let start_button = spawn_button(&mut commands, &asset_server, "PvP", Color::LIME_GREEN);
+fn spawn_menu(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    theme: Res<Theme>,
+    tournament: Option<Res<Tournament>>,
+    bricks: Res<BrickLibrary>,
+) {
+    let start_button = spawn_button(
+        &mut commands,
+        &asset_server,
+        &theme,
+        "PvP",
+        theme.button_primary,
+    );
     commands.entity(start_button).insert(StartButton);
 
-    let quit_button = spawn_button(&mut commands, &asset_server, "Quit", Color::DARK_GRAY);
+    // secondary entries are compact so the whole menu fits
+    let continue_button = saved_match_path().exists().then(|| {
+        let continue_button = spawn_sized_button(
+            &mut commands,
+            &asset_server,
+            &theme,
+            "Continue",
+            theme.button_primary,
+            ButtonSize::Compact,
+        );
+        commands.entity(continue_button).insert(ContinueButton);
+        continue_button
+    });
+
+    let survival_button = spawn_sized_button(
+        &mut commands,
+        &asset_server,
+        &theme,
+        "Survival",
+        theme.button,
+        ButtonSize::Compact,
+    );
+    commands.entity(survival_button).insert(SurvivalButton);
+
+    // tournament in progress is continued
+    let tournament_label = if tournament.is_some() {
+        "Bracket"
+    } else {
+        "Tournament"
+    };
+    let tournament_button = spawn_sized_button(
+        &mut commands,
+        &asset_server,
+        &theme,
+        tournament_label,
+        theme.button,
+        ButtonSize::Compact,
+    );
+    commands.entity(tournament_button).insert(TournamentButton);
+
+    // breakout needs a brick layout
+    let breakout_button = bricks.selected().is_some().then(|| {
+        let breakout_button = spawn_sized_button(
+            &mut commands,
+            &asset_server,
+            &theme,
+            "Breakout",
+            theme.button,
+            ButtonSize::Compact,
+        );
+        commands.entity(breakout_button).insert(BreakoutButton);
+        breakout_button
+    });
+
+    let practice_button = spawn_sized_button(
+        &mut commands,
+        &asset_server,
+        &theme,
+        "Practice",
+        theme.button,
+        ButtonSize::Compact,
+    );
+    commands.entity(practice_button).insert(PracticeButton);
+
+    let lan_button = spawn_sized_button(
+        &mut commands,
+        &asset_server,
+        &theme,
+        "LAN",
+        theme.button,
+        ButtonSize::Compact,
+    );
+    commands.entity(lan_button).insert(LanButton);
+
+    let stats_button = spawn_sized_button(
+        &mut commands,
+        &asset_server,
+        &theme,
+        "Stats",
+        theme.button,
+        ButtonSize::Compact,
+    );
+    commands.entity(stats_button).insert(StatsButton);
+
+    let history_button = spawn_sized_button(
+        &mut commands,
+        &asset_server,
+        &theme,
+        "History",
+        theme.button,
+        ButtonSize::Compact,
+    );
+    commands.entity(history_button).insert(HistoryButton);
+
+    let settings_button = spawn_sized_button(
+        &mut commands,
+        &asset_server,
+        &theme,
+        "Settings",
+        theme.button,
+        ButtonSize::Compact,
+    );
+    commands.entity(settings_button).insert(SettingsButton);
+
+    let quit_button = spawn_sized_button(
+        &mut commands,
+        &asset_server,
+        &theme,
+        "Quit",
+        theme.button_secondary,
+        ButtonSize::Compact,
+    );
     commands.entity(quit_button).insert(QuitButton);
 
+    let mut grid_buttons = vec![survival_button, tournament_button];
+    grid_buttons.extend(breakout_button);
+    grid_buttons.extend([
+        practice_button,
+        lan_button,
+        stats_button,
+        history_button,
+        settings_button,
+    ]);
+    let grid = spawn_button_grid(&mut commands, &grid_buttons);
+
     commands
         .spawn(NodeBundle {
             style: Style {
@@ -53,14 +234,16 @@ fn spawn_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
                     "PONG",
                     TextStyle {
                         font: asset_server.load(FONT_PATH),
-                        font_size: 96.0,
-                        color: Color::BLACK,
+                        font_size: 96.0 * theme.font_scale,
+                        color: theme.text,
                     },
                 ),
                 ..default()
             });
         })
         .add_child(start_button)
+        .push_children(&Vec::from_iter(continue_button))
+        .add_child(grid)
         .add_child(quit_button);
 }
 
@@ -71,15 +254,130 @@ fn despawn_menu(mut commands: Commands, menu_root: Query<Entity, With<MenuUIRoot
 
 fn play_button_clicked(
     interactions: Query<&Interaction, (With<StartButton>, Changed<Interaction>)>,
+    mut mode: ResMut<GameMode>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for interaction in &interactions {
+        if matches!(interaction, Interaction::Pressed) {
+            *mode = GameMode::Versus;
+            next_state.set(GameState::Gameplay);
+        }
+    }
+}
+
+/// Continued match keeps the mode it was saved with
+fn continue_button_clicked(
+    mut commands: Commands,
+    interactions: Query<&Interaction, (With<ContinueButton>, Changed<Interaction>)>,
+) {
+    for interaction in &interactions {
+        if matches!(interaction, Interaction::Pressed) {
+            commands.add(continue_saved_match);
+        }
+    }
+}
+
+fn survival_button_clicked(
+    interactions: Query<&Interaction, (With<SurvivalButton>, Changed<Interaction>)>,
+    mut mode: ResMut<GameMode>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for interaction in &interactions {
+        if matches!(interaction, Interaction::Pressed) {
+            *mode = GameMode::Survival;
+            next_state.set(GameState::Gameplay);
+        }
+    }
+}
+
+fn tournament_button_clicked(
+    interactions: Query<&Interaction, (With<TournamentButton>, Changed<Interaction>)>,
+    tournament: Option<Res<Tournament>>,
     mut next_state: ResMut<NextState<GameState>>,
 ) {
     for interaction in &interactions {
         if matches!(interaction, Interaction::Pressed) {
+            next_state.set(if tournament.is_some() {
+                GameState::Bracket
+            } else {
+                GameState::TournamentSetup
+            });
+        }
+    }
+}
+
+/// Bricks are set up from the layout chosen in settings
+fn breakout_button_clicked(
+    interactions: Query<&Interaction, (With<BreakoutButton>, Changed<Interaction>)>,
+    mut mode: ResMut<GameMode>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for interaction in &interactions {
+        if matches!(interaction, Interaction::Pressed) {
+            *mode = GameMode::Breakout;
             next_state.set(GameState::Gameplay);
         }
     }
 }
 
+fn practice_button_clicked(
+    interactions: Query<&Interaction, (With<PracticeButton>, Changed<Interaction>)>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for interaction in &interactions {
+        if matches!(interaction, Interaction::Pressed) {
+            next_state.set(GameState::Practice);
+        }
+    }
+}
+
+/// Networked matches are always versus
+fn lan_button_clicked(
+    interactions: Query<&Interaction, (With<LanButton>, Changed<Interaction>)>,
+    mut mode: ResMut<GameMode>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for interaction in &interactions {
+        if matches!(interaction, Interaction::Pressed) {
+            *mode = GameMode::Versus;
+            next_state.set(GameState::Join);
+        }
+    }
+}
+
+fn stats_button_clicked(
+    interactions: Query<&Interaction, (With<StatsButton>, Changed<Interaction>)>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for interaction in &interactions {
+        if matches!(interaction, Interaction::Pressed) {
+            next_state.set(GameState::Stats);
+        }
+    }
+}
+
+fn history_button_clicked(
+    interactions: Query<&Interaction, (With<HistoryButton>, Changed<Interaction>)>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for interaction in &interactions {
+        if matches!(interaction, Interaction::Pressed) {
+            next_state.set(GameState::History);
+        }
+    }
+}
+
+fn settings_button_clicked(
+    interactions: Query<&Interaction, (With<SettingsButton>, Changed<Interaction>)>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for interaction in &interactions {
+        if matches!(interaction, Interaction::Pressed) {
+            next_state.set(GameState::Settings);
+        }
+    }
+}
+
 fn quit_button_clicked(
     interactions: Query<&Interaction, (With<QuitButton>, Changed<Interaction>)>,
     mut event_writer: EventWriter<AppExit>,
@@ -0,0 +1,567 @@
+use std::collections::VecDeque;
+use std::io::ErrorKind;
+use std::net::{SocketAddr, UdpSocket};
+
+use bevy::prelude::*;
+
+use crate::gameplay::{
+    apply_dice_inputs, seed_match_rng, Resimulating, SeedSequence, SimSnapshot, SimulationDriver,
+    SimulationStep,
+};
+use crate::utils::*;
+use crate::GameState;
+
+/// Peer-to-peer match over UDP with rollback.
+///
+/// Start two processes, e.g.
+/// `bevy_pong --netplay 7001 127.0.0.1:7002 left` and
+/// `bevy_pong --netplay 7002 127.0.0.1:7001 right`.
+pub struct NetplayPlugin;
+
+impl Plugin for NetplayPlugin {
+    fn build(&self, app: &mut App) {
+        let args: Vec<String> = std::env::args().collect();
+        if let Some(config) = NetplayConfig::from_args(&args) {
+            let seed = app.world.resource_mut::<SeedSequence>().next_seed();
+            match NetSession::bind(&config, seed) {
+                Ok(session) => {
+                    info!(
+                        "netplay as {:?} player on port {}, peer {}",
+                        config.side, config.local_port, config.peer
+                    );
+                    app.insert_resource(session)
+                        .insert_resource(SimulationDriver::External)
+                        .insert_resource(NextState(Some(GameState::Gameplay)));
+                }
+                Err(e) => error!("failed to start netplay: {}", e),
+            }
+        }
+
+        app.add_systems(
+            Update,
+            sample_local_input.run_if(resource_exists::<NetSession>()),
+        )
+        .add_systems(
+            FixedUpdate,
+            netplay_tick
+                .run_if(in_state(GameState::Gameplay).and_then(resource_exists::<NetSession>())),
+        )
+        .add_systems(
+            OnExit(GameState::Gameplay),
+            end_session.run_if(resource_exists::<NetSession>()),
+        );
+    }
+}
+
+/// frames between sampling local input and applying it, hides most of latency
+const INPUT_DELAY: usize = 2;
+/// simulation stalls when remote inputs are missing for more frames than this
+const MAX_PREDICTION_FRAMES: u32 = 8;
+/// max inputs resent in one packet
+const MAX_INPUTS_PER_PACKET: usize = 64;
+/// remote frames accepted ahead of next local frame, peer stalls before it gets
+/// further as both sides delay their inputs
+const MAX_REMOTE_LEAD: usize = MAX_PREDICTION_FRAMES as usize + 2 * INPUT_DELAY;
+/// secs between handshake retries
+const HELLO_INTERVAL: f32 = 0.25;
+/// secs without packets after which peer is considered gone
+const DISCONNECT_TIMEOUT: f32 = 5.0;
+
+const PACKET_HELLO: u8 = 0;
+const PACKET_INPUT: u8 = 1;
+const PACKET_BYE: u8 = 2;
+
+pub struct NetplayConfig {
+    pub local_port: u16,
+    pub peer: SocketAddr,
+    pub side: DiceKind,
+}
+
+impl NetplayConfig {
+    /// Parses `--netplay <local_port> <peer_addr> <left|right>`
+    pub fn from_args(args: &[String]) -> Option<Self> {
+        let position = args.iter().position(|arg| arg == "--netplay")?;
+        let parsed = (|| {
+            let local_port = args.get(position + 1)?.parse().ok()?;
+            let peer = args.get(position + 2)?.parse().ok()?;
+            let side = match args.get(position + 3)?.as_str() {
+                "left" => DiceKind::Left,
+                "right" => DiceKind::Right,
+                _ => return None,
+            };
+            Some(Self {
+                local_port,
+                peer,
+                side,
+            })
+        })();
+        if parsed.is_none() {
+            error!("usage: --netplay <local_port> <peer_addr> <left|right>");
+        }
+        parsed
+    }
+}
+
+/// True for handshake packet peer sends when its session starts
+pub fn is_hello_packet(packet: &[u8]) -> bool {
+    packet.len() == 10 && packet[0] == PACKET_HELLO
+}
+
+/// Reason why networked match ended early
+enum SessionEnd {
+    PeerLeft,
+    TimedOut,
+}
+
+#[derive(Resource)]
+pub struct NetSession {
+    socket: UdpSocket,
+    peer: SocketAddr,
+    local_side: DiceKind,
+    /// serve seed, chosen by left player
+    seed: u64,
+    connected: bool,
+    /// latest local input, sampled every frame
+    local_axis: i8,
+    /// next frame to simulate
+    frame: u32,
+    /// local inputs by frame, already shifted by `INPUT_DELAY`
+    local_inputs: Vec<i8>,
+    /// remote inputs by frame, `None` until received
+    remote_inputs: Vec<Option<i8>>,
+    /// remote inputs used when frame was simulated, either confirmed or predicted
+    used_remote_inputs: Vec<i8>,
+    /// all remote inputs before this frame are known, so those frames are final
+    confirmed_frame: u32,
+    /// number of local inputs peer has confirmed
+    peer_ack: u32,
+    /// states before simulating given frame, oldest first
+    snapshots: VecDeque<(u32, SimSnapshot)>,
+    /// state change requested by simulation, applied once its frame is final
+    pending_state: Option<(u32, GameState)>,
+    since_last_packet: f32,
+    since_hello: f32,
+}
+
+impl NetSession {
+    /// `seed` is used for serves when local player is on the left
+    pub fn bind(config: &NetplayConfig, seed: u64) -> std::io::Result<Self> {
+        let socket = UdpSocket::bind(("0.0.0.0", config.local_port))?;
+        Self::from_socket(socket, config.peer, config.side, seed)
+    }
+
+    /// Session over already bound socket, e.g. one used for lobby handshake
+    pub fn from_socket(
+        socket: UdpSocket,
+        peer: SocketAddr,
+        local_side: DiceKind,
+        seed: u64,
+    ) -> std::io::Result<Self> {
+        socket.set_nonblocking(true)?;
+        Ok(Self {
+            socket,
+            peer,
+            local_side,
+            seed,
+            connected: false,
+            local_axis: 0,
+            frame: 0,
+            // first frames have no input because of input delay
+            local_inputs: vec![0; INPUT_DELAY],
+            remote_inputs: vec![Some(0); INPUT_DELAY],
+            used_remote_inputs: Vec::new(),
+            confirmed_frame: 0,
+            peer_ack: 0,
+            snapshots: VecDeque::new(),
+            pending_state: None,
+            since_last_packet: 0.0,
+            since_hello: HELLO_INTERVAL,
+        })
+    }
+
+    fn send(&self, packet: &[u8]) {
+        if let Err(e) = self.socket.send_to(packet, self.peer) {
+            warn!("netplay send failed: {}", e);
+        }
+    }
+
+    fn send_hello(&self) {
+        let mut packet = vec![PACKET_HELLO, self.local_side as u8];
+        packet.extend_from_slice(&self.seed.to_le_bytes());
+        self.send(&packet);
+    }
+
+    /// Sends all local inputs peer has not confirmed yet
+    fn send_inputs(&self) {
+        let start = (self.peer_ack as usize).min(self.local_inputs.len());
+        let end = self.local_inputs.len().min(start + MAX_INPUTS_PER_PACKET);
+        let mut packet = vec![PACKET_INPUT];
+        packet.extend_from_slice(&self.confirmed_frame.to_le_bytes());
+        packet.extend_from_slice(&(start as u32).to_le_bytes());
+        packet.push((end - start) as u8);
+        packet.extend(
+            self.local_inputs[start..end]
+                .iter()
+                .map(|&input| input as u8),
+        );
+        self.send(&packet);
+    }
+
+    /// Reads all pending packets, returns earliest frame simulated with wrong prediction
+    fn receive(&mut self) -> Result<Option<u32>, SessionEnd> {
+        let mut rollback_from: Option<u32> = None;
+        let mut buf = [0u8; 512];
+        loop {
+            let len = match self.socket.recv_from(&mut buf) {
+                Ok((len, from)) if from == self.peer => len,
+                Ok(_) => continue,
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) => {
+                    warn!("netplay receive failed: {}", e);
+                    break;
+                }
+            };
+            self.since_last_packet = 0.0;
+            let packet = &buf[..len];
+            match packet.first() {
+                Some(&PACKET_HELLO) if len == 10 => self.handle_hello(packet),
+                Some(&PACKET_INPUT) if len >= 10 && self.connected => {
+                    if let Some(frame) = self.handle_inputs(packet) {
+                        rollback_from = Some(rollback_from.map_or(frame, |r| r.min(frame)));
+                    }
+                }
+                Some(&PACKET_BYE) => return Err(SessionEnd::PeerLeft),
+                _ => {}
+            }
+        }
+        Ok(rollback_from)
+    }
+
+    fn handle_hello(&mut self, packet: &[u8]) {
+        let peer_is_left = packet[1] == DiceKind::Left as u8;
+        if peer_is_left == (self.local_side == DiceKind::Left) {
+            warn!("netplay peer picked the same side");
+            return;
+        }
+        if !self.connected {
+            if peer_is_left {
+                self.seed = u64::from_le_bytes(packet[2..10].try_into().unwrap());
+            }
+            self.connected = true;
+            info!("netplay connected to {}", self.peer);
+        }
+        // peer keeps saying hello until it hears back
+        self.send_hello();
+    }
+
+    fn handle_inputs(&mut self, packet: &[u8]) -> Option<u32> {
+        let ack = u32::from_le_bytes(packet[1..5].try_into().unwrap());
+        let start = u32::from_le_bytes(packet[5..9].try_into().unwrap()) as usize;
+        let inputs = &packet[10..];
+        if inputs.len() != packet[9] as usize {
+            return None;
+        }
+        // never trust the wire, peer only resends around frames we are at
+        let oldest = (self.confirmed_frame as usize).saturating_sub(MAX_INPUTS_PER_PACKET);
+        let newest = self.frame as usize + MAX_REMOTE_LEAD;
+        if start < oldest || start > newest {
+            return None;
+        }
+        self.peer_ack = self.peer_ack.max(ack);
+
+        let mut mispredicted: Option<u32> = None;
+        for (offset, &input) in inputs.iter().enumerate() {
+            let frame = start + offset;
+            // clamp to valid axis range
+            let input = (input as i8).clamp(-1, 1);
+            if self.remote_inputs.len() <= frame {
+                self.remote_inputs.resize(frame + 1, None);
+            }
+            if self.remote_inputs[frame].is_some() {
+                continue;
+            }
+            self.remote_inputs[frame] = Some(input);
+            let was_used = self.used_remote_inputs.get(frame);
+            if was_used.is_some_and(|&used| used != input) && mispredicted.is_none() {
+                mispredicted = Some(frame as u32);
+            }
+        }
+        while self
+            .remote_inputs
+            .get(self.confirmed_frame as usize)
+            .is_some_and(|input| input.is_some())
+        {
+            self.confirmed_frame += 1;
+        }
+        mispredicted
+    }
+
+    /// Remote input for frame, predicted as repeat of last known input when missing
+    fn remote_input(&self, frame: u32) -> i8 {
+        let frame = frame as usize;
+        if let Some(Some(input)) = self.remote_inputs.get(frame) {
+            return *input;
+        }
+        let last_known = (self.confirmed_frame as usize).min(frame);
+        last_known
+            .checked_sub(1)
+            .and_then(|f| self.remote_inputs[f])
+            .unwrap_or(0)
+    }
+
+    /// Simulates single frame, storing state before it for rollback
+    fn simulate_frame(&mut self, world: &mut World, frame: u32) {
+        let Some(snapshot) = SimSnapshot::capture(world) else {
+            return;
+        };
+        self.snapshots.push_back((frame, snapshot));
+
+        let local = self.local_inputs[frame as usize];
+        let remote = self.remote_input(frame);
+        if self.used_remote_inputs.len() <= frame as usize {
+            self.used_remote_inputs.push(remote);
+        } else {
+            self.used_remote_inputs[frame as usize] = remote;
+        }
+        let (left, right) = match self.local_side {
+            DiceKind::Left => (local, remote),
+            DiceKind::Right => (remote, local),
+        };
+        apply_dice_inputs(world, left as f32, right as f32);
+        world.run_schedule(SimulationStep);
+
+        // state transitions can not be undone, defer them until frame is final
+        if let Some(state) = world.resource_mut::<NextState<GameState>>().0.take() {
+            self.pending_state = Some((frame, state));
+        }
+    }
+
+    /// Restores state before `from` and simulates again up to current frame.
+    /// Those frames sent their events already, so they are not sent again.
+    fn rollback(&mut self, world: &mut World, from: u32) {
+        let Some(index) = self.snapshots.iter().position(|(f, _)| *f == from) else {
+            warn!("netplay can not roll back to frame {}", from);
+            return;
+        };
+        self.snapshots[index].1.restore(world);
+        self.snapshots.truncate(index);
+        if self.pending_state.is_some_and(|(f, _)| f >= from) {
+            self.pending_state = None;
+        }
+        world.insert_resource(Resimulating);
+        for frame in from..self.frame {
+            self.simulate_frame(world, frame);
+        }
+        world.remove_resource::<Resimulating>();
+    }
+
+    fn advance(&mut self, world: &mut World) {
+        self.local_inputs.push(self.local_axis);
+        let frame = self.frame;
+        self.simulate_frame(world, frame);
+        self.frame += 1;
+    }
+
+    /// Applies deferred state change and drops snapshots that can not be rolled back to
+    fn commit_final_frames(&mut self, world: &mut World) {
+        if let Some((frame, state)) = self.pending_state {
+            if frame < self.confirmed_frame {
+                world.resource_mut::<NextState<GameState>>().set(state);
+                self.pending_state = None;
+            }
+        }
+        while self
+            .snapshots
+            .front()
+            .is_some_and(|(frame, _)| *frame < self.confirmed_frame)
+        {
+            self.snapshots.pop_front();
+        }
+    }
+}
+
+fn sample_local_input(mut session: ResMut<NetSession>, keyboard: Res<Input<KeyCode>>) {
+    let mut axis = 0;
+    if keyboard.any_pressed([KeyCode::W, KeyCode::Up]) {
+        axis += 1;
+    }
+    if keyboard.any_pressed([KeyCode::S, KeyCode::Down]) {
+        axis -= 1;
+    }
+    session.local_axis = axis;
+}
+
+fn netplay_tick(world: &mut World) {
+    let dt = world.resource::<Time>().delta_seconds();
+    let ended = world.resource_scope(|world, mut session: Mut<NetSession>| {
+        session.since_last_packet += dt;
+        let was_connected = session.connected;
+        let rollback_from = match session.receive() {
+            Ok(rollback_from) => rollback_from,
+            Err(end) => return Some(end),
+        };
+
+        if !session.connected {
+            session.since_hello += dt;
+            if session.since_hello >= HELLO_INTERVAL {
+                session.since_hello = 0.0;
+                session.send_hello();
+            }
+            return None;
+        }
+        if !was_connected {
+            seed_match_rng(world, session.seed);
+        }
+        if session.since_last_packet > DISCONNECT_TIMEOUT {
+            return Some(SessionEnd::TimedOut);
+        }
+
+        if let Some(from) = rollback_from {
+            session.rollback(world, from);
+        }
+        if session.frame < session.confirmed_frame + MAX_PREDICTION_FRAMES {
+            session.advance(world);
+        }
+        session.send_inputs();
+        session.commit_final_frames(world);
+        None
+    });
+
+    if let Some(end) = ended {
+        // peer leaves right after match end, which may not be final here yet
+        let pending_state = world.resource::<NetSession>().pending_state;
+        let next_state = match (end, pending_state) {
+            (SessionEnd::PeerLeft, Some((_, state))) => state,
+            (SessionEnd::PeerLeft, None) => {
+                info!("netplay peer left the match");
+                GameState::MainMenu
+            }
+            (SessionEnd::TimedOut, _) => {
+                warn!("netplay peer timed out");
+                GameState::MainMenu
+            }
+        };
+        world.resource_mut::<NextState<GameState>>().set(next_state);
+    }
+}
+
+fn end_session(mut commands: Commands, session: Res<NetSession>) {
+    session.send(&[PACKET_BYE]);
+    commands.remove_resource::<NetSession>();
+    commands.insert_resource(SimulationDriver::Local);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::gameplay::{MatchSim, SIMULATION_HZ};
+    use crate::harness::HeadlessGame;
+
+    fn session() -> NetSession {
+        let socket = UdpSocket::bind(("127.0.0.1", 0)).unwrap();
+        let peer = "127.0.0.1:9".parse().unwrap();
+        let mut session = NetSession::from_socket(socket, peer, DiceKind::Left, 1).unwrap();
+        session.connected = true;
+        session
+    }
+
+    fn input_packet(start: u32, inputs: &[i8]) -> Vec<u8> {
+        let mut packet = vec![PACKET_INPUT];
+        packet.extend_from_slice(&0u32.to_le_bytes());
+        packet.extend_from_slice(&start.to_le_bytes());
+        packet.push(inputs.len() as u8);
+        packet.extend(inputs.iter().map(|&input| input as u8));
+        packet
+    }
+
+    #[test]
+    fn inputs_far_ahead_are_dropped() {
+        let mut session = session();
+        assert_eq!(session.handle_inputs(&input_packet(u32::MAX, &[1])), None);
+        let start = (MAX_REMOTE_LEAD + 1) as u32;
+        assert_eq!(session.handle_inputs(&input_packet(start, &[1])), None);
+        assert_eq!(session.remote_inputs.len(), INPUT_DELAY);
+
+        session.handle_inputs(&input_packet(INPUT_DELAY as u32, &[1, -1]));
+        assert_eq!(session.confirmed_frame as usize, INPUT_DELAY + 2);
+    }
+
+    /// Headless match driven by netplay session on 127.0.0.1
+    fn networked_game(socket: UdpSocket, peer: SocketAddr, side: DiceKind) -> HeadlessGame {
+        let mut game = HeadlessGame::new(0);
+        let session = NetSession::from_socket(socket, peer, side, 7).unwrap();
+        game.app.world.insert_resource(session);
+        game
+    }
+
+    /// One fixed update with local input chosen by next frame
+    fn tick(game: &mut HeadlessGame, axis: impl Fn(u32) -> i8) {
+        let world = &mut game.app.world;
+        let delta = Duration::from_secs_f64(1.0 / SIMULATION_HZ);
+        world.resource_mut::<Time>().advance_by(delta);
+        let mut session = world.resource_mut::<NetSession>();
+        session.local_axis = axis(session.frame);
+        netplay_tick(world);
+    }
+
+    fn session_of(game: &HeadlessGame) -> &NetSession {
+        game.app.world.resource::<NetSession>()
+    }
+
+    #[test]
+    fn delayed_peer_rolls_back_to_same_match() {
+        let left_socket = UdpSocket::bind(("127.0.0.1", 0)).unwrap();
+        let right_socket = UdpSocket::bind(("127.0.0.1", 0)).unwrap();
+        let left_addr = left_socket.local_addr().unwrap();
+        let right_addr = right_socket.local_addr().unwrap();
+        let mut left = networked_game(left_socket, right_addr, DiceKind::Left);
+        let mut right = networked_game(right_socket, left_addr, DiceKind::Right);
+
+        for _ in 0..100 {
+            if session_of(&left).connected && session_of(&right).connected {
+                break;
+            }
+            tick(&mut left, |_| 0);
+            tick(&mut right, |_| 0);
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        assert!(session_of(&left).connected && session_of(&right).connected);
+
+        // right side runs in bursts, so left predicts its inputs and has to roll back
+        const DELAY: u32 = 4;
+        let left_axis = |frame: u32| [1, 0, -1][(frame / 11 % 3) as usize];
+        let right_axis = |frame: u32| [-1, 1, 0][(frame / 5 % 3) as usize];
+        for step in 0..600 {
+            tick(&mut left, left_axis);
+            if step % DELAY == DELAY - 1 {
+                for _ in 0..DELAY {
+                    tick(&mut right, right_axis);
+                }
+            }
+        }
+
+        // catch up until both sides simulated the same frames with confirmed inputs only
+        let mut settled = false;
+        for _ in 0..100 {
+            let (l, r) = (session_of(&left).frame, session_of(&right).frame);
+            if l <= r {
+                tick(&mut left, |_| 0);
+            }
+            if r <= l {
+                tick(&mut right, |_| 0);
+            }
+            let (l, r) = (session_of(&left), session_of(&right));
+            if l.frame == r.frame && l.confirmed_frame >= l.frame && r.confirmed_frame >= r.frame {
+                settled = true;
+                break;
+            }
+        }
+        assert!(settled);
+        assert!(session_of(&left).frame > 300);
+        let left_sim = &left.app.world.resource::<MatchSim>().0;
+        let right_sim = &right.app.world.resource::<MatchSim>().0;
+        assert_eq!(left_sim, right_sim);
+    }
+}
@@ -0,0 +1,637 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::gameplay::{
+    DiceHit, GameMode, MatchSim, MatchSimSet, PlayerLost, SimulationDriver, SimulationStep,
+};
+use crate::sim::{BoardConfig, Launcher, PongSim, SimEvent};
+use crate::theme::Theme;
+use crate::utils::*;
+use crate::GameState;
+
+/// Practice of the left dice against a wall or ball launcher, with drills and
+/// feedback on where the ball met the dice.
+///
+/// Escape leaves back to practice setup, finished drills end on game over screen.
+pub struct PracticePlugin;
+
+impl Plugin for PracticePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(PracticeSetup::default())
+            .add_systems(OnEnter(GameState::Practice), spawn_setup)
+            .add_systems(OnExit(GameState::Practice), despawn_setup)
+            .add_systems(
+                Update,
+                (
+                    setup_button_clicked,
+                    update_setup_labels.run_if(resource_changed::<PracticeSetup>()),
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Practice)),
+            )
+            .add_systems(
+                OnEnter(GameState::Gameplay),
+                (
+                    forget_last_run,
+                    (start_run, spawn_hud).chain().run_if(
+                        resource_equals(SimulationDriver::Local)
+                            .and_then(|mode: Res<GameMode>| mode.is_practice()),
+                    ),
+                )
+                    .chain(),
+            )
+            .add_systems(OnExit(GameState::Gameplay), despawn_hud)
+            .add_systems(
+                SimulationStep,
+                follow_drill.after(MatchSimSet).run_if(
+                    resource_exists::<PracticeRun>().and_then(resource_exists::<MatchSim>()),
+                ),
+            )
+            .add_systems(
+                Update,
+                (leave_practice, update_hud).run_if(
+                    in_state(GameState::Gameplay).and_then(resource_exists::<PracticeRun>()),
+                ),
+            );
+    }
+}
+
+/// balls of target zone and steep angle drills
+pub const DRILL_BALLS: u32 = 10;
+/// returns in a row of survive drill
+pub const SURVIVE_RETURNS: u32 = 20;
+/// launcher speeds offered on setup screen, in percent of board ball speed
+const LAUNCH_SPEEDS: [u32; 4] = [75, 100, 150, 200];
+/// hits closer to dice centre count as centred
+const CENTRED_OFFSET: f32 = 0.1;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Drill {
+    /// no goal, ends with Escape
+    #[default]
+    Free,
+    /// returns have to reach the wall inside a target zone
+    TargetZone,
+    /// `SURVIVE_RETURNS` returns without a miss
+    Survive,
+    /// launcher fires close to `MAX_BOUNCE_ANGLE`
+    SteepAngles,
+}
+
+impl Drill {
+    fn next(&self) -> Self {
+        match self {
+            Drill::Free => Drill::TargetZone,
+            Drill::TargetZone => Drill::Survive,
+            Drill::Survive => Drill::SteepAngles,
+            Drill::SteepAngles => Drill::Free,
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            Drill::Free => "Free",
+            Drill::TargetZone => "Target Zone",
+            Drill::Survive => "Survive",
+            Drill::SteepAngles => "Steep Angles",
+        }
+    }
+}
+
+/// Angles a launcher fires at
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LaunchPattern {
+    #[default]
+    Mixed,
+    Flat,
+    /// close to `MAX_BOUNCE_ANGLE`, bouncing off top and bottom walls
+    Steep,
+}
+
+impl LaunchPattern {
+    /// Angles relative to horizontal axis, positive upwards
+    pub fn angles(&self) -> Vec<f32> {
+        let shares: &[f32] = match self {
+            LaunchPattern::Mixed => &[0.0, 0.4, -0.25, 0.7, -0.55, 0.15, -0.8],
+            LaunchPattern::Flat => &[0.0, 0.1, -0.1, 0.2, -0.2],
+            LaunchPattern::Steep => &[0.9, -0.9, 0.95, -0.85, 0.85, -0.95],
+        };
+        shares
+            .iter()
+            .map(|share| share * MAX_BOUNCE_ANGLE)
+            .collect()
+    }
+
+    fn next(&self) -> Self {
+        match self {
+            LaunchPattern::Mixed => LaunchPattern::Flat,
+            LaunchPattern::Flat => LaunchPattern::Steep,
+            LaunchPattern::Steep => LaunchPattern::Mixed,
+        }
+    }
+}
+
+/// Other side of a practice match
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PracticeOpponent {
+    /// ball bounces back as it came
+    #[default]
+    Wall,
+    Launcher {
+        pattern: LaunchPattern,
+        /// ball speed in percent of board ball speed
+        speed_percent: u32,
+    },
+}
+
+impl PracticeOpponent {
+    pub fn launcher(&self) -> Option<Launcher> {
+        match *self {
+            PracticeOpponent::Wall => None,
+            PracticeOpponent::Launcher {
+                pattern,
+                speed_percent,
+            } => Some(Launcher {
+                angles: pattern.angles(),
+                speed: speed_percent as f32 / 100.,
+                shots: 0,
+            }),
+        }
+    }
+}
+
+/// Practice chosen on setup screen
+#[derive(Clone, Debug, PartialEq, Resource)]
+pub struct PracticeSetup {
+    pub drill: Drill,
+    pub launcher: bool,
+    pub pattern: LaunchPattern,
+    pub speed_percent: u32,
+}
+
+impl Default for PracticeSetup {
+    fn default() -> Self {
+        Self {
+            drill: Drill::default(),
+            launcher: false,
+            pattern: LaunchPattern::default(),
+            speed_percent: 100,
+        }
+    }
+}
+
+impl PracticeSetup {
+    /// Steep angle drill always plays against a steep launcher
+    pub fn opponent(&self) -> PracticeOpponent {
+        match (self.drill, self.launcher) {
+            (Drill::SteepAngles, _) => PracticeOpponent::Launcher {
+                pattern: LaunchPattern::Steep,
+                speed_percent: self.speed_percent,
+            },
+            (_, true) => PracticeOpponent::Launcher {
+                pattern: self.pattern,
+                speed_percent: self.speed_percent,
+            },
+            (_, false) => PracticeOpponent::Wall,
+        }
+    }
+}
+
+/// Progress of the practice in play or just finished, kept until the next match
+#[derive(Clone, Debug, Default, Resource)]
+pub struct PracticeRun {
+    pub drill: Drill,
+    pub returns: u32,
+    pub misses: u32,
+    /// target zone returns reaching the wall inside the zone
+    pub zone_hits: u32,
+    /// balls done with: missed, or in target zone and steep angle drills returned to the wall
+    pub attempts: u32,
+    /// `Ball::hit_offset` of the last return
+    pub last_offset: Option<f32>,
+    offsets: f32,
+    /// y of the last return while it heads to the wall
+    incoming: Option<f32>,
+    /// board height, target zones are laid out along it
+    board_height: f32,
+}
+
+impl PracticeRun {
+    pub fn new(drill: Drill, board: &BoardConfig) -> Self {
+        Self {
+            drill,
+            board_height: board.height(),
+            ..default()
+        }
+    }
+
+    /// Range of y a target zone return has to reach the wall in, `None` in other drills
+    pub fn zone(&self) -> Option<(f32, f32)> {
+        if self.drill != Drill::TargetZone {
+            return None;
+        }
+        // top, bottom and middle third in turn
+        let third = self.board_height / 3.;
+        let centre = [third, -third, 0.][self.attempts as usize % 3];
+        let half = self.board_height / 8.;
+        Some((centre - half, centre + half))
+    }
+
+    /// Mean distance of returns from dice centre, 0 at centre and 1 at edge
+    pub fn average_offset(&self) -> Option<f32> {
+        (self.returns > 0).then(|| self.offsets / self.returns as f32)
+    }
+
+    pub fn finished(&self) -> bool {
+        match self.drill {
+            Drill::Free => false,
+            Drill::TargetZone | Drill::SteepAngles => self.attempts >= DRILL_BALLS,
+            Drill::Survive => self.misses > 0 || self.returns >= SURVIVE_RETURNS,
+        }
+    }
+
+    /// Follows practice after every step, `sim` is the state after `events`
+    pub fn observe(&mut self, sim: &PongSim, events: &[SimEvent]) {
+        let player = sim.wall.map_or(DiceKind::Left, DiceKind::opponent);
+        for event in events {
+            match event {
+                SimEvent::DiceHit { kind, .. } if *kind == player => {
+                    self.returns += 1;
+                    self.last_offset = sim.ball.hit_offset;
+                    self.offsets += sim.ball.hit_offset.unwrap_or(0.).abs();
+                    self.incoming = Some(sim.ball.y);
+                }
+                SimEvent::DiceHit { .. }
+                | SimEvent::TargetHit { .. }
+                | SimEvent::BrickHit { .. }
+                | SimEvent::BricksCleared => {}
+                SimEvent::PlayerLost { .. } => {
+                    self.misses += 1;
+                    self.incoming = None;
+                    if self.drill != Drill::Survive {
+                        self.attempts += 1;
+                    }
+                }
+            }
+        }
+
+        let Some(incoming) = self.incoming else {
+            return;
+        };
+        let towards_wall = match player {
+            DiceKind::Left => sim.ball.velocity_x > 0.,
+            DiceKind::Right => sim.ball.velocity_x < 0.,
+        };
+        if towards_wall {
+            self.incoming = Some(sim.ball.y);
+            return;
+        }
+        // turned back by the wall, `incoming` is where it got there
+        self.incoming = None;
+        match self.drill {
+            Drill::TargetZone => {
+                if self
+                    .zone()
+                    .is_some_and(|(bottom, top)| (bottom..=top).contains(&incoming))
+                {
+                    self.zone_hits += 1;
+                }
+                self.attempts += 1;
+            }
+            Drill::SteepAngles => self.attempts += 1,
+            Drill::Free | Drill::Survive => {}
+        }
+    }
+
+    fn progress(&self) -> String {
+        match self.drill {
+            Drill::Free => format!("Returns {}   Misses {}", self.returns, self.misses),
+            Drill::TargetZone => format!(
+                "Zone {}/{}   Ball {}/{}",
+                self.zone_hits,
+                self.attempts,
+                (self.attempts + 1).min(DRILL_BALLS),
+                DRILL_BALLS
+            ),
+            Drill::Survive => format!("Returns {}/{}", self.returns, SURVIVE_RETURNS),
+            Drill::SteepAngles => format!(
+                "Caught {}   Ball {}/{}",
+                self.returns,
+                (self.attempts + 1).min(DRILL_BALLS),
+                DRILL_BALLS
+            ),
+        }
+    }
+
+    /// Title of game over screen
+    pub fn title(&self) -> String {
+        match self.drill {
+            Drill::Survive if self.misses > 0 => "Drill Failed".to_string(),
+            _ => "Drill Complete".to_string(),
+        }
+    }
+
+    /// Outcome for game over screen
+    pub fn summary(&self) -> String {
+        let result = match self.drill {
+            Drill::Free => format!("{} returns, {} misses", self.returns, self.misses),
+            Drill::TargetZone => format!("{}/{} returns in zone", self.zone_hits, DRILL_BALLS),
+            Drill::Survive => format!("{}/{} returns", self.returns, SURVIVE_RETURNS),
+            Drill::SteepAngles => format!("{}/{} steep balls caught", self.returns, DRILL_BALLS),
+        };
+        let offset = self
+            .average_offset()
+            .map_or("-".to_string(), |offset| format!("{:.0}%", offset * 100.));
+        format!(
+            "{}: {}\nAvg Hit Offset {}",
+            self.drill.label(),
+            result,
+            offset
+        )
+    }
+}
+
+/// "35% above centre" style description of `Ball::hit_offset`
+pub fn describe_offset(offset: f32) -> String {
+    if offset.abs() < CENTRED_OFFSET {
+        return "centre".to_string();
+    }
+    let side = if offset > 0. { "above" } else { "below" };
+    format!("{:.0}% {} centre", offset.abs() * 100., side)
+}
+
+#[derive(Component)]
+pub struct PracticeUIRoot;
+
+#[derive(Clone, Copy, Component)]
+pub enum PracticeOption {
+    Drill,
+    Opponent,
+    Angles,
+    Speed,
+    Start,
+    Back,
+}
+
+impl PracticeOption {
+    fn label(&self, setup: &PracticeSetup) -> String {
+        let opponent = setup.opponent();
+        match (self, opponent) {
+            (PracticeOption::Drill, _) => format!("Drill: {}", setup.drill.label()),
+            (PracticeOption::Opponent, PracticeOpponent::Wall) => "Opponent: Wall".to_string(),
+            (PracticeOption::Opponent, _) => "Opponent: Launcher".to_string(),
+            (PracticeOption::Angles, PracticeOpponent::Launcher { pattern, .. }) => {
+                format!("Angles: {:?}", pattern)
+            }
+            (PracticeOption::Angles, PracticeOpponent::Wall) => "Angles: -".to_string(),
+            (
+                PracticeOption::Speed,
+                PracticeOpponent::Launcher {
+                    speed_percent: speed,
+                    ..
+                },
+            ) => format!("Speed: {}%", speed),
+            (PracticeOption::Speed, PracticeOpponent::Wall) => "Speed: -".to_string(),
+            (PracticeOption::Start, _) => "Start".to_string(),
+            (PracticeOption::Back, _) => "Back".to_string(),
+        }
+    }
+}
+
+fn spawn_setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    theme: Res<Theme>,
+    setup: Res<PracticeSetup>,
+) {
+    let mut spawn = |option: PracticeOption, color: Color| {
+        let button = spawn_sized_button(
+            &mut commands,
+            &asset_server,
+            &theme,
+            &option.label(&setup),
+            color,
+            ButtonSize::Compact,
+        );
+        commands.entity(button).insert(option);
+        button
+    };
+    let buttons = [
+        spawn(PracticeOption::Drill, theme.button),
+        spawn(PracticeOption::Opponent, theme.button),
+        spawn(PracticeOption::Angles, theme.button),
+        spawn(PracticeOption::Speed, theme.button),
+        spawn(PracticeOption::Start, theme.button_primary),
+        spawn(PracticeOption::Back, theme.button_secondary),
+    ];
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            ..default()
+        })
+        .insert(PracticeUIRoot)
+        .with_children(|commands| {
+            commands.spawn(TextBundle {
+                style: Style {
+                    align_self: AlignSelf::Center,
+                    margin: UiRect::all(Val::Percent(2.0)),
+                    ..default()
+                },
+                text: Text::from_section(
+                    "PRACTICE",
+                    TextStyle {
+                        font: asset_server.load(FONT_PATH),
+                        font_size: 96.0 * theme.font_scale,
+                        color: theme.text,
+                    },
+                ),
+                ..default()
+            });
+        })
+        .push_children(&buttons);
+}
+
+fn despawn_setup(mut commands: Commands, menu_root: Query<Entity, With<PracticeUIRoot>>) {
+    let root_entity = menu_root.single();
+    commands.entity(root_entity).despawn_recursive();
+}
+
+fn setup_button_clicked(
+    interactions: Query<(&Interaction, &PracticeOption), Changed<Interaction>>,
+    mut setup: ResMut<PracticeSetup>,
+    mut mode: ResMut<GameMode>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for (interaction, option) in &interactions {
+        if !matches!(interaction, Interaction::Pressed) {
+            continue;
+        }
+        match option {
+            PracticeOption::Drill => setup.drill = setup.drill.next(),
+            PracticeOption::Opponent => setup.launcher = !setup.launcher,
+            PracticeOption::Angles => setup.pattern = setup.pattern.next(),
+            PracticeOption::Speed => {
+                let index = LAUNCH_SPEEDS
+                    .iter()
+                    .position(|&speed| speed == setup.speed_percent)
+                    .map_or(0, |index| (index + 1) % LAUNCH_SPEEDS.len());
+                setup.speed_percent = LAUNCH_SPEEDS[index];
+            }
+            PracticeOption::Start => {
+                *mode = GameMode::Practice(setup.opponent());
+                next_state.set(GameState::Gameplay);
+            }
+            PracticeOption::Back => next_state.set(GameState::MainMenu),
+        }
+    }
+}
+
+fn update_setup_labels(
+    setup: Res<PracticeSetup>,
+    buttons: Query<(&PracticeOption, &Children)>,
+    mut texts: Query<&mut Text>,
+) {
+    for (option, children) in &buttons {
+        set_button_text(children, &mut texts, option.label(&setup));
+    }
+}
+
+fn forget_last_run(mut commands: Commands) {
+    commands.remove_resource::<PracticeRun>();
+}
+
+fn start_run(mut commands: Commands, setup: Res<PracticeSetup>, board: Res<BoardConfig>) {
+    commands.insert_resource(PracticeRun::new(setup.drill, &board));
+}
+
+fn follow_drill(
+    match_sim: Res<MatchSim>,
+    mut run: ResMut<PracticeRun>,
+    mut hits: EventReader<DiceHit>,
+    mut lost: EventReader<PlayerLost>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let events: Vec<SimEvent> = hits
+        .read()
+        .map(|hit| SimEvent::DiceHit {
+            kind: hit.kind,
+            bounce_angle: hit.bounce_angle,
+        })
+        .chain(lost.read().map(|lost| SimEvent::PlayerLost {
+            is_right: lost.is_right,
+        }))
+        .collect();
+    let was_finished = run.finished();
+    run.observe(&match_sim.0, &events);
+    if run.finished() && !was_finished {
+        next_state.set(GameState::GameOver);
+    }
+}
+
+/// Escape gives up the practice, it is not kept to continue later
+fn leave_practice(keyboard: Res<Input<KeyCode>>, mut next_state: ResMut<NextState<GameState>>) {
+    if keyboard.just_pressed(KeyCode::Escape) {
+        next_state.set(GameState::Practice);
+    }
+}
+
+#[derive(Component)]
+pub struct PracticeHud;
+
+#[derive(Component)]
+pub struct PracticeHudText;
+
+/// Target zone of the drill drawn on the wall
+#[derive(Component)]
+pub struct DrillZoneSprite;
+
+fn spawn_hud(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    theme: Res<Theme>,
+    board: Res<BoardConfig>,
+    mode: Res<GameMode>,
+) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Percent(2.0),
+                    width: Val::Percent(100.0),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            PracticeHud,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font: asset_server.load(FONT_PATH),
+                        font_size: 32.0 * theme.font_scale,
+                        color: theme.score_text,
+                    },
+                ),
+                PracticeHudText,
+            ));
+        });
+
+    let Some(wall) = mode.wall() else {
+        return;
+    };
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: theme.right_trail,
+                custom_size: Some(Vec2::new(board.dice_width(), 1.)),
+                ..default()
+            },
+            transform: Transform::from_xyz(board.dice_position_x(wall), 0., 1.),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        PracticeHud,
+        DrillZoneSprite,
+    ));
+}
+
+fn despawn_hud(mut commands: Commands, huds: Query<Entity, With<PracticeHud>>) {
+    for hud in &huds {
+        commands.entity(hud).despawn_recursive();
+    }
+}
+
+fn update_hud(
+    run: Res<PracticeRun>,
+    mut texts: Query<&mut Text, With<PracticeHudText>>,
+    mut zones: Query<(&mut Transform, &mut Visibility), With<DrillZoneSprite>>,
+) {
+    if !run.is_changed() {
+        return;
+    }
+    let last_hit = run.last_offset.map_or("-".to_string(), describe_offset);
+    for mut text in &mut texts {
+        text.sections[0].value = format!("{}   Last Hit: {}", run.progress(), last_hit);
+    }
+    for (mut transform, mut visibility) in &mut zones {
+        match run.zone().filter(|_| !run.finished()) {
+            Some((bottom, top)) => {
+                transform.translation.y = (bottom + top) / 2.;
+                transform.scale.y = top - bottom;
+                *visibility = Visibility::Visible;
+            }
+            None => *visibility = Visibility::Hidden,
+        }
+    }
+}
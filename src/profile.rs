@@ -0,0 +1,566 @@
+use std::fs;
+use std::path::Path;
+
+use bevy::asset::io::file::FileAssetReader;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::gameplay::{
+    DiceHit, GameMode, MatchSim, MatchSimSet, PlayerLost, ResumedMatch, SimulationDriver,
+    SimulationStep,
+};
+use crate::protocol::{sanitize_name, MAX_NAME_LENGTH};
+use crate::theme::Theme;
+use crate::tournament::TournamentMatch;
+use crate::utils::*;
+use crate::GameState;
+
+/// Player profiles with lifetime stats, kept across restarts in `profiles.ron`.
+///
+/// Stats are recorded for finished local versus matches outside of tournaments, the
+/// active profile plays its preferred side and the other side is a guest.
+pub struct ProfilePlugin;
+
+impl Plugin for ProfilePlugin {
+    fn build(&self, app: &mut App) {
+        let profiles = Profiles::load(&profiles_path());
+
+        app.insert_resource(profiles)
+            .insert_resource(NameEdit(false))
+            .add_systems(
+                OnEnter(GameState::Gameplay),
+                start_tracking.run_if(
+                    resource_equals(SimulationDriver::Local)
+                        .and_then(resource_equals(GameMode::Versus))
+                        .and_then(not(resource_exists::<ResumedMatch>()))
+                        // bracket entrants are not the active profile and a guest
+                        .and_then(not(resource_exists::<TournamentMatch>())),
+                ),
+            )
+            .add_systems(
+                SimulationStep,
+                track_match.after(MatchSimSet).run_if(
+                    resource_exists::<MatchTracker>().and_then(resource_exists::<MatchSim>()),
+                ),
+            )
+            .add_systems(
+                OnExit(GameState::Gameplay),
+                record_match.run_if(resource_exists::<MatchTracker>()),
+            )
+            .add_systems(OnEnter(GameState::Stats), spawn_menu)
+            .add_systems(
+                OnExit(GameState::Stats),
+                (despawn_menu, (stop_name_edit, save_profiles).chain()),
+            )
+            .add_systems(
+                Update,
+                (
+                    option_button_clicked,
+                    back_button_clicked,
+                    edit_name.run_if(resource_equals(NameEdit(true))),
+                    // labels and stats follow the selected profile
+                    (despawn_menu, spawn_menu).chain().run_if(
+                        in_state(GameState::Stats).and_then(
+                            resource_changed::<Profiles>()
+                                .or_else(resource_changed::<NameEdit>())
+                                .or_else(resource_changed::<Theme>()),
+                        ),
+                    ),
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// profiles file, relative to asset base path
+const PROFILES_FILE: &str = "profiles.ron";
+
+fn profiles_path() -> std::path::PathBuf {
+    FileAssetReader::get_base_path().join(PROFILES_FILE)
+}
+
+/// Keys moving a dice up and down
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Controls {
+    #[default]
+    WS,
+    Arrows,
+}
+
+impl Controls {
+    /// `(up, down)` keys
+    pub fn keys(&self) -> (KeyCode, KeyCode) {
+        match self {
+            Controls::WS => (KeyCode::W, KeyCode::S),
+            Controls::Arrows => (KeyCode::Up, KeyCode::Down),
+        }
+    }
+
+    /// Controls left for the guest on the other side
+    pub fn other(&self) -> Self {
+        match self {
+            Controls::WS => Controls::Arrows,
+            Controls::Arrows => Controls::WS,
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            Controls::WS => "W/S",
+            Controls::Arrows => "Up/Down",
+        }
+    }
+}
+
+/// Lifetime stats of one profile, over finished matches only
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlayerStats {
+    pub matches_played: u32,
+    pub matches_won: u32,
+    pub points_scored: u32,
+    pub points_conceded: u32,
+    /// most dice hits within one point
+    pub longest_rally: u32,
+    /// in world units per sec
+    pub fastest_ball: f32,
+    /// number of points played, with `rally_hits` gives average rally length
+    pub rallies: u32,
+    pub rally_hits: u32,
+}
+
+impl PlayerStats {
+    /// Dice hits per point, 0 before the first point
+    pub fn average_rally(&self) -> f32 {
+        if self.rallies == 0 {
+            return 0.;
+        }
+        self.rally_hits as f32 / self.rallies as f32
+    }
+
+    /// Adds finished match played on `side`
+    pub fn record(&mut self, tracker: &MatchTracker, side: DiceKind) {
+        let (scored, conceded) = match side {
+            DiceKind::Left => (tracker.points_left, tracker.points_right),
+            DiceKind::Right => (tracker.points_right, tracker.points_left),
+        };
+        self.matches_played += 1;
+        if tracker.winner == Some(side) {
+            self.matches_won += 1;
+        }
+        self.points_scored += scored;
+        self.points_conceded += conceded;
+        self.longest_rally = self.longest_rally.max(tracker.longest_rally);
+        self.fastest_ball = self.fastest_ball.max(tracker.fastest_ball);
+        self.rallies += tracker.rallies;
+        self.rally_hits += tracker.rally_hits;
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Profile {
+    pub name: String,
+    pub side: DiceKind,
+    pub controls: Controls,
+    #[serde(default)]
+    pub stats: PlayerStats,
+}
+
+impl Profile {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            side: DiceKind::Left,
+            controls: Controls::default(),
+            stats: PlayerStats::default(),
+        }
+    }
+}
+
+/// All profiles on this machine, one of them plays local matches
+#[derive(Clone, Debug, PartialEq, Resource, Serialize, Deserialize)]
+pub struct Profiles {
+    profiles: Vec<Profile>,
+    active: usize,
+}
+
+impl Default for Profiles {
+    fn default() -> Self {
+        Self {
+            profiles: vec![Profile::new("Player 1")],
+            active: 0,
+        }
+    }
+}
+
+impl Profiles {
+    /// Reads `path`, missing or broken file gives a single default profile
+    pub fn load(path: &Path) -> Self {
+        if !path.exists() {
+            return Self::default();
+        }
+        match Self::read(path) {
+            Ok(profiles) => profiles,
+            Err(e) => {
+                warn!("failed to load profiles {}: {}", path.display(), e);
+                Self::default()
+            }
+        }
+    }
+
+    fn read(path: &Path) -> Result<Self, String> {
+        let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let mut profiles: Self = ron::from_str(&content).map_err(|e| e.to_string())?;
+        if profiles.profiles.is_empty() {
+            return Err("no profiles".to_string());
+        }
+        profiles.active = profiles.active.min(profiles.profiles.len() - 1);
+        Ok(profiles)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let content = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|e| e.to_string())?;
+        fs::write(path, content).map_err(|e| e.to_string())
+    }
+
+    pub fn profiles(&self) -> &[Profile] {
+        &self.profiles
+    }
+
+    pub fn active(&self) -> &Profile {
+        &self.profiles[self.active]
+    }
+
+    pub fn active_mut(&mut self) -> &mut Profile {
+        &mut self.profiles[self.active]
+    }
+
+    pub fn select_next(&mut self) {
+        self.active = (self.active + 1) % self.profiles.len();
+    }
+
+    /// Adds profile with unused default name and makes it active
+    pub fn add(&mut self) {
+        let name = (self.profiles.len() + 1..)
+            .map(|n| format!("Player {}", n))
+            .find(|name| self.profiles.iter().all(|p| &p.name != name))
+            .expect("some player number is free");
+        self.profiles.push(Profile::new(&name));
+        self.active = self.profiles.len() - 1;
+    }
+
+    /// Controls of the dice on `kind` side, guest gets whatever the active profile leaves
+    pub fn controls(&self, kind: DiceKind) -> Controls {
+        let active = self.active();
+        if active.side == kind {
+            active.controls
+        } else {
+            active.controls.other()
+        }
+    }
+}
+
+/// Collects stats of the current local match
+#[derive(Clone, Debug, Default, Resource, Serialize, Deserialize)]
+pub struct MatchTracker {
+    pub points_left: u32,
+    pub points_right: u32,
+    pub winner: Option<DiceKind>,
+    pub longest_rally: u32,
+    pub fastest_ball: f32,
+    pub rallies: u32,
+    pub rally_hits: u32,
+    /// hits of the point in play
+    pub hits: u32,
+}
+
+fn start_tracking(mut commands: Commands) {
+    commands.insert_resource(MatchTracker::default());
+}
+
+fn track_match(
+    mut tracker: ResMut<MatchTracker>,
+    match_sim: Res<MatchSim>,
+    mut hits: EventReader<DiceHit>,
+    mut lost: EventReader<PlayerLost>,
+) {
+    let sim = &match_sim.0;
+    tracker.hits += hits.read().count() as u32;
+    for event in lost.read() {
+        if event.is_right {
+            tracker.points_left += 1;
+        } else {
+            tracker.points_right += 1;
+        }
+        let hits = std::mem::take(&mut tracker.hits);
+        tracker.longest_rally = tracker.longest_rally.max(hits);
+        tracker.rallies += 1;
+        tracker.rally_hits += hits;
+        tracker.winner = sim.winner();
+    }
+
+    let ball = &sim.ball;
+    let speed = Vec2::new(ball.velocity_x, ball.velocity_y).length()
+        * sim.board.ball_speed()
+        * sim.ball_speed_factor;
+    tracker.fastest_ball = tracker.fastest_ball.max(speed);
+}
+
+fn record_match(
+    mut commands: Commands,
+    tracker: Res<MatchTracker>,
+    mut profiles: ResMut<Profiles>,
+) {
+    commands.remove_resource::<MatchTracker>();
+    if tracker.winner.is_none() {
+        return;
+    }
+    let profile = profiles.active_mut();
+    let side = profile.side;
+    profile.stats.record(&tracker, side);
+    save(&profiles);
+}
+
+fn save_profiles(profiles: Res<Profiles>) {
+    save(&profiles);
+}
+
+fn save(profiles: &Profiles) {
+    let path = profiles_path();
+    if let Err(e) = profiles.save(&path) {
+        error!("failed to save profiles {}: {}", path.display(), e);
+    }
+}
+
+/// Name of the active profile is being typed in
+#[derive(Clone, Copy, PartialEq, Resource)]
+struct NameEdit(bool);
+
+#[derive(Component)]
+pub struct StatsUIRoot;
+
+#[derive(Component)]
+pub struct BackButton;
+
+/// Button changing the active profile
+#[derive(Clone, Copy, Component)]
+pub enum ProfileOption {
+    Profile,
+    Name,
+    Side,
+    Controls,
+    New,
+}
+
+const PROFILE_OPTIONS: [ProfileOption; 5] = [
+    ProfileOption::Profile,
+    ProfileOption::Name,
+    ProfileOption::Side,
+    ProfileOption::Controls,
+    ProfileOption::New,
+];
+
+impl ProfileOption {
+    fn label(&self, profiles: &Profiles, editing: bool) -> String {
+        let profile = profiles.active();
+        match self {
+            ProfileOption::Profile => format!(
+                "Profile {}/{}",
+                profiles.active + 1,
+                profiles.profiles.len()
+            ),
+            ProfileOption::Name if editing => format!("Name: {}_", profile.name),
+            ProfileOption::Name => format!("Name: {}", profile.name),
+            ProfileOption::Side => format!("Side: {:?}", profile.side),
+            ProfileOption::Controls => format!("Keys: {}", profile.controls.label()),
+            ProfileOption::New => "New Profile".to_string(),
+        }
+    }
+}
+
+fn stats_lines(stats: &PlayerStats) -> String {
+    let win_rate = if stats.matches_played == 0 {
+        0.
+    } else {
+        stats.matches_won as f32 / stats.matches_played as f32 * 100.
+    };
+    format!(
+        "Matches: {} played, {} won ({:.0}%)\n\
+         Points: {} scored, {} conceded\n\
+         Longest Rally: {} hits\n\
+         Average Rally: {:.1} hits\n\
+         Fastest Ball: {:.0}",
+        stats.matches_played,
+        stats.matches_won,
+        win_rate,
+        stats.points_scored,
+        stats.points_conceded,
+        stats.longest_rally,
+        stats.average_rally(),
+        stats.fastest_ball,
+    )
+}
+
+fn spawn_menu(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    theme: Res<Theme>,
+    profiles: Res<Profiles>,
+    name_edit: Res<NameEdit>,
+) {
+    let option_buttons: Vec<Entity> = PROFILE_OPTIONS
+        .iter()
+        .map(|option| {
+            let button = spawn_sized_button(
+                &mut commands,
+                &asset_server,
+                &theme,
+                &option.label(&profiles, name_edit.0),
+                theme.button,
+                ButtonSize::Compact,
+            );
+            commands.entity(button).insert(*option);
+            button
+        })
+        .collect();
+
+    let back_button = spawn_sized_button(
+        &mut commands,
+        &asset_server,
+        &theme,
+        "Back",
+        theme.button_secondary,
+        ButtonSize::Compact,
+    );
+    commands.entity(back_button).insert(BackButton);
+
+    let text_style = |font_size: f32| TextStyle {
+        font: asset_server.load(FONT_PATH),
+        font_size: font_size * theme.font_scale,
+        color: theme.text,
+    };
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            ..default()
+        })
+        .insert(StatsUIRoot)
+        .with_children(|commands| {
+            commands.spawn(TextBundle {
+                style: Style {
+                    align_self: AlignSelf::Center,
+                    margin: UiRect::all(Val::Percent(2.0)),
+                    ..default()
+                },
+                text: Text::from_section("STATS", text_style(96.0)),
+                ..default()
+            });
+        })
+        .push_children(&option_buttons)
+        .with_children(|commands| {
+            commands.spawn(TextBundle {
+                style: Style {
+                    align_self: AlignSelf::Center,
+                    margin: UiRect::all(Val::Percent(2.0)),
+                    ..default()
+                },
+                text: Text::from_section(stats_lines(&profiles.active().stats), text_style(32.0)),
+                ..default()
+            });
+        })
+        .add_child(back_button);
+}
+
+fn despawn_menu(mut commands: Commands, menu_root: Query<Entity, With<StatsUIRoot>>) {
+    let root_entity = menu_root.single();
+    commands.entity(root_entity).despawn_recursive();
+}
+
+fn stop_name_edit(mut profiles: ResMut<Profiles>, mut name_edit: ResMut<NameEdit>) {
+    if name_edit.0 {
+        finish_name(profiles.active_mut());
+        name_edit.0 = false;
+    }
+}
+
+/// Typed name may end up empty or with stray spaces
+fn finish_name(profile: &mut Profile) {
+    profile.name = sanitize_name(&profile.name);
+    if profile.name.is_empty() {
+        profile.name = "Player".to_string();
+    }
+}
+
+fn option_button_clicked(
+    interactions: Query<(&Interaction, &ProfileOption), Changed<Interaction>>,
+    mut profiles: ResMut<Profiles>,
+    mut name_edit: ResMut<NameEdit>,
+) {
+    for (interaction, option) in &interactions {
+        if !matches!(interaction, Interaction::Pressed) {
+            continue;
+        }
+        let editing = name_edit.0;
+        if editing {
+            finish_name(profiles.active_mut());
+            name_edit.0 = false;
+        }
+        match option {
+            ProfileOption::Profile => profiles.select_next(),
+            ProfileOption::Name => name_edit.0 = !editing,
+            ProfileOption::Side => {
+                let profile = profiles.active_mut();
+                profile.side = profile.side.opponent();
+            }
+            ProfileOption::Controls => {
+                let profile = profiles.active_mut();
+                profile.controls = profile.controls.other();
+            }
+            ProfileOption::New => profiles.add(),
+        }
+    }
+}
+
+/// Types into the name of the active profile, Return finishes
+fn edit_name(
+    mut characters: EventReader<ReceivedCharacter>,
+    keyboard: Res<Input<KeyCode>>,
+    mut profiles: ResMut<Profiles>,
+    mut name_edit: ResMut<NameEdit>,
+) {
+    let mut name = profiles.active().name.clone();
+    for event in characters.read() {
+        if !event.char.is_control() && name.chars().count() < MAX_NAME_LENGTH {
+            name.push(event.char);
+        }
+    }
+    if keyboard.just_pressed(KeyCode::Back) {
+        name.pop();
+    }
+    if name != profiles.active().name {
+        profiles.active_mut().name = name;
+    }
+    if keyboard.just_pressed(KeyCode::Return) {
+        finish_name(profiles.active_mut());
+        name_edit.0 = false;
+    }
+}
+
+fn back_button_clicked(
+    interactions: Query<&Interaction, (With<BackButton>, Changed<Interaction>)>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for interaction in &interactions {
+        if matches!(interaction, Interaction::Pressed) {
+            next_state.set(GameState::MainMenu);
+        }
+    }
+}
@@ -0,0 +1,81 @@
+use bincode::Options;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::gameplay::BoardState;
+use crate::utils::DiceKind;
+
+/// UDP port dedicated server listens on unless told otherwise
+pub const DEFAULT_SERVER_PORT: u16 = 7100;
+/// larger datagrams are never sent and are dropped on receive
+pub const MAX_PACKET_SIZE: usize = 512;
+/// longer player names are cut
+pub const MAX_NAME_LENGTH: usize = 16;
+
+/// Messages sent by game client to dedicated server
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ClientMessage {
+    /// repeated until server answers with `Welcome` or `Full`
+    Join {
+        name: String,
+    },
+    /// dice direction in -1..1, `seq` grows with every message to drop reordered ones
+    Input {
+        seq: u32,
+        axis: f32,
+    },
+    /// watch without playing, repeated as keep-alive
+    Spectate {
+        name: String,
+    },
+    Leave,
+}
+
+/// Messages sent by dedicated server to game clients
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ServerMessage {
+    Welcome {
+        side: DiceKind,
+    },
+    /// both sides or all spectator places are taken
+    Full,
+    /// answer to `Spectate`, names are `None` for free sides
+    Spectating {
+        left_name: Option<String>,
+        right_name: Option<String>,
+    },
+    /// board after simulation step `tick`
+    Snapshot {
+        tick: u32,
+        board: BoardState,
+    },
+    /// `winner` is `None` when match was abandoned by both players
+    MatchOver {
+        winner: Option<DiceKind>,
+    },
+}
+
+fn options() -> impl Options {
+    bincode::DefaultOptions::new().with_limit(MAX_PACKET_SIZE as u64)
+}
+
+pub fn encode<T: Serialize>(message: &T) -> Vec<u8> {
+    options()
+        .serialize(message)
+        .expect("protocol messages fit into a packet")
+}
+
+/// Returns `None` for malformed or oversized packets
+pub fn decode<T: DeserializeOwned>(packet: &[u8]) -> Option<T> {
+    options().deserialize(packet).ok()
+}
+
+/// Trims name to something safe to show in UI
+pub fn sanitize_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| !c.is_control())
+        .take(MAX_NAME_LENGTH)
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
@@ -0,0 +1,433 @@
+use std::collections::VecDeque;
+use std::io::ErrorKind;
+use std::net::{SocketAddr, UdpSocket};
+
+use bevy::prelude::*;
+
+use crate::gameplay::{BoardState, DiceHit, PlayerLost, SimulationDriver, SIMULATION_HZ};
+use crate::protocol::*;
+use crate::theme::Theme;
+use crate::utils::*;
+use crate::{GameState, LastWinner};
+
+/// Match hosted by dedicated `pong-server`, e.g.
+/// `bevy_pong --server 127.0.0.1:7100 --name Alice`,
+/// add `--spectate` to only watch.
+///
+/// Board is not simulated locally, it shows server snapshots slightly in the past
+/// and smoothly interpolated between them.
+pub struct RemotePlugin;
+
+impl Plugin for RemotePlugin {
+    fn build(&self, app: &mut App) {
+        let args: Vec<String> = std::env::args().collect();
+        if let Some(config) = RemoteConfig::from_args(&args) {
+            match RemoteSession::connect(&config) {
+                Ok(session) => {
+                    let role = if config.spectate {
+                        "spectator"
+                    } else {
+                        "player"
+                    };
+                    info!(
+                        "joining server {} as {} {}",
+                        config.server, role, config.name
+                    );
+                    app.insert_resource(session)
+                        .insert_resource(SimulationDriver::External);
+                }
+                Err(e) => error!("failed to connect to server: {}", e),
+            }
+        }
+
+        app.add_systems(
+            Update,
+            (receive_server_messages, send_remote_input)
+                .chain()
+                .run_if(resource_exists::<RemoteSession>()),
+        )
+        .add_systems(
+            Update,
+            apply_snapshots
+                .after(receive_server_messages)
+                .run_if(in_state(GameState::Gameplay).and_then(resource_exists::<RemoteSession>())),
+        )
+        .add_systems(
+            OnEnter(GameState::Gameplay),
+            spawn_spectator_hud.run_if(resource_exists::<RemoteSession>()),
+        )
+        .add_systems(
+            Update,
+            update_spectator_hud.run_if(resource_exists_and_changed::<RemoteSession>()),
+        )
+        .add_systems(
+            OnExit(GameState::Gameplay),
+            (
+                leave_server.run_if(resource_exists::<RemoteSession>()),
+                despawn_spectator_hud,
+            ),
+        );
+    }
+}
+
+/// ticks board is shown behind latest snapshot, covers late and lost packets
+const INTERPOLATION_DELAY_TICKS: f64 = 6.;
+/// playback jumps instead of catching up smoothly when off by more ticks
+const MAX_PLAYBACK_DRIFT_TICKS: f64 = 30.;
+/// secs between join retries
+const JOIN_INTERVAL: f32 = 0.25;
+/// secs between spectator keep-alives, players keep connection alive by inputs
+const KEEP_ALIVE_INTERVAL: f32 = 1.0;
+/// secs before asking again when server had no free place
+const FULL_RETRY_INTERVAL: f32 = 5.0;
+/// secs without packets after which server is considered gone
+const SERVER_TIMEOUT: f32 = 5.0;
+
+pub struct RemoteConfig {
+    pub server: SocketAddr,
+    pub name: String,
+    /// watch the match without controlling a dice
+    pub spectate: bool,
+}
+
+impl RemoteConfig {
+    /// Parses `--server <addr> [--name <name>] [--spectate]`
+    pub fn from_args(args: &[String]) -> Option<Self> {
+        let position = args.iter().position(|arg| arg == "--server")?;
+        let Some(server) = args.get(position + 1).and_then(|addr| addr.parse().ok()) else {
+            error!("usage: --server <addr> [--name <name>] [--spectate]");
+            return None;
+        };
+        let name = args
+            .iter()
+            .position(|arg| arg == "--name")
+            .and_then(|position| args.get(position + 1))
+            .map_or("Player".to_string(), |name| sanitize_name(name));
+        let spectate = args.iter().any(|arg| arg == "--spectate");
+        Some(Self {
+            server,
+            name,
+            spectate,
+        })
+    }
+}
+
+#[derive(Resource)]
+pub struct RemoteSession {
+    socket: UdpSocket,
+    server: SocketAddr,
+    name: String,
+    spectator: bool,
+    /// assigned by server on join
+    side: Option<DiceKind>,
+    /// player names as (left, right), set once server accepted spectator
+    watching: Option<(Option<String>, Option<String>)>,
+    input_seq: u32,
+    /// received snapshots by tick, oldest first
+    snapshots: VecDeque<(u32, BoardState)>,
+    /// tick currently shown, fractional between two snapshots
+    playback_tick: f64,
+    /// last board written to the world, to detect hits and points
+    shown: Option<BoardState>,
+    since_last_packet: f32,
+    since_join: f32,
+}
+
+impl RemoteSession {
+    pub fn connect(config: &RemoteConfig) -> std::io::Result<Self> {
+        let socket = UdpSocket::bind(("0.0.0.0", 0))?;
+        socket.set_nonblocking(true)?;
+        Ok(Self {
+            socket,
+            server: config.server,
+            name: config.name.clone(),
+            spectator: config.spectate,
+            side: None,
+            watching: None,
+            input_seq: 0,
+            snapshots: VecDeque::new(),
+            playback_tick: 0.0,
+            shown: None,
+            since_last_packet: 0.0,
+            since_join: JOIN_INTERVAL,
+        })
+    }
+
+    fn send(&self, message: &ClientMessage) {
+        if let Err(e) = self.socket.send_to(&encode(message), self.server) {
+            warn!("send to server failed: {}", e);
+        }
+    }
+
+    fn is_joined(&self) -> bool {
+        self.side.is_some() || self.watching.is_some()
+    }
+
+    fn join_message(&self) -> ClientMessage {
+        let name = self.name.clone();
+        if self.spectator {
+            ClientMessage::Spectate { name }
+        } else {
+            ClientMessage::Join { name }
+        }
+    }
+
+    fn push_snapshot(&mut self, tick: u32, board: BoardState) {
+        let latest = self.snapshots.back().map(|(t, _)| *t);
+        if latest.is_some_and(|latest| tick <= latest) {
+            // reordered or duplicated packet
+            return;
+        }
+        if latest.is_none() {
+            self.playback_tick = tick as f64 - INTERPOLATION_DELAY_TICKS;
+        }
+        self.snapshots.push_back((tick, board));
+    }
+
+    /// Board at `playback_tick`, drops snapshots no longer needed
+    fn interpolated_board(&mut self) -> Option<BoardState> {
+        let (latest, _) = self.snapshots.back()?;
+        let target = *latest as f64 - INTERPOLATION_DELAY_TICKS;
+        if (target - self.playback_tick).abs() > MAX_PLAYBACK_DRIFT_TICKS {
+            self.playback_tick = target;
+        }
+        while self
+            .snapshots
+            .get(1)
+            .is_some_and(|(tick, _)| (*tick as f64) <= self.playback_tick)
+        {
+            self.snapshots.pop_front();
+        }
+        let (from_tick, from) = self.snapshots.front()?;
+        match self.snapshots.get(1) {
+            Some((to_tick, to)) => {
+                let t = (self.playback_tick - *from_tick as f64) / (*to_tick - *from_tick) as f64;
+                Some(from.lerp(to, t.clamp(0.0, 1.0) as f32))
+            }
+            // ran out of snapshots, hold the last one
+            None => Some(from.clone()),
+        }
+    }
+}
+
+fn receive_server_messages(
+    time: Res<Time<Real>>,
+    state: Res<State<GameState>>,
+    mut session: ResMut<RemoteSession>,
+    mut last_winner: ResMut<LastWinner>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let dt = time.delta_seconds();
+    session.since_last_packet += dt;
+    let mut buf = [0u8; MAX_PACKET_SIZE];
+    loop {
+        let len = match session.socket.recv_from(&mut buf) {
+            Ok((len, from)) if from == session.server => len,
+            Ok(_) => continue,
+            Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+            Err(e) => {
+                warn!("receive from server failed: {}", e);
+                break;
+            }
+        };
+        let Some(message) = decode::<ServerMessage>(&buf[..len]) else {
+            continue;
+        };
+        session.since_last_packet = 0.0;
+        match message {
+            ServerMessage::Welcome { side } => {
+                if session.side.is_none() {
+                    info!("joined as {:?} player, waiting for opponent", side);
+                }
+                session.side = Some(side);
+            }
+            ServerMessage::Full => {
+                warn!("server is full, retrying later");
+                session.since_join = JOIN_INTERVAL - FULL_RETRY_INTERVAL;
+            }
+            ServerMessage::Spectating {
+                left_name,
+                right_name,
+            } => {
+                if session.watching.is_none() {
+                    info!("spectating, waiting for match");
+                }
+                let names = Some((left_name, right_name));
+                if session.watching != names {
+                    session.watching = names;
+                }
+            }
+            ServerMessage::Snapshot { tick, board } => {
+                session.push_snapshot(tick, board);
+                if *state.get() != GameState::Gameplay {
+                    next_state.set(GameState::Gameplay);
+                }
+            }
+            ServerMessage::MatchOver { winner } => {
+                last_winner.player = winner;
+                next_state.set(GameState::GameOver);
+            }
+        }
+    }
+
+    let joined = session.is_joined();
+    let join_interval = match (joined, session.spectator) {
+        (false, _) => Some(JOIN_INTERVAL),
+        (true, true) => Some(KEEP_ALIVE_INTERVAL),
+        (true, false) => None,
+    };
+    if let Some(interval) = join_interval {
+        session.since_join += dt;
+        if session.since_join >= interval {
+            session.since_join = 0.0;
+            session.send(&session.join_message());
+        }
+    }
+    if joined && session.since_last_packet > SERVER_TIMEOUT && *state.get() == GameState::Gameplay {
+        warn!("server timed out");
+        next_state.set(GameState::MainMenu);
+    }
+}
+
+fn send_remote_input(mut session: ResMut<RemoteSession>, keyboard: Res<Input<KeyCode>>) {
+    // spectators never send inputs, server would drop them anyway
+    if session.side.is_none() {
+        return;
+    }
+    let mut axis = 0.0;
+    if keyboard.any_pressed([KeyCode::W, KeyCode::Up]) {
+        axis += 1.0;
+    }
+    if keyboard.any_pressed([KeyCode::S, KeyCode::Down]) {
+        axis -= 1.0;
+    }
+    session.input_seq += 1;
+    let seq = session.input_seq;
+    session.send(&ClientMessage::Input { seq, axis });
+}
+
+/// Writes interpolated board to the world and replays events visuals depend on
+fn apply_snapshots(world: &mut World) {
+    let dt = world.resource::<Time<Real>>().delta_seconds_f64();
+    let board = world.resource_scope(|_, mut session: Mut<RemoteSession>| {
+        session.playback_tick += dt * SIMULATION_HZ;
+        let board = session.interpolated_board()?;
+        let shown = session.shown.replace(board.clone());
+        Some((shown, board))
+    });
+    let Some((shown, board)) = board else {
+        return;
+    };
+    board.write(world);
+
+    let Some(shown) = shown else {
+        return;
+    };
+    if shown.ball_velocity_x.signum() != board.ball_velocity_x.signum()
+        && shown.ball_velocity_x != 0.0
+        && board.ball_velocity_x != 0.0
+    {
+        if let Some(kind) = board.last_hit {
+            world.send_event(DiceHit {
+                kind,
+                bounce_angle: board.ball_velocity_y.abs().asin(),
+            });
+        }
+    }
+    if board.score_left > shown.score_left {
+        world.send_event(PlayerLost { is_right: true });
+    }
+    if board.score_right > shown.score_right {
+        world.send_event(PlayerLost { is_right: false });
+    }
+}
+
+fn leave_server(mut commands: Commands, session: Res<RemoteSession>) {
+    session.send(&ClientMessage::Leave);
+    commands.remove_resource::<RemoteSession>();
+    commands.insert_resource(SimulationDriver::Local);
+}
+
+#[derive(Component)]
+struct SpectatorHud;
+
+#[derive(Component)]
+struct PlayerNameText(DiceKind);
+
+fn spawn_spectator_hud(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    theme: Res<Theme>,
+    session: Res<RemoteSession>,
+) {
+    if !session.spectator {
+        return;
+    }
+    let text_style = TextStyle {
+        font: asset_server.load(FONT_PATH),
+        font_size: 48.0 * theme.font_scale,
+        color: theme.text,
+    };
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::SpaceBetween,
+                    padding: UiRect::all(Val::Percent(4.0)),
+                    ..default()
+                },
+                ..default()
+            },
+            SpectatorHud,
+        ))
+        .with_children(|commands| {
+            // names flank score text drawn in the middle of the upper score area
+            commands
+                .spawn(NodeBundle {
+                    style: Style {
+                        justify_content: JustifyContent::SpaceBetween,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|commands| {
+                    for kind in [DiceKind::Left, DiceKind::Right] {
+                        commands.spawn((
+                            TextBundle::from_section("", text_style.clone()),
+                            PlayerNameText(kind),
+                        ));
+                    }
+                });
+            commands.spawn(
+                TextBundle::from_section("SPECTATING", text_style.clone()).with_style(Style {
+                    align_self: AlignSelf::Center,
+                    ..default()
+                }),
+            );
+        });
+}
+
+fn update_spectator_hud(
+    session: Res<RemoteSession>,
+    mut name_texts: Query<(&mut Text, &PlayerNameText)>,
+) {
+    let Some((left_name, right_name)) = &session.watching else {
+        return;
+    };
+    for (mut text, name) in &mut name_texts {
+        let (player_name, fallback) = match name.0 {
+            DiceKind::Left => (left_name, "Left"),
+            DiceKind::Right => (right_name, "Right"),
+        };
+        text.sections[0].value = player_name.clone().unwrap_or(fallback.to_string());
+    }
+}
+
+fn despawn_spectator_hud(mut commands: Commands, huds: Query<Entity, With<SpectatorHud>>) {
+    for hud in &huds {
+        commands.entity(hud).despawn_recursive();
+    }
+}
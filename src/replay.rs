@@ -0,0 +1,508 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::asset::io::file::FileAssetReader;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use serde::{Deserialize, Serialize};
+
+use crate::gameplay::{
+    apply_dice_inputs, read_dice_inputs, reseed_match_rng, seed_match_rng, BoardConfig,
+    BrickLayout, Forces, GameMode, MatchSeed, ResumedMatch, SimSnapshot, SimulationDriver,
+    SimulationStep, SpeedAssist, TargetLayout, SIMULATION_HZ,
+};
+use crate::theme::Theme;
+use crate::utils::*;
+use crate::GameState;
+
+/// Records local matches and plays them back.
+///
+/// Replay holds only the starting conditions and per-tick inputs, playback
+/// simulates the match again, e.g. `bevy_pong --replay replays/match-1700000000.replay`.
+/// Space pauses, Left/Right seek, Up/Down change speed, Escape leaves.
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        let args: Vec<String> = std::env::args().collect();
+        if let Some(position) = args.iter().position(|arg| arg == "--replay") {
+            match args
+                .get(position + 1)
+                .map(|path| Replay::load(Path::new(path)))
+            {
+                Some(Ok(replay)) => {
+                    app.insert_resource(NextState(Some(GameState::Gameplay)));
+                    start_playback(&mut app.world, replay);
+                }
+                Some(Err(e)) => error!("failed to load replay: {}", e),
+                None => error!("usage: --replay <file>"),
+            }
+        }
+
+        app.add_systems(
+            OnEnter(GameState::Gameplay),
+            (
+                forget_last_replay.run_if(not(resource_exists::<ReplayPlayer>())),
+                start_recording.after(reseed_match_rng).run_if(
+                    resource_equals(SimulationDriver::Local)
+                        .and_then(not(resource_exists::<ResumedMatch>())),
+                ),
+                spawn_replay_hud.run_if(resource_exists::<ReplayPlayer>()),
+            ),
+        )
+        .add_systems(
+            OnExit(GameState::Gameplay),
+            (
+                save_recording.run_if(resource_exists::<ReplayRecorder>()),
+                end_playback.run_if(resource_exists::<ReplayPlayer>()),
+            ),
+        )
+        .add_systems(
+            SimulationStep,
+            record_inputs.run_if(resource_exists::<ReplayRecorder>()),
+        )
+        .add_systems(
+            FixedUpdate,
+            playback_tick
+                .run_if(in_state(GameState::Gameplay).and_then(resource_exists::<ReplayPlayer>())),
+        )
+        .add_systems(
+            Update,
+            (playback_controls, scrub_timeline, update_replay_hud)
+                .chain()
+                .run_if(in_state(GameState::Gameplay).and_then(resource_exists::<ReplayPlayer>())),
+        )
+        .add_systems(Update, replay_button_clicked);
+    }
+}
+
+/// bumped whenever simulation changes in a way old replays would play differently
+const REPLAY_VERSION: u16 = 8;
+/// directory with recorded matches, relative to asset base path
+const REPLAYS_DIR: &str = "replays";
+/// ticks between stored playback states, seeking simulates at most this many ticks
+const KEYFRAME_INTERVAL: u32 = 120;
+/// secs skipped by one seek key press
+const SEEK_STEP_SECS: f64 = 5.0;
+const PLAYBACK_SPEEDS: [f32; 5] = [0.25, 0.5, 1.0, 2.0, 4.0];
+/// index of normal speed in `PLAYBACK_SPEEDS`
+const NORMAL_SPEED: usize = 2;
+
+/// Everything needed to simulate a match again
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Replay {
+    version: u16,
+    /// seed of `GameRng`, decides all serves
+    seed: u64,
+    board: BoardConfig,
+    targets: TargetLayout,
+    bricks: BrickLayout,
+    forces: Forces,
+    mode: GameMode,
+    /// `SpeedAssist` the match was played with
+    ball_speed_factor: f32,
+    /// `Dice::axis_input` of (left, right) dice for every simulation step
+    inputs: Vec<(f32, f32)>,
+}
+
+impl Replay {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let bytes = fs::read(path).map_err(|e| e.to_string())?;
+        let replay: Replay = bincode::deserialize(&bytes).map_err(|e| e.to_string())?;
+        if replay.version != REPLAY_VERSION {
+            return Err(format!(
+                "replay version {} is not supported, expected {}",
+                replay.version, REPLAY_VERSION
+            ));
+        }
+        Ok(replay)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        let bytes = bincode::serialize(self).map_err(|e| e.to_string())?;
+        fs::write(path, bytes).map_err(|e| e.to_string())
+    }
+
+    pub fn ticks(&self) -> u32 {
+        self.inputs.len() as u32
+    }
+}
+
+/// Replay of the match being played
+#[derive(Clone, Resource)]
+pub struct ReplayRecorder {
+    pub replay: Replay,
+}
+
+/// File of the last recorded match, offered on game over screen
+#[derive(Resource)]
+pub struct LastReplay(pub PathBuf);
+
+#[derive(Resource)]
+pub struct ReplayPlayer {
+    replay: Replay,
+    /// next tick to simulate
+    tick: u32,
+    paused: bool,
+    speed_index: usize,
+    /// fractional ticks owed at current speed
+    pending_ticks: f32,
+    /// tick to jump to before next step
+    seek_to: Option<u32>,
+    /// states before given tick, ordered by tick
+    keyframes: Vec<(u32, SimSnapshot)>,
+}
+
+impl ReplayPlayer {
+    fn speed(&self) -> f32 {
+        PLAYBACK_SPEEDS[self.speed_index]
+    }
+
+    fn seek_by(&mut self, secs: f64) {
+        let target = self.seek_to.unwrap_or(self.tick) as f64 + secs * SIMULATION_HZ;
+        self.seek_to = Some(target.clamp(0.0, self.replay.ticks() as f64) as u32);
+    }
+}
+
+/// Prepares world to play `replay` once `GameState::Gameplay` is entered
+fn start_playback(world: &mut World, replay: Replay) {
+    info!(
+        "playing replay of {} secs",
+        replay.ticks() as f64 / SIMULATION_HZ
+    );
+    // board is spawned from config on entering gameplay, rest is set on first tick
+    world.insert_resource(replay.board.clone());
+    world.insert_resource(replay.targets.clone());
+    world.insert_resource(replay.bricks.clone());
+    world.insert_resource(replay.forces.clone());
+    world.insert_resource(replay.mode);
+    world.insert_resource(SimulationDriver::External);
+    world.insert_resource(ReplayPlayer {
+        replay,
+        tick: 0,
+        paused: false,
+        speed_index: NORMAL_SPEED,
+        pending_ticks: 0.0,
+        seek_to: None,
+        keyframes: Vec::new(),
+    });
+}
+
+fn end_playback(mut commands: Commands, huds: Query<Entity, With<ReplayHud>>) {
+    commands.remove_resource::<ReplayPlayer>();
+    commands.insert_resource(SimulationDriver::Local);
+    for hud in &huds {
+        commands.entity(hud).despawn_recursive();
+    }
+}
+
+/// Only local matches are recorded, others must not offer replay of an older one
+fn forget_last_replay(mut commands: Commands) {
+    commands.remove_resource::<LastReplay>();
+}
+
+fn start_recording(
+    mut commands: Commands,
+    board: Res<BoardConfig>,
+    targets: Res<TargetLayout>,
+    bricks: Res<BrickLayout>,
+    forces: Res<Forces>,
+    mode: Res<GameMode>,
+    seed: Res<MatchSeed>,
+) {
+    commands.insert_resource(ReplayRecorder {
+        replay: Replay {
+            version: REPLAY_VERSION,
+            seed: seed.0,
+            board: board.clone(),
+            targets: targets.clone(),
+            bricks: bricks.clone(),
+            forces: forces.clone(),
+            mode: *mode,
+            ball_speed_factor: 1.0,
+            inputs: Vec::new(),
+        },
+    });
+}
+
+fn record_inputs(world: &mut World) {
+    let inputs = read_dice_inputs(world);
+    let speed_assist = world.resource::<SpeedAssist>().0;
+    let mut recorder = world.resource_mut::<ReplayRecorder>();
+    // assist is applied on entering gameplay, after recording started
+    recorder.replay.ball_speed_factor = speed_assist;
+    recorder.replay.inputs.push(inputs);
+}
+
+fn save_recording(mut commands: Commands, recorder: Res<ReplayRecorder>) {
+    commands.remove_resource::<ReplayRecorder>();
+    if recorder.replay.inputs.is_empty() {
+        return;
+    }
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    let path = FileAssetReader::get_base_path()
+        .join(REPLAYS_DIR)
+        .join(format!("match-{}.replay", timestamp));
+    match recorder.replay.save(&path) {
+        Ok(()) => {
+            info!("replay saved to {}", path.display());
+            commands.insert_resource(LastReplay(path));
+        }
+        Err(e) => error!("failed to save replay {}: {}", path.display(), e),
+    }
+}
+
+/// Simulates single recorded tick, storing keyframes on the way
+fn step_replay(world: &mut World, player: &mut ReplayPlayer) {
+    let tick = player.tick;
+    let is_keyframe_tick = tick.is_multiple_of(KEYFRAME_INTERVAL);
+    let is_stored = player.keyframes.last().is_some_and(|(t, _)| *t >= tick);
+    if is_keyframe_tick && !is_stored {
+        if let Some(snapshot) = SimSnapshot::capture(world) {
+            player.keyframes.push((tick, snapshot));
+        }
+    }
+    let (left, right) = player.replay.inputs[tick as usize];
+    apply_dice_inputs(world, left, right);
+    world.run_schedule(SimulationStep);
+    // match end must not leave the replay, it stays on last frame instead
+    world.resource_mut::<NextState<GameState>>().0 = None;
+    player.tick += 1;
+}
+
+fn seek(world: &mut World, player: &mut ReplayPlayer, target: u32) {
+    let target = target.min(player.replay.ticks());
+    if target < player.tick {
+        let keyframe = player
+            .keyframes
+            .iter()
+            .rev()
+            .find(|(tick, _)| *tick <= target);
+        let Some((tick, snapshot)) = keyframe else {
+            return;
+        };
+        snapshot.restore(world);
+        player.tick = *tick;
+    }
+    while player.tick < target {
+        step_replay(world, player);
+    }
+}
+
+fn playback_tick(world: &mut World) {
+    world.resource_scope(|world, mut player: Mut<ReplayPlayer>| {
+        if player.tick == 0 && player.keyframes.is_empty() {
+            // overrides values set for a live match on entering gameplay
+            seed_match_rng(world, player.replay.seed);
+            world.insert_resource(SpeedAssist(player.replay.ball_speed_factor));
+        }
+        if let Some(target) = player.seek_to.take() {
+            seek(world, &mut player, target);
+            player.pending_ticks = 0.0;
+        }
+        if player.paused {
+            return;
+        }
+        player.pending_ticks += player.speed();
+        while player.pending_ticks >= 1.0 && player.tick < player.replay.ticks() {
+            player.pending_ticks -= 1.0;
+            step_replay(world, &mut player);
+        }
+        if player.tick >= player.replay.ticks() {
+            player.paused = true;
+            player.pending_ticks = 0.0;
+        }
+    });
+}
+
+fn playback_controls(
+    keyboard: Res<Input<KeyCode>>,
+    mut player: ResMut<ReplayPlayer>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if keyboard.just_pressed(KeyCode::Space) {
+        if player.paused && player.tick >= player.replay.ticks() {
+            // play again from start
+            player.seek_to = Some(0);
+        }
+        player.paused = !player.paused;
+    }
+    if keyboard.just_pressed(KeyCode::Left) {
+        player.seek_by(-SEEK_STEP_SECS);
+    }
+    if keyboard.just_pressed(KeyCode::Right) {
+        player.seek_by(SEEK_STEP_SECS);
+    }
+    if keyboard.just_pressed(KeyCode::Up) {
+        player.speed_index = (player.speed_index + 1).min(PLAYBACK_SPEEDS.len() - 1);
+    }
+    if keyboard.just_pressed(KeyCode::Down) {
+        player.speed_index = player.speed_index.saturating_sub(1);
+    }
+    if keyboard.just_pressed(KeyCode::Escape) {
+        next_state.set(GameState::MainMenu);
+    }
+}
+
+#[derive(Component)]
+struct ReplayHud;
+
+#[derive(Component)]
+struct ReplayStatusText;
+
+/// Clickable bar, pressing or dragging over it seeks
+#[derive(Component)]
+struct ReplayTimeline;
+
+#[derive(Component)]
+struct ReplayTimelineFill;
+
+fn spawn_replay_hud(mut commands: Commands, asset_server: Res<AssetServer>, theme: Res<Theme>) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::FlexEnd,
+                    padding: UiRect::all(Val::Percent(3.0)),
+                    ..default()
+                },
+                ..default()
+            },
+            ReplayHud,
+        ))
+        .with_children(|commands| {
+            commands.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font: asset_server.load(FONT_PATH),
+                        font_size: 40.0 * theme.font_scale,
+                        color: theme.text,
+                    },
+                )
+                .with_style(Style {
+                    align_self: AlignSelf::Center,
+                    margin: UiRect::bottom(Val::Percent(1.0)),
+                    ..default()
+                }),
+                ReplayStatusText,
+            ));
+            commands
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Percent(100.0),
+                            height: Val::Px(24.0),
+                            ..default()
+                        },
+                        background_color: theme.button_secondary.into(),
+                        ..default()
+                    },
+                    ReplayTimeline,
+                ))
+                .with_children(|commands| {
+                    commands.spawn((
+                        NodeBundle {
+                            style: Style {
+                                width: Val::Percent(0.0),
+                                height: Val::Percent(100.0),
+                                ..default()
+                            },
+                            background_color: theme.button_primary.into(),
+                            ..default()
+                        },
+                        ReplayTimelineFill,
+                    ));
+                });
+        });
+}
+
+fn scrub_timeline(
+    mut player: ResMut<ReplayPlayer>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    timelines: Query<(&Interaction, &Node, &GlobalTransform), With<ReplayTimeline>>,
+) {
+    let Ok((interaction, node, transform)) = timelines.get_single() else {
+        return;
+    };
+    if !matches!(interaction, Interaction::Pressed) {
+        return;
+    }
+    let Some(cursor) = windows.get_single().ok().and_then(|w| w.cursor_position()) else {
+        return;
+    };
+    let width = node.size().x;
+    if width <= 0.0 {
+        return;
+    }
+    let left = transform.translation().x - width / 2.0;
+    let fraction = ((cursor.x - left) / width).clamp(0.0, 1.0);
+    let target = (fraction * player.replay.ticks() as f32) as u32;
+    if player.seek_to != Some(target) && player.tick != target {
+        player.seek_to = Some(target);
+    }
+}
+
+fn format_time(ticks: u32) -> String {
+    let secs = (ticks as f64 / SIMULATION_HZ) as u32;
+    format!("{:02}:{:02}", secs / 60, secs % 60)
+}
+
+fn update_replay_hud(
+    player: Res<ReplayPlayer>,
+    mut texts: Query<&mut Text, With<ReplayStatusText>>,
+    mut fills: Query<&mut Style, With<ReplayTimelineFill>>,
+) {
+    if !player.is_changed() {
+        return;
+    }
+    let state = if player.paused { "PAUSED" } else { "PLAYING" };
+    if let Ok(mut text) = texts.get_single_mut() {
+        text.sections[0].value = format!(
+            "{} {}x  {} / {}",
+            state,
+            player.speed(),
+            format_time(player.tick),
+            format_time(player.replay.ticks())
+        );
+    }
+    if let Ok(mut style) = fills.get_single_mut() {
+        let fraction = player.tick as f32 / player.replay.ticks().max(1) as f32;
+        style.width = Val::Percent(fraction * 100.0);
+    }
+}
+
+/// Marks button that plays `LastReplay`
+#[derive(Component)]
+pub struct ReplayButton;
+
+fn replay_button_clicked(
+    interactions: Query<&Interaction, (With<ReplayButton>, Changed<Interaction>)>,
+    last_replay: Option<Res<LastReplay>>,
+    mut commands: Commands,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let Some(last_replay) = last_replay else {
+        return;
+    };
+    for interaction in &interactions {
+        if matches!(interaction, Interaction::Pressed) {
+            match Replay::load(&last_replay.0) {
+                Ok(replay) => {
+                    commands.add(|world: &mut World| start_playback(world, replay));
+                    next_state.set(GameState::Gameplay);
+                }
+                Err(e) => error!("failed to load replay: {}", e),
+            }
+        }
+    }
+}
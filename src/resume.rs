@@ -0,0 +1,181 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use bevy::app::AppExit;
+use bevy::asset::io::file::FileAssetReader;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::gameplay::{
+    GameMode, GameRng, MatchSeed, MatchSim, ResumedMatch, RngState, SimulationDriver,
+};
+use crate::history::MatchRecorder;
+use crate::profile::MatchTracker;
+use crate::replay::{Replay, ReplayRecorder};
+use crate::sim::PongSim;
+use crate::survival::SurvivalRun;
+use crate::tournament::TournamentMatch;
+use crate::GameState;
+
+/// Keeps an unfinished local match in `match.save` to continue it later.
+///
+/// Escape leaves the match to main menu, closing the window mid-match saves it too.
+/// Practice is not kept, drills are short.
+/// Main menu offers "Continue" while the file exists, the file is removed once continued.
+pub struct ResumePlugin;
+
+impl Plugin for ResumePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            suspend_match.run_if(
+                in_state(GameState::Gameplay)
+                    .and_then(resource_equals(SimulationDriver::Local))
+                    .and_then(|mode: Res<GameMode>| !mode.is_practice()),
+            ),
+        )
+        .add_systems(
+            Last,
+            save_match.run_if(
+                in_state(GameState::Gameplay)
+                    .and_then(resource_equals(SimulationDriver::Local))
+                    .and_then(|mode: Res<GameMode>| !mode.is_practice())
+                    .and_then(on_event::<AppExit>()),
+            ),
+        );
+    }
+}
+
+/// bumped whenever saved state changes in a way older saves can not be read
+const SAVE_VERSION: u16 = 7;
+/// saved match file, relative to asset base path
+const SAVE_FILE: &str = "match.save";
+
+pub fn saved_match_path() -> PathBuf {
+    FileAssetReader::get_base_path().join(SAVE_FILE)
+}
+
+/// Everything to continue a match exactly where it was left
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SavedMatch {
+    version: u16,
+    mode: GameMode,
+    /// board, ball, dices, score and pending serve
+    sim: PongSim,
+    rng: RngState,
+    seed: u64,
+    /// replay continues recording, it still starts from the match seed
+    replay: Option<Replay>,
+    tracker: Option<MatchTracker>,
+    recorder: Option<MatchRecorder>,
+    survival: Option<SurvivalRun>,
+    tournament: Option<TournamentMatch>,
+}
+
+impl SavedMatch {
+    /// Returns `None` when board is not spawned
+    pub fn capture(world: &World) -> Option<Self> {
+        let sim = world.get_resource::<MatchSim>()?.0.clone();
+        Some(Self {
+            version: SAVE_VERSION,
+            mode: *world.resource::<GameMode>(),
+            sim,
+            rng: world.resource::<GameRng>().state(),
+            seed: world.resource::<MatchSeed>().0,
+            replay: world
+                .get_resource::<ReplayRecorder>()
+                .map(|recorder| recorder.replay.clone()),
+            tracker: world.get_resource::<MatchTracker>().cloned(),
+            recorder: world.get_resource::<MatchRecorder>().cloned(),
+            survival: world.get_resource::<SurvivalRun>().cloned(),
+            tournament: world.get_resource::<TournamentMatch>().cloned(),
+        })
+    }
+
+    /// Prepares world to continue the match once `GameState::Gameplay` is entered
+    pub fn restore(self, world: &mut World) {
+        world.insert_resource(self.sim.board.clone());
+        world.insert_resource(self.mode);
+        world.insert_resource(ResumedMatch {
+            rng: GameRng::from_state(&self.rng),
+            seed: self.seed,
+            sim: self.sim,
+        });
+        insert_or_remove(world, self.replay.map(|replay| ReplayRecorder { replay }));
+        insert_or_remove(world, self.tracker);
+        insert_or_remove(world, self.recorder);
+        insert_or_remove(world, self.survival);
+        insert_or_remove(world, self.tournament);
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let bytes = fs::read(path).map_err(|e| e.to_string())?;
+        let saved: SavedMatch = bincode::deserialize(&bytes).map_err(|e| e.to_string())?;
+        if saved.version != SAVE_VERSION {
+            return Err(format!(
+                "saved match version {} is not supported, expected {}",
+                saved.version, SAVE_VERSION
+            ));
+        }
+        Ok(saved)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let bytes = bincode::serialize(self).map_err(|e| e.to_string())?;
+        fs::write(path, bytes).map_err(|e| e.to_string())
+    }
+}
+
+fn insert_or_remove<R: Resource>(world: &mut World, resource: Option<R>) {
+    match resource {
+        Some(resource) => world.insert_resource(resource),
+        None => {
+            world.remove_resource::<R>();
+        }
+    }
+}
+
+fn save_match(world: &World) {
+    let Some(saved) = SavedMatch::capture(world) else {
+        return;
+    };
+    let path = saved_match_path();
+    match saved.save(&path) {
+        Ok(()) => info!("match saved to {}", path.display()),
+        Err(e) => error!("failed to save match {}: {}", path.display(), e),
+    }
+}
+
+fn suspend_match(world: &mut World) {
+    if !world
+        .resource::<Input<KeyCode>>()
+        .just_pressed(KeyCode::Escape)
+    {
+        return;
+    }
+    save_match(world);
+    // unfinished replay is kept in the save, not in replays
+    world.remove_resource::<ReplayRecorder>();
+    world
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::MainMenu);
+}
+
+/// Loads saved match into world and removes its file, `GameState::Gameplay` continues it
+pub fn continue_saved_match(world: &mut World) {
+    let path = saved_match_path();
+    let saved = SavedMatch::load(&path);
+    // broken or outdated save would stay offered forever
+    if let Err(e) = fs::remove_file(&path) {
+        warn!("failed to remove saved match {}: {}", path.display(), e);
+    }
+    match saved {
+        Ok(saved) => {
+            saved.restore(world);
+            world
+                .resource_mut::<NextState<GameState>>()
+                .set(GameState::Gameplay);
+        }
+        Err(e) => error!("failed to load saved match {}: {}", path.display(), e),
+    }
+}
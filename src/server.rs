@@ -0,0 +1,326 @@
+use std::io::ErrorKind;
+use std::net::{SocketAddr, UdpSocket};
+
+use bevy::prelude::*;
+
+use crate::gameplay::{
+    apply_dice_inputs, reseed_match_rng, BoardState, SimulationDriver, SimulationStep,
+};
+use crate::protocol::*;
+use crate::utils::*;
+use crate::{GameState, LastWinner};
+
+/// Authoritative match host for two remote clients, runs without rendering.
+///
+/// While in `GameState::MainMenu` server waits for players, the match starts
+/// once both sides are taken and server returns to waiting after it ends.
+/// Spectators may connect any time, every snapshot holds the whole board.
+///
+/// Expects already bound `GameServer` resource, so binary can report a taken port.
+pub struct ServerPlugin;
+
+impl Plugin for ServerPlugin {
+    fn build(&self, app: &mut App) {
+        let server = app.world.resource::<GameServer>();
+        info!("server listening on port {}", server.local_port());
+
+        app.insert_resource(SimulationDriver::External)
+            .add_systems(Update, (receive_messages, drop_timed_out_clients).chain())
+            .add_systems(
+                Update,
+                start_match
+                    .after(drop_timed_out_clients)
+                    .run_if(in_state(GameState::MainMenu)),
+            )
+            .add_systems(
+                Update,
+                award_abandoned_match
+                    .after(drop_timed_out_clients)
+                    .run_if(in_state(GameState::Gameplay)),
+            )
+            .add_systems(
+                FixedUpdate,
+                server_tick.run_if(in_state(GameState::Gameplay)),
+            )
+            .add_systems(OnEnter(GameState::Gameplay), reseed_match_rng)
+            .add_systems(OnEnter(GameState::GameOver), finish_match);
+    }
+}
+
+/// secs without packets after which client is considered gone
+const CLIENT_TIMEOUT: f32 = 5.0;
+/// more spectators are turned away
+const MAX_SPECTATORS: usize = 16;
+
+struct RemoteClient {
+    addr: SocketAddr,
+    name: String,
+    side: DiceKind,
+    /// latest validated input
+    axis: f32,
+    /// highest input sequence number seen, older inputs are ignored
+    last_seq: Option<u32>,
+    since_last_packet: f32,
+}
+
+/// Receives snapshots but has no dice to control
+struct Spectator {
+    addr: SocketAddr,
+    name: String,
+    since_last_packet: f32,
+}
+
+#[derive(Resource)]
+pub struct GameServer {
+    socket: UdpSocket,
+    clients: Vec<RemoteClient>,
+    spectators: Vec<Spectator>,
+    /// simulation steps since match start
+    tick: u32,
+}
+
+impl GameServer {
+    pub fn bind(port: u16) -> std::io::Result<Self> {
+        let socket = UdpSocket::bind(("0.0.0.0", port))?;
+        socket.set_nonblocking(true)?;
+        Ok(Self {
+            socket,
+            clients: Vec::new(),
+            spectators: Vec::new(),
+            tick: 0,
+        })
+    }
+
+    /// Actual port, differs from requested one when bound to port 0
+    pub fn local_port(&self) -> u16 {
+        self.socket.local_addr().map_or(0, |addr| addr.port())
+    }
+
+    fn send(&self, addr: SocketAddr, message: &ServerMessage) {
+        if let Err(e) = self.socket.send_to(&encode(message), addr) {
+            warn!("server send to {} failed: {}", addr, e);
+        }
+    }
+
+    fn broadcast(&self, message: &ServerMessage) {
+        let packet = encode(message);
+        let players = self.clients.iter().map(|client| client.addr);
+        let spectators = self.spectators.iter().map(|spectator| spectator.addr);
+        for addr in players.chain(spectators) {
+            if let Err(e) = self.socket.send_to(&packet, addr) {
+                warn!("server send to {} failed: {}", addr, e);
+            }
+        }
+    }
+
+    fn free_side(&self) -> Option<DiceKind> {
+        [DiceKind::Left, DiceKind::Right]
+            .into_iter()
+            .find(|side| self.clients.iter().all(|client| client.side != *side))
+    }
+
+    fn handle_join(&mut self, addr: SocketAddr, name: &str, accepts_players: bool) {
+        if self
+            .spectators
+            .iter()
+            .any(|spectator| spectator.addr == addr)
+        {
+            return;
+        }
+        if let Some(client) = self.clients.iter().find(|client| client.addr == addr) {
+            // welcome got lost, client keeps asking
+            self.send(addr, &ServerMessage::Welcome { side: client.side });
+            return;
+        }
+        let side = self.free_side().filter(|_| accepts_players);
+        let Some(side) = side else {
+            self.send(addr, &ServerMessage::Full);
+            return;
+        };
+        let name = sanitize_name(name);
+        info!("{} joined from {} as {:?} player", name, addr, side);
+        self.clients.push(RemoteClient {
+            addr,
+            name,
+            side,
+            axis: 0.0,
+            last_seq: None,
+            since_last_packet: 0.0,
+        });
+        self.send(addr, &ServerMessage::Welcome { side });
+    }
+
+    fn handle_spectate(&mut self, addr: SocketAddr, name: &str) {
+        let is_player = self.clients.iter().any(|client| client.addr == addr);
+        let is_known = self
+            .spectators
+            .iter()
+            .any(|spectator| spectator.addr == addr);
+        if is_player || (!is_known && self.spectators.len() >= MAX_SPECTATORS) {
+            self.send(addr, &ServerMessage::Full);
+            return;
+        }
+        if !is_known {
+            let name = sanitize_name(name);
+            info!("{} spectates from {}", name, addr);
+            self.spectators.push(Spectator {
+                addr,
+                name,
+                since_last_packet: 0.0,
+            });
+        }
+        let player_name = |side: DiceKind| {
+            self.clients
+                .iter()
+                .find(|client| client.side == side)
+                .map(|client| client.name.clone())
+        };
+        let spectating = ServerMessage::Spectating {
+            left_name: player_name(DiceKind::Left),
+            right_name: player_name(DiceKind::Right),
+        };
+        self.send(addr, &spectating);
+    }
+
+    fn handle_input(&mut self, addr: SocketAddr, seq: u32, axis: f32) {
+        let Some(client) = self.clients.iter_mut().find(|client| client.addr == addr) else {
+            return;
+        };
+        if client.last_seq.is_some_and(|last| seq <= last) || !axis.is_finite() {
+            return;
+        }
+        client.last_seq = Some(seq);
+        // never trust the wire, dice can not move faster than from keyboard
+        client.axis = axis.clamp(-1.0, 1.0);
+    }
+
+    fn handle_leave(&mut self, addr: SocketAddr) {
+        if let Some(index) = self.clients.iter().position(|client| client.addr == addr) {
+            let client = self.clients.remove(index);
+            info!("{} left", client.name);
+        }
+        self.spectators.retain(|spectator| {
+            let stays = spectator.addr != addr;
+            if !stays {
+                info!("{} stopped spectating", spectator.name);
+            }
+            stays
+        });
+    }
+
+    fn input(&self, side: DiceKind) -> f32 {
+        self.clients
+            .iter()
+            .find(|client| client.side == side)
+            .map_or(0.0, |client| client.axis)
+    }
+}
+
+fn receive_messages(
+    time: Res<Time<Real>>,
+    state: Res<State<GameState>>,
+    mut server: ResMut<GameServer>,
+) {
+    let dt = time.delta_seconds();
+    for client in &mut server.clients {
+        client.since_last_packet += dt;
+    }
+    for spectator in &mut server.spectators {
+        spectator.since_last_packet += dt;
+    }
+
+    let accepts_players = *state.get() == GameState::MainMenu;
+    let mut buf = [0u8; MAX_PACKET_SIZE];
+    loop {
+        let (len, from) = match server.socket.recv_from(&mut buf) {
+            Ok(received) => received,
+            Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+            Err(e) => {
+                // e.g. ICMP port unreachable of a client that quit
+                debug!("server receive failed: {}", e);
+                continue;
+            }
+        };
+        let Some(message) = decode::<ClientMessage>(&buf[..len]) else {
+            continue;
+        };
+        if let Some(client) = server.clients.iter_mut().find(|c| c.addr == from) {
+            client.since_last_packet = 0.0;
+        }
+        if let Some(spectator) = server.spectators.iter_mut().find(|s| s.addr == from) {
+            spectator.since_last_packet = 0.0;
+        }
+        match message {
+            ClientMessage::Join { name } => server.handle_join(from, &name, accepts_players),
+            ClientMessage::Input { seq, axis } => server.handle_input(from, seq, axis),
+            ClientMessage::Spectate { name } => server.handle_spectate(from, &name),
+            ClientMessage::Leave => server.handle_leave(from),
+        }
+    }
+}
+
+fn drop_timed_out_clients(mut server: ResMut<GameServer>) {
+    server.clients.retain(|client| {
+        let alive = client.since_last_packet <= CLIENT_TIMEOUT;
+        if !alive {
+            warn!("{} timed out", client.name);
+        }
+        alive
+    });
+    server.spectators.retain(|spectator| {
+        let alive = spectator.since_last_packet <= CLIENT_TIMEOUT;
+        if !alive {
+            info!("spectator {} timed out", spectator.name);
+        }
+        alive
+    });
+}
+
+fn start_match(mut server: ResMut<GameServer>, mut next_state: ResMut<NextState<GameState>>) {
+    if server.clients.len() == 2 {
+        info!("match started");
+        server.tick = 0;
+        next_state.set(GameState::Gameplay);
+    }
+}
+
+/// Player who stays in the match wins it
+fn award_abandoned_match(
+    server: Res<GameServer>,
+    mut last_winner: ResMut<LastWinner>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if server.clients.len() < 2 {
+        last_winner.player = server.clients.first().map(|client| client.side);
+        next_state.set(GameState::GameOver);
+    }
+}
+
+fn server_tick(world: &mut World) {
+    let server = world.resource::<GameServer>();
+    let (left, right) = (server.input(DiceKind::Left), server.input(DiceKind::Right));
+    apply_dice_inputs(world, left, right);
+    world.run_schedule(SimulationStep);
+
+    let Some(board) = BoardState::read(world) else {
+        return;
+    };
+    let mut server = world.resource_mut::<GameServer>();
+    server.tick += 1;
+    let tick = server.tick;
+    server.broadcast(&ServerMessage::Snapshot { tick, board });
+}
+
+fn finish_match(
+    mut server: ResMut<GameServer>,
+    last_winner: Res<LastWinner>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    info!("match won by {:?}", last_winner.player);
+    server.broadcast(&ServerMessage::MatchOver {
+        winner: last_winner.player,
+    });
+    server.clients.clear();
+    server.spectators.clear();
+    next_state.set(GameState::MainMenu);
+}
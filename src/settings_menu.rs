@@ -0,0 +1,273 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+
+use crate::accessibility::{AccessibilitySettings, ColorVision};
+use crate::breakout::BrickLibrary;
+use crate::camera_fx::{EffectsSettings, EFFECT_INTENSITIES};
+use crate::forces::ForcesLibrary;
+use crate::targets::TargetLibrary;
+use crate::theme::{Theme, ThemeLibrary};
+use crate::utils::*;
+use crate::GameState;
+
+pub struct SettingsMenuPlugin;
+
+#[derive(Component)]
+pub struct SettingsUIRoot;
+
+#[derive(Component)]
+pub struct BackButton;
+
+/// Button cycling through values of one setting
+#[derive(Clone, Copy, Component)]
+pub enum SettingsOption {
+    Theme,
+    Targets,
+    Bricks,
+    Forces,
+    EffectsIntensity,
+    ReduceMotion,
+    ColorVision,
+    Outlines,
+    LargeText,
+    SlowMotion,
+}
+
+/// Resources modified from settings screen
+#[derive(SystemParam)]
+pub struct SettingsResources<'w> {
+    effects: ResMut<'w, EffectsSettings>,
+    themes: ResMut<'w, ThemeLibrary>,
+    targets: ResMut<'w, TargetLibrary>,
+    bricks: ResMut<'w, BrickLibrary>,
+    forces: ResMut<'w, ForcesLibrary>,
+    accessibility: ResMut<'w, AccessibilitySettings>,
+}
+
+fn on_off(value: bool) -> &'static str {
+    if value {
+        "On"
+    } else {
+        "Off"
+    }
+}
+
+impl SettingsOption {
+    fn label(&self, settings: &SettingsResources) -> String {
+        let effects = &settings.effects;
+        match self {
+            SettingsOption::Theme => format!("Theme: {}", settings.themes.selected().name),
+            SettingsOption::Targets => {
+                let value = settings
+                    .targets
+                    .selected()
+                    .map_or("Off", |layout| layout.name.as_str());
+                format!("Targets: {}", value)
+            }
+            SettingsOption::Bricks => {
+                let value = settings
+                    .bricks
+                    .selected()
+                    .map_or("None", |layout| layout.name.as_str());
+                format!("Bricks: {}", value)
+            }
+            SettingsOption::Forces => {
+                let value = settings
+                    .forces
+                    .selected()
+                    .map_or("Off", |forces| forces.name.as_str());
+                format!("Forces: {}", value)
+            }
+            SettingsOption::EffectsIntensity => {
+                format!("Effects: {}%", (effects.intensity * 100.).round())
+            }
+            SettingsOption::ReduceMotion => {
+                let value = if effects.reduce_motion {
+                    "Reduced"
+                } else {
+                    "Full"
+                };
+                format!("Motion: {}", value)
+            }
+            SettingsOption::ColorVision => {
+                let value = match settings.accessibility.color_vision {
+                    ColorVision::Normal => "Normal",
+                    ColorVision::Deuteranopia => "Deutan",
+                    ColorVision::Protanopia => "Protan",
+                    ColorVision::Tritanopia => "Tritan",
+                };
+                format!("Colours: {}", value)
+            }
+            SettingsOption::Outlines => {
+                format!("Outlines: {}", on_off(settings.accessibility.outlines))
+            }
+            SettingsOption::LargeText => {
+                format!("Large Text: {}", on_off(settings.accessibility.large_text))
+            }
+            SettingsOption::SlowMotion => {
+                format!("Slow Ball: {}", on_off(settings.accessibility.slow_motion))
+            }
+        }
+    }
+
+    fn advance(&self, settings: &mut SettingsResources) {
+        let effects = &mut settings.effects;
+        match self {
+            SettingsOption::Theme => settings.themes.select_next(),
+            SettingsOption::Targets => settings.targets.select_next(),
+            SettingsOption::Bricks => settings.bricks.select_next(),
+            SettingsOption::Forces => settings.forces.select_next(),
+            SettingsOption::EffectsIntensity => {
+                let current = EFFECT_INTENSITIES
+                    .iter()
+                    .position(|&v| v == effects.intensity)
+                    .unwrap_or(0);
+                effects.intensity = EFFECT_INTENSITIES[(current + 1) % EFFECT_INTENSITIES.len()];
+            }
+            SettingsOption::ReduceMotion => {
+                effects.reduce_motion = !effects.reduce_motion;
+            }
+            SettingsOption::ColorVision => {
+                let accessibility = &mut settings.accessibility;
+                accessibility.color_vision = accessibility.color_vision.next();
+            }
+            SettingsOption::Outlines => {
+                settings.accessibility.outlines = !settings.accessibility.outlines;
+            }
+            SettingsOption::LargeText => {
+                settings.accessibility.large_text = !settings.accessibility.large_text;
+            }
+            SettingsOption::SlowMotion => {
+                settings.accessibility.slow_motion = !settings.accessibility.slow_motion;
+            }
+        }
+    }
+}
+
+const SETTINGS_OPTIONS: [SettingsOption; 10] = [
+    SettingsOption::Theme,
+    SettingsOption::Targets,
+    SettingsOption::Bricks,
+    SettingsOption::Forces,
+    SettingsOption::ColorVision,
+    SettingsOption::Outlines,
+    SettingsOption::LargeText,
+    SettingsOption::SlowMotion,
+    SettingsOption::EffectsIntensity,
+    SettingsOption::ReduceMotion,
+];
+
+impl Plugin for SettingsMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Settings), spawn_menu)
+            .add_systems(OnExit(GameState::Settings), despawn_menu)
+            .add_systems(
+                Update,
+                (
+                    option_button_clicked,
+                    back_button_clicked,
+                    // rebuild with new colours after theme switch
+                    (despawn_menu, spawn_menu).chain().run_if(
+                        in_state(GameState::Settings).and_then(resource_changed::<Theme>()),
+                    ),
+                ),
+            );
+    }
+}
+
+fn spawn_menu(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    theme: Res<Theme>,
+    settings: SettingsResources,
+) {
+    let option_buttons: Vec<Entity> = SETTINGS_OPTIONS
+        .iter()
+        .map(|option| {
+            let button = spawn_sized_button(
+                &mut commands,
+                &asset_server,
+                &theme,
+                &option.label(&settings),
+                theme.button,
+                ButtonSize::Compact,
+            );
+            commands.entity(button).insert(*option);
+            button
+        })
+        .collect();
+    // options no longer fit below each other
+    let option_grid = spawn_button_grid(&mut commands, &option_buttons);
+
+    let back_button = spawn_sized_button(
+        &mut commands,
+        &asset_server,
+        &theme,
+        "Back",
+        theme.button_secondary,
+        ButtonSize::Compact,
+    );
+    commands.entity(back_button).insert(BackButton);
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            ..default()
+        })
+        .insert(SettingsUIRoot)
+        .with_children(|commands| {
+            commands.spawn(TextBundle {
+                style: Style {
+                    align_self: AlignSelf::Center,
+                    margin: UiRect::all(Val::Percent(3.0)),
+                    ..default()
+                },
+                text: Text::from_section(
+                    "SETTINGS",
+                    TextStyle {
+                        font: asset_server.load(FONT_PATH),
+                        font_size: 96.0 * theme.font_scale,
+                        color: theme.text,
+                    },
+                ),
+                ..default()
+            });
+        })
+        .add_child(option_grid)
+        .add_child(back_button);
+}
+
+fn despawn_menu(mut commands: Commands, menu_root: Query<Entity, With<SettingsUIRoot>>) {
+    let root_entity = menu_root.single();
+    commands.entity(root_entity).despawn_recursive();
+}
+
+fn option_button_clicked(
+    interactions: Query<(&Interaction, &SettingsOption, &Children), Changed<Interaction>>,
+    mut texts: Query<&mut Text>,
+    mut settings: SettingsResources,
+) {
+    for (interaction, option, children) in &interactions {
+        if matches!(interaction, Interaction::Pressed) {
+            option.advance(&mut settings);
+            set_button_text(children, &mut texts, option.label(&settings));
+        }
+    }
+}
+
+fn back_button_clicked(
+    interactions: Query<&Interaction, (With<BackButton>, Changed<Interaction>)>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for interaction in &interactions {
+        if matches!(interaction, Interaction::Pressed) {
+            next_state.set(GameState::MainMenu);
+        }
+    }
+}
@@ -0,0 +1,943 @@
+//! Pong rules in plain Rust, without any engine types.
+//!
+//! `GameplayPlugin` owns one `PongSim` per match and mirrors it into the ECS,
+//! tests, tools and the server may step it directly.
+
+use std::f32::consts::PI;
+
+use rand::distributions::{Distribution, Uniform};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+pub const MAX_BOUNCE_ANGLE: f32 = 5. * PI / 12.;
+
+/// Dice travel per sec at full input
+const INPUT_FACTOR: f32 = 1000.;
+
+/// Ball speed gained per return in survival, as share of board ball speed
+const SURVIVAL_SPEED_UP: f32 = 0.05;
+/// Limit of `Ball::speed_bonus`, faster balls are not fun to chase anymore
+const MAX_SPEED_BONUS: f32 = 2.;
+/// Least horizontal velocity forces leave the ball with, it always reaches the other side
+const MIN_CROSSING_VELOCITY: f32 = 0.3;
+/// Limit of ball velocity length forces may build up, 1 is a ball without forces
+const MAX_FORCED_VELOCITY: f32 = 1.6;
+
+#[derive(Clone, Debug, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DiceKind {
+    Left,
+    Right,
+}
+
+impl DiceKind {
+    pub fn opponent(self) -> DiceKind {
+        match self {
+            DiceKind::Left => DiceKind::Right,
+            DiceKind::Right => DiceKind::Left,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BoardConfig {
+    /// board dimension from left to right
+    width: f32,
+    /// board dimensions from top to bottom
+    height: f32,
+    /// dice visual thickness
+    dice_width: f32,
+    /// area of dice hit surface
+    dice_length: f32,
+    /// offset from board edge to dice axis
+    dice_offset: f32,
+    /// speed of ball travel
+    ball_speed: f32,
+    /// visual radius of ball
+    ball_radius: f32,
+    /// visual width of surrounding walls
+    border_width: f32,
+    /// space reserved above and below the walls, score is shown in the upper one
+    score_area_height: f32,
+    /// space kept free left and right of the walls
+    screen_margin: f32,
+    /// score for one player to win the game
+    winning_score: usize,
+    /// secs before ball is launched
+    start_delay: f32,
+}
+
+impl Default for BoardConfig {
+    fn default() -> Self {
+        Self {
+            ball_speed: 400.,
+            width: 1000.,
+            height: 700.,
+            dice_width: 40.,
+            dice_length: 150.,
+            dice_offset: 100.,
+            ball_radius: 20.,
+            border_width: 20.,
+            score_area_height: 130.,
+            screen_margin: 30.,
+            winning_score: 10,
+            start_delay: 1.5,
+        }
+    }
+}
+
+impl BoardConfig {
+    /// Board of given dimensions, walls, margins and match rules are default
+    pub fn new(
+        width: f32,
+        height: f32,
+        dice_length: f32,
+        dice_offset: f32,
+        ball_radius: f32,
+        ball_speed: f32,
+    ) -> Self {
+        Self {
+            width,
+            height,
+            dice_length,
+            dice_offset,
+            ball_radius,
+            ball_speed,
+            ..Self::default()
+        }
+    }
+
+    /// world area that has to stay visible regardless of window size
+    pub fn world_size(&self) -> (f32, f32) {
+        (
+            self.width + self.border_width * 2. + self.screen_margin * 2.,
+            self.height + self.border_width * 2. + self.score_area_height * 2.,
+        )
+    }
+
+    /// resting position of dice on x axis
+    pub fn dice_position_x(&self, kind: DiceKind) -> f32 {
+        match kind {
+            DiceKind::Left => {
+                -self.width / 2. + self.dice_offset - self.ball_radius - self.dice_width / 2.
+            }
+            DiceKind::Right => {
+                self.width / 2. - self.dice_offset + self.ball_radius + self.dice_width / 2.
+            }
+        }
+    }
+
+    pub fn max_dice_position(&self, is_top: bool) -> f32 {
+        let offset = self.height / 2. - self.dice_length / 2.;
+        if is_top {
+            offset
+        } else {
+            -offset
+        }
+    }
+
+    pub fn width(&self) -> f32 {
+        self.width
+    }
+
+    pub fn height(&self) -> f32 {
+        self.height
+    }
+
+    pub fn dice_width(&self) -> f32 {
+        self.dice_width
+    }
+
+    pub fn dice_length(&self) -> f32 {
+        self.dice_length
+    }
+
+    pub fn ball_speed(&self) -> f32 {
+        self.ball_speed
+    }
+
+    pub fn ball_radius(&self) -> f32 {
+        self.ball_radius
+    }
+
+    pub fn border_width(&self) -> f32 {
+        self.border_width
+    }
+
+    pub fn score_area_height(&self) -> f32 {
+        self.score_area_height
+    }
+
+    /// x of the line dice hit surface lies on, ball crossing it is either hit or lost
+    pub fn dice_axis_x(&self, kind: DiceKind) -> f32 {
+        match kind {
+            DiceKind::Left => -self.width / 2. + self.dice_offset,
+            DiceKind::Right => self.width / 2. - self.dice_offset,
+        }
+    }
+
+    pub fn winning_score(&self) -> usize {
+        self.winning_score
+    }
+
+    pub fn start_delay(&self) -> f32 {
+        self.start_delay
+    }
+
+    /// Errs on board no match can be played on, e.g. one received from the network
+    pub fn check(&self) -> Result<(), String> {
+        let sizes = [
+            ("width", self.width),
+            ("height", self.height),
+            ("dice width", self.dice_width),
+            ("dice length", self.dice_length),
+            ("ball speed", self.ball_speed),
+            ("ball radius", self.ball_radius),
+        ];
+        for (name, value) in sizes {
+            if !value.is_finite() || value <= 0. {
+                return Err(format!("board {} must be positive", name));
+            }
+        }
+        let margins = [
+            ("dice offset", self.dice_offset),
+            ("border width", self.border_width),
+            ("score area height", self.score_area_height),
+            ("screen margin", self.screen_margin),
+            ("start delay", self.start_delay),
+        ];
+        for (name, value) in margins {
+            if !value.is_finite() || value < 0. {
+                return Err(format!("board {} must not be negative", name));
+            }
+        }
+        if self.dice_length >= self.height || self.ball_radius * 2. >= self.height {
+            return Err("dices and ball must be shorter than the board".to_string());
+        }
+        if self.dice_offset >= self.width / 2. {
+            return Err("dices must be placed within the board".to_string());
+        }
+        if self.winning_score == 0 {
+            return Err("board winning score must be positive".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Ball {
+    pub x: f32,
+    pub y: f32,
+    /// direction scaled by ball speed when moving, normalized off dices and serves
+    /// and bent by `PongSim::forces` in between
+    pub velocity_x: f32,
+    pub velocity_y: f32,
+    /// bounce off dice was applied, cleared once ball is back between dice axes
+    pub is_colliding_x: bool,
+    /// ball passed dice axis without a hit, round ends at the goal line
+    pub is_lost: bool,
+    /// dice that touched the ball last in current round
+    pub last_hit: Option<DiceKind>,
+    /// secs until serve, `None` while ball is in play
+    pub serve_delay: Option<f32>,
+    /// speed gained from dice hits in current round, as share of board ball speed
+    pub speed_bonus: f32,
+    /// `hit_offset` of the last dice hit in current round
+    pub hit_offset: Option<f32>,
+    /// indices of `PongSim::targets` already passed since the last dice hit
+    pub passed_targets: Vec<usize>,
+}
+
+impl Ball {
+    /// Ball resting in the middle, served after `delay` secs
+    pub fn waiting(delay: f32) -> Self {
+        Self {
+            serve_delay: Some(delay),
+            ..Self::default()
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SimEvent {
+    /// ball bounced off a dice, `bounce_angle` is absolute angle of the
+    /// outgoing ball relative to horizontal axis
+    DiceHit {
+        kind: DiceKind,
+        bounce_angle: f32,
+    },
+    PlayerLost {
+        is_right: bool,
+    },
+    /// ball hit by `kind` passed through target `zone`, worth `points` of bonus
+    TargetHit {
+        kind: DiceKind,
+        zone: usize,
+        points: usize,
+    },
+    /// ball bounced off `brick`, `kind` touched the ball last and scores if it broke
+    BrickHit {
+        kind: Option<DiceKind>,
+        brick: usize,
+        broken: bool,
+    },
+    /// last brick broke, round ends like after a goal
+    BricksCleared,
+}
+
+/// One match of Pong: board, dices, ball and score
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PongSim {
+    pub board: BoardConfig,
+    pub ball: Ball,
+    pub left_dice_y: f32,
+    pub right_dice_y: f32,
+    pub score_left: usize,
+    pub score_right: usize,
+    /// multiplier of ball speed, e.g. slow-motion assist
+    pub ball_speed_factor: f32,
+    /// side closed by a wall instead of a dice, ball always bounces back off
+    /// it. Every return then scores for the dice and the first miss ends the match.
+    pub wall: Option<DiceKind>,
+    /// added to `Ball::speed_bonus` on every dice hit
+    pub speed_up: f32,
+    /// fires balls back from the wall side instead of bouncing them
+    pub launcher: Option<Launcher>,
+    /// score never ends the match, e.g. practice
+    pub endless: bool,
+    /// zones awarding bonus points, mirrored for each side
+    pub targets: Vec<TargetZone>,
+    /// bonus points from targets, kept apart from the score
+    pub bonus_left: usize,
+    pub bonus_right: usize,
+    /// bricks every round starts with, empty outside breakout
+    pub brick_layout: Vec<Brick>,
+    /// bricks of the current round, broken ones stay with no hit points left
+    pub bricks: Vec<Brick>,
+    /// gravity, wind and wells acting on the ball in flight
+    pub forces: Forces,
+    /// secs since the match started, wind gusts follow it
+    pub elapsed_secs: f32,
+}
+
+/// Environmental forces on the ball, accelerations are in ball velocity per sec
+/// where 1 is the length of a ball velocity without forces
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Forces {
+    pub name: String,
+    /// pull towards the bottom border
+    pub gravity: f32,
+    pub wind: Option<Wind>,
+    pub wells: Vec<Well>,
+}
+
+/// Horizontal gusts swelling and turning around with a fixed period
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Wind {
+    /// strongest push, towards right dice first
+    pub strength: f32,
+    /// secs of one cycle of gusts to the right and to the left
+    pub period: f32,
+}
+
+/// Spot pulling the ball in, or pushing it away with negative `strength`.
+/// Positions are shares of the half board like `TargetZone`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Well {
+    /// centre, -1..1 of half width, positive towards right dice
+    pub x: f32,
+    /// centre, -1..1 of half height, positive upwards
+    pub y: f32,
+    /// reach, share of half height, pull fades to nothing at its edge
+    pub radius: f32,
+    /// pull at the centre
+    pub strength: f32,
+}
+
+impl Well {
+    pub fn centre(&self, board: &BoardConfig) -> (f32, f32) {
+        (self.x * board.width / 2., self.y * board.height / 2.)
+    }
+
+    pub fn reach(&self, board: &BoardConfig) -> f32 {
+        self.radius * board.height / 2.
+    }
+}
+
+impl Forces {
+    pub fn is_calm(&self) -> bool {
+        self.gravity == 0. && self.wind.is_none() && self.wells.is_empty()
+    }
+
+    /// Wind push after `elapsed_secs`, positive towards right dice
+    pub fn wind_at(&self, elapsed_secs: f32) -> f32 {
+        self.wind.as_ref().map_or(0., |wind| {
+            wind.strength * (elapsed_secs / wind.period * 2. * PI).sin()
+        })
+    }
+
+    /// Acceleration of a ball at `(x, y)` after `elapsed_secs`
+    pub fn acceleration(
+        &self,
+        board: &BoardConfig,
+        elapsed_secs: f32,
+        x: f32,
+        y: f32,
+    ) -> (f32, f32) {
+        let mut acceleration = (self.wind_at(elapsed_secs), -self.gravity);
+        for well in &self.wells {
+            let (centre_x, centre_y) = well.centre(board);
+            let (offset_x, offset_y) = (centre_x - x, centre_y - y);
+            let distance = (offset_x * offset_x + offset_y * offset_y).sqrt();
+            let reach = well.reach(board);
+            // right at the centre there is no direction to pull in
+            if distance >= reach || distance < f32::EPSILON {
+                continue;
+            }
+            let pull = well.strength * (1. - distance / reach);
+            acceleration.0 += pull * offset_x / distance;
+            acceleration.1 += pull * offset_y / distance;
+        }
+        acceleration
+    }
+}
+
+/// Rectangle on the opponent's half of a dice, a ball it hit passing through
+/// awards `points` once per hit. Sizes are shares of the half board so a layout
+/// fits any board, zones of the right dice are mirrored onto the left half.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TargetZone {
+    /// centre distance from the middle line, 0..1 of half width
+    pub x: f32,
+    /// centre height, -1..1 of half height, positive upwards
+    pub y: f32,
+    /// share of half width
+    pub width: f32,
+    /// share of half height
+    pub height: f32,
+    pub points: usize,
+}
+
+impl TargetZone {
+    /// Centre of the zone awarding bonus to `kind`
+    pub fn centre(&self, board: &BoardConfig, kind: DiceKind) -> (f32, f32) {
+        let x = self.x * board.width / 2.;
+        let y = self.y * board.height / 2.;
+        match kind {
+            DiceKind::Left => (x, y),
+            DiceKind::Right => (-x, y),
+        }
+    }
+
+    pub fn size(&self, board: &BoardConfig) -> (f32, f32) {
+        (
+            self.width * board.width / 2.,
+            self.height * board.height / 2.,
+        )
+    }
+
+    pub fn contains(&self, board: &BoardConfig, kind: DiceKind, x: f32, y: f32) -> bool {
+        let (centre_x, centre_y) = self.centre(board, kind);
+        let (width, height) = self.size(board);
+        (x - centre_x).abs() <= width / 2. && (y - centre_y).abs() <= height / 2.
+    }
+}
+
+/// Block in the middle of the board, ball bounces off it and breaks it once its hit points
+/// are used up. Sizes are shares of the half board like `TargetZone`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Brick {
+    /// centre, -1..1 of half width, positive towards right dice
+    pub x: f32,
+    /// centre, -1..1 of half height, positive upwards
+    pub y: f32,
+    /// share of half width
+    pub width: f32,
+    /// share of half height
+    pub height: f32,
+    pub hp: u32,
+    /// score of the dice breaking it
+    pub points: usize,
+}
+
+impl Brick {
+    pub fn centre(&self, board: &BoardConfig) -> (f32, f32) {
+        (self.x * board.width / 2., self.y * board.height / 2.)
+    }
+
+    pub fn size(&self, board: &BoardConfig) -> (f32, f32) {
+        (
+            self.width * board.width / 2.,
+            self.height * board.height / 2.,
+        )
+    }
+
+    pub fn is_broken(&self) -> bool {
+        self.hp == 0
+    }
+}
+
+/// Named set of bricks for breakout, picked in settings
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct BrickLayout {
+    pub name: String,
+    pub bricks: Vec<Brick>,
+}
+
+/// Named set of target zones, picked in settings
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct TargetLayout {
+    pub name: String,
+    pub zones: Vec<TargetZone>,
+}
+
+/// Ball machine on a wall, fires every ball reaching it at the next of its angles
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Launcher {
+    /// angles relative to horizontal axis, positive upwards, fired in turn.
+    /// Balls go straight without any.
+    pub angles: Vec<f32>,
+    /// ball speed as share of board ball speed
+    pub speed: f32,
+    /// balls fired so far
+    pub shots: usize,
+}
+
+impl Launcher {
+    /// Normalized direction of the next ball from the wall on `side`
+    fn fire(&mut self, side: DiceKind) -> (f32, f32) {
+        let angle = match self.angles.len() {
+            0 => 0.,
+            len => self.angles[self.shots % len],
+        };
+        self.shots += 1;
+        match side {
+            DiceKind::Left => (angle.cos(), angle.sin()),
+            DiceKind::Right => (-angle.cos(), angle.sin()),
+        }
+    }
+}
+
+impl PongSim {
+    pub fn new(board: BoardConfig) -> Self {
+        Self {
+            ball: Ball::waiting(board.start_delay),
+            board,
+            left_dice_y: 0.0,
+            right_dice_y: 0.0,
+            score_left: 0,
+            score_right: 0,
+            ball_speed_factor: 1.0,
+            wall: None,
+            speed_up: 0.0,
+            launcher: None,
+            endless: false,
+            targets: Vec::new(),
+            bonus_left: 0,
+            bonus_right: 0,
+            brick_layout: Vec::new(),
+            bricks: Vec::new(),
+            forces: Forces::default(),
+            elapsed_secs: 0.,
+        }
+    }
+
+    /// Versus match with `bricks` in the middle, clearing them ends the round
+    pub fn breakout(board: BoardConfig, bricks: Vec<Brick>) -> Self {
+        Self {
+            bricks: bricks.clone(),
+            brick_layout: bricks,
+            ..Self::new(board)
+        }
+    }
+
+    /// Single player match against a wall on `wall` side, ball speeds up with every return
+    pub fn survival(board: BoardConfig, wall: DiceKind) -> Self {
+        Self {
+            wall: Some(wall),
+            speed_up: SURVIVAL_SPEED_UP,
+            ..Self::new(board)
+        }
+    }
+
+    /// Endless practice against a wall on `wall` side, or against `launcher` on it
+    pub fn practice(board: BoardConfig, wall: DiceKind, launcher: Option<Launcher>) -> Self {
+        Self {
+            wall: Some(wall),
+            launcher,
+            endless: true,
+            ..Self::new(board)
+        }
+    }
+
+    pub fn dice_y(&self, kind: DiceKind) -> f32 {
+        match kind {
+            DiceKind::Left => self.left_dice_y,
+            DiceKind::Right => self.right_dice_y,
+        }
+    }
+
+    /// Distance the ball travels per sec, with speed assist and bonus from dice hits
+    pub fn ball_speed(&self) -> f32 {
+        let ball = &self.ball;
+        ball.velocity_x.hypot(ball.velocity_y)
+            * self.board.ball_speed
+            * self.ball_speed_factor
+            * (1. + ball.speed_bonus)
+    }
+
+    /// Player who reached winning score, match is over once there is one
+    pub fn winner(&self) -> Option<DiceKind> {
+        if self.endless {
+            return None;
+        }
+        if let Some(wall) = self.wall {
+            let wall_score = match wall {
+                DiceKind::Left => self.score_left,
+                DiceKind::Right => self.score_right,
+            };
+            return (wall_score > 0).then_some(wall);
+        }
+        if self.score_left >= self.board.winning_score {
+            Some(DiceKind::Left)
+        } else if self.score_right >= self.board.winning_score {
+            Some(DiceKind::Right)
+        } else {
+            None
+        }
+    }
+
+    /// Advances the match by `dt` secs with dice inputs `(left, right)` in -1..1,
+    /// `rng` decides serve directions
+    pub fn step(&mut self, dt: f32, inputs: (f32, f32), rng: &mut impl Rng) -> Vec<SimEvent> {
+        let mut events = Vec::new();
+        self.update_serve(dt, rng);
+        self.update_dices(dt, inputs);
+        self.update_forces(dt);
+        self.update_ball(dt, &mut events);
+        self.update_bricks(&mut events);
+        self.update_targets(&mut events);
+        self.next_round(&events);
+        events
+    }
+
+    fn update_serve(&mut self, dt: f32, rng: &mut impl Rng) {
+        if let Some(remaining_sec) = &mut self.ball.serve_delay {
+            *remaining_sec -= dt;
+            if *remaining_sec < 0.0 {
+                let (velocity_x, velocity_y) = match (&mut self.launcher, self.wall) {
+                    (Some(launcher), Some(wall)) => {
+                        self.ball.speed_bonus = launcher.speed - 1.;
+                        launcher.fire(wall)
+                    }
+                    _ => random_starting_angle(rng),
+                };
+                self.ball.velocity_x = velocity_x;
+                self.ball.velocity_y = velocity_y;
+                self.ball.serve_delay = None;
+            }
+        }
+    }
+
+    fn update_dices(&mut self, dt: f32, (left, right): (f32, f32)) {
+        let top_y_limit = self.board.max_dice_position(true);
+        let bottom_y_limit = self.board.max_dice_position(false);
+        for (dice_y, input) in [
+            (&mut self.left_dice_y, left),
+            (&mut self.right_dice_y, right),
+        ] {
+            *dice_y += dt * input * INPUT_FACTOR;
+            *dice_y = dice_y.clamp(bottom_y_limit, top_y_limit);
+        }
+    }
+
+    fn update_forces(&mut self, dt: f32) {
+        self.elapsed_secs += dt;
+        if self.ball.serve_delay.is_some() || self.forces.is_calm() {
+            return;
+        }
+        (self.ball.velocity_x, self.ball.velocity_y) =
+            forced_velocity(&self.forces, &self.board, self.elapsed_secs, &self.ball, dt);
+    }
+
+    fn update_ball(&mut self, dt: f32, events: &mut Vec<SimEvent>) {
+        let board = &self.board;
+        let ball = &mut self.ball;
+        let speed = board.ball_speed * self.ball_speed_factor * (1. + ball.speed_bonus);
+        ball.x += ball.velocity_x * dt * speed;
+        ball.y += ball.velocity_y * dt * speed;
+
+        let top_y = board.height / 2. - board.ball_radius;
+        let bottom_y = -board.height / 2. + board.ball_radius;
+        let dice_axis_left = board.dice_axis_x(DiceKind::Left);
+        let dice_axis_right = board.dice_axis_x(DiceKind::Right);
+        if !ball.is_colliding_x {
+            let over_right_axis = ball.x > dice_axis_right;
+            let over_left_axis = ball.x < dice_axis_left;
+            if (over_left_axis || over_right_axis) && !ball.is_lost {
+                let kind = if over_right_axis {
+                    DiceKind::Right
+                } else {
+                    DiceKind::Left
+                };
+                if self.wall == Some(kind) {
+                    match &mut self.launcher {
+                        Some(launcher) => {
+                            (ball.velocity_x, ball.velocity_y) = launcher.fire(kind);
+                            ball.speed_bonus = launcher.speed - 1.;
+                        }
+                        None => ball.velocity_x *= -1.,
+                    }
+                    ball.is_colliding_x = true;
+                } else {
+                    let dice_y = match kind {
+                        DiceKind::Left => self.left_dice_y,
+                        DiceKind::Right => self.right_dice_y,
+                    };
+                    let quarter_ball = board.ball_radius / 2.;
+                    let half_dice = board.dice_length / 2.;
+                    let collision = dice_collision(ball.y, quarter_ball, dice_y, half_dice, kind);
+                    if let Some((velocity_x, velocity_y)) = collision {
+                        ball.hit_offset = hit_offset(ball.y, quarter_ball, dice_y, half_dice);
+                        ball.passed_targets.clear();
+                        ball.velocity_x = velocity_x;
+                        ball.velocity_y = velocity_y;
+                        ball.is_colliding_x = true;
+                        ball.last_hit = Some(kind);
+                        ball.speed_bonus = (ball.speed_bonus + self.speed_up).min(MAX_SPEED_BONUS);
+                        events.push(SimEvent::DiceHit {
+                            kind,
+                            bounce_angle: velocity_y.abs().asin(),
+                        });
+                        // every return scores against a wall
+                        if self.wall.is_some() {
+                            match kind {
+                                DiceKind::Left => self.score_left += 1,
+                                DiceKind::Right => self.score_right += 1,
+                            }
+                        }
+                    } else {
+                        ball.is_lost = true;
+                    }
+                }
+            }
+        } else if ball.x > dice_axis_left && ball.x < dice_axis_right {
+            ball.is_colliding_x = false;
+        }
+        // bounce only while heading outwards, dice hit close to a wall may have
+        // turned the ball back already
+        let heading_out =
+            (ball.y > top_y && ball.velocity_y > 0.) || (ball.y < bottom_y && ball.velocity_y < 0.);
+        if heading_out {
+            ball.velocity_y *= -1.;
+        }
+        if ball.is_lost {
+            if ball.x > board.width / 2. {
+                events.push(SimEvent::PlayerLost { is_right: true });
+            }
+            if ball.x < -board.width / 2. {
+                events.push(SimEvent::PlayerLost { is_right: false });
+            }
+        }
+    }
+
+    fn update_bricks(&mut self, events: &mut Vec<SimEvent>) {
+        let ball = &mut self.ball;
+        if ball.serve_delay.is_some() {
+            return;
+        }
+        for (index, brick) in self.bricks.iter_mut().enumerate() {
+            if brick.is_broken() {
+                continue;
+            }
+            let centre = brick.centre(&self.board);
+            let size = brick.size(&self.board);
+            let Some((normal_x, normal_y)) =
+                brick_collision(ball.x, ball.y, self.board.ball_radius, centre, size)
+            else {
+                continue;
+            };
+            // bounce only while heading into the brick, like off the walls
+            if ball.velocity_x * normal_x + ball.velocity_y * normal_y >= 0. {
+                continue;
+            }
+            if normal_x != 0. {
+                ball.velocity_x *= -1.;
+            } else {
+                ball.velocity_y *= -1.;
+            }
+            brick.hp -= 1;
+            let broken = brick.is_broken();
+            if broken {
+                match ball.last_hit {
+                    Some(DiceKind::Left) => self.score_left += brick.points,
+                    Some(DiceKind::Right) => self.score_right += brick.points,
+                    None => {}
+                }
+            }
+            events.push(SimEvent::BrickHit {
+                kind: ball.last_hit,
+                brick: index,
+                broken,
+            });
+            if broken && self.bricks.iter().all(Brick::is_broken) {
+                events.push(SimEvent::BricksCleared);
+            }
+            // one bounce per step, the ball is still close to the others
+            return;
+        }
+    }
+
+    fn update_targets(&mut self, events: &mut Vec<SimEvent>) {
+        let ball = &mut self.ball;
+        let Some(kind) = ball.last_hit else {
+            return;
+        };
+        // only on the way out from the dice, not back off a wall or launcher
+        let heading_away = match kind {
+            DiceKind::Left => ball.velocity_x > 0.,
+            DiceKind::Right => ball.velocity_x < 0.,
+        };
+        if !heading_away || ball.is_lost {
+            return;
+        }
+        for (zone, target) in self.targets.iter().enumerate() {
+            if ball.passed_targets.contains(&zone)
+                || !target.contains(&self.board, kind, ball.x, ball.y)
+            {
+                continue;
+            }
+            ball.passed_targets.push(zone);
+            match kind {
+                DiceKind::Left => self.bonus_left += target.points,
+                DiceKind::Right => self.bonus_right += target.points,
+            }
+            events.push(SimEvent::TargetHit {
+                kind,
+                zone,
+                points: target.points,
+            });
+        }
+    }
+
+    fn next_round(&mut self, events: &[SimEvent]) {
+        let lost = events.iter().find_map(|event| match event {
+            SimEvent::PlayerLost { is_right } => Some(*is_right),
+            _ => None,
+        });
+        if let Some(is_right) = lost {
+            if is_right {
+                self.score_left += 1;
+            } else {
+                self.score_right += 1;
+            }
+        }
+        let cleared = events.contains(&SimEvent::BricksCleared);
+        if lost.is_some() || cleared {
+            self.ball = Ball::waiting(self.board.start_delay);
+            self.bricks.clone_from(&self.brick_layout);
+        }
+    }
+}
+
+/// Velocity of `ball` after `forces` acted on it for `dt` secs up to `elapsed_secs`.
+/// Forces bend the ball but never turn it back or stall it.
+pub fn forced_velocity(
+    forces: &Forces,
+    board: &BoardConfig,
+    elapsed_secs: f32,
+    ball: &Ball,
+    dt: f32,
+) -> (f32, f32) {
+    let (acceleration_x, mut acceleration_y) =
+        forces.acceleration(board, elapsed_secs, ball.x, ball.y);
+    // ball beyond a wall is bouncing back, turning it out again would sink it through
+    if ball.y.abs() > board.height / 2. - board.ball_radius {
+        acceleration_y = 0.;
+    }
+    let heading = ball.velocity_x.signum();
+    let mut velocity_x = ball.velocity_x + acceleration_x * dt;
+    let mut velocity_y = ball.velocity_y + acceleration_y * dt;
+    let length = velocity_x.hypot(velocity_y);
+    if length > MAX_FORCED_VELOCITY {
+        velocity_x *= MAX_FORCED_VELOCITY / length;
+        velocity_y *= MAX_FORCED_VELOCITY / length;
+    }
+    velocity_x = heading * (velocity_x * heading).max(MIN_CROSSING_VELOCITY);
+    // raised crossing velocity must not push the ball past the limit
+    let max_velocity_y = (MAX_FORCED_VELOCITY.powi(2) - velocity_x.powi(2)).sqrt();
+    velocity_y = velocity_y.clamp(-max_velocity_y, max_velocity_y);
+    (velocity_x, velocity_y)
+}
+
+/// Where ball meets dice, from -1 at its bottom edge over 0 at centre to 1 at its top edge.
+/// `None` when ball misses it.
+pub fn hit_offset(ball_y: f32, quarter_ball: f32, dice_y: f32, half_dice: f32) -> Option<f32> {
+    let upper_edge = dice_y + half_dice + quarter_ball;
+    let lower_edge = dice_y - half_dice - quarter_ball;
+    (ball_y <= upper_edge && ball_y >= lower_edge)
+        .then(|| (ball_y - dice_y) / (half_dice + quarter_ball))
+}
+
+/// Side of a brick the ball touches as outward normal, `None` when it does not.
+/// Ball overlapping a corner is pushed out along the axis it is further off.
+pub fn brick_collision(
+    ball_x: f32,
+    ball_y: f32,
+    radius: f32,
+    (centre_x, centre_y): (f32, f32),
+    (width, height): (f32, f32),
+) -> Option<(f32, f32)> {
+    let offset_x = ball_x - centre_x;
+    let offset_y = ball_y - centre_y;
+    let closest_x = offset_x.clamp(-width / 2., width / 2.);
+    let closest_y = offset_y.clamp(-height / 2., height / 2.);
+    let distance_x = offset_x - closest_x;
+    let distance_y = offset_y - closest_y;
+    if distance_x * distance_x + distance_y * distance_y > radius * radius {
+        return None;
+    }
+    let is_x_side = if distance_x == 0. && distance_y == 0. {
+        // centre already inside, leave by the shallower side
+        width / 2. - offset_x.abs() <= height / 2. - offset_y.abs()
+    } else {
+        distance_x.abs() >= distance_y.abs()
+    };
+    if is_x_side {
+        Some((offset_x.signum(), 0.))
+    } else {
+        Some((0., offset_y.signum()))
+    }
+}
+
+/// Normalized ball velocity after hitting dice, `None` when ball misses it.
+/// Hits off centre bounce away from it, up to `MAX_BOUNCE_ANGLE` at the edges.
+pub fn dice_collision(
+    ball_y: f32,
+    quarter_ball: f32,
+    dice_y: f32,
+    half_dice: f32,
+    kind: DiceKind,
+) -> Option<(f32, f32)> {
+    if let Some(offset) = hit_offset(ball_y, quarter_ball, dice_y, half_dice) {
+        let bounce_angle = -offset * MAX_BOUNCE_ANGLE;
+        let velocity = match kind {
+            DiceKind::Right => (-bounce_angle.cos(), -bounce_angle.sin()),
+            DiceKind::Left => (bounce_angle.cos(), -bounce_angle.sin()),
+        };
+        return Some(velocity);
+    }
+    None
+}
+
+/// Normalized direction of a serve towards random side
+pub fn random_starting_angle(rng: &mut impl Rng) -> (f32, f32) {
+    let step = Uniform::new(-1.0, 1.0);
+    let swing = step.sample(rng);
+    let is_right = rng.gen::<bool>();
+    let bounce_angle = swing * MAX_BOUNCE_ANGLE;
+    if is_right {
+        (-bounce_angle.cos(), -bounce_angle.sin())
+    } else {
+        (bounce_angle.cos(), -bounce_angle.sin())
+    }
+}
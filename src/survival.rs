@@ -0,0 +1,293 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::asset::io::file::FileAssetReader;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::gameplay::{
+    GameMode, MatchSim, MatchSimSet, ResumedMatch, SimulationDriver, SimulationStep,
+};
+use crate::profile::Profiles;
+use crate::protocol::{sanitize_name, MAX_NAME_LENGTH};
+use crate::text_input::{spawn_text_input, TextInput, TextInputSubmitted};
+use crate::theme::Theme;
+use crate::utils::*;
+use crate::GameState;
+
+/// Survival runs against a wall with a local top 10 kept in `leaderboard.ron`,
+/// qualifying runs enter a name on game over screen.
+pub struct SurvivalPlugin;
+
+impl Plugin for SurvivalPlugin {
+    fn build(&self, app: &mut App) {
+        let leaderboard = Leaderboard::load(&leaderboard_path());
+
+        app.insert_resource(leaderboard)
+            .add_systems(
+                OnEnter(GameState::Gameplay),
+                (
+                    forget_last_run,
+                    start_run.run_if(
+                        resource_equals(SimulationDriver::Local)
+                            .and_then(resource_equals(GameMode::Survival)),
+                    ),
+                )
+                    .chain()
+                    // resumed run is restored with the match
+                    .run_if(not(resource_exists::<ResumedMatch>())),
+            )
+            .add_systems(
+                SimulationStep,
+                count_returns.after(MatchSimSet).run_if(
+                    resource_exists::<SurvivalRun>().and_then(resource_exists::<MatchSim>()),
+                ),
+            )
+            .add_systems(Update, submit_name.run_if(in_state(GameState::GameOver)))
+            // leaving without Return keeps the run under the name typed so far
+            .add_systems(
+                OnExit(GameState::GameOver),
+                submit_pending_name.run_if(resource_exists::<SurvivalRun>()),
+            );
+    }
+}
+
+/// leaderboard file, relative to asset base path
+const LEADERBOARD_FILE: &str = "leaderboard.ron";
+/// runs kept on the leaderboard
+const LEADERBOARD_SIZE: usize = 10;
+
+fn leaderboard_path() -> PathBuf {
+    FileAssetReader::get_base_path().join(LEADERBOARD_FILE)
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LeaderboardEntry {
+    pub name: String,
+    pub returns: u32,
+    /// secs since unix epoch
+    pub finished_at: u64,
+}
+
+/// Best survival runs, best first
+#[derive(Clone, Debug, Default, PartialEq, Resource, Serialize, Deserialize)]
+pub struct Leaderboard {
+    entries: Vec<LeaderboardEntry>,
+}
+
+impl Leaderboard {
+    /// Reads `path`, missing or broken file gives empty leaderboard
+    pub fn load(path: &Path) -> Self {
+        if !path.exists() {
+            return Self::default();
+        }
+        match Self::read(path) {
+            Ok(leaderboard) => leaderboard,
+            Err(e) => {
+                warn!("failed to load leaderboard {}: {}", path.display(), e);
+                Self::default()
+            }
+        }
+    }
+
+    fn read(path: &Path) -> Result<Self, String> {
+        let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        ron::from_str(&content).map_err(|e| e.to_string())
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let content = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|e| e.to_string())?;
+        fs::write(path, content).map_err(|e| e.to_string())
+    }
+
+    pub fn entries(&self) -> &[LeaderboardEntry] {
+        &self.entries
+    }
+
+    /// Run with `returns` would make it to the leaderboard
+    pub fn qualifies(&self, returns: u32) -> bool {
+        returns > 0
+            && (self.entries.len() < LEADERBOARD_SIZE
+                || self
+                    .entries
+                    .last()
+                    .is_some_and(|last| returns > last.returns))
+    }
+
+    /// Adds run behind those with as many returns, gives its rank from 0
+    /// or `None` if it did not qualify
+    pub fn insert(&mut self, entry: LeaderboardEntry) -> Option<usize> {
+        if !self.qualifies(entry.returns) {
+            return None;
+        }
+        let rank = self
+            .entries
+            .iter()
+            .position(|other| other.returns < entry.returns)
+            .unwrap_or(self.entries.len());
+        self.entries.insert(rank, entry);
+        self.entries.truncate(LEADERBOARD_SIZE);
+        Some(rank)
+    }
+}
+
+/// Survival run in play or just finished, kept until the next match
+#[derive(Clone, Debug, Default, Resource, Serialize, Deserialize)]
+pub struct SurvivalRun {
+    pub returns: u32,
+    /// name was entered and the run is on the leaderboard
+    pub submitted: bool,
+}
+
+/// Name field of a qualifying run on game over screen
+#[derive(Component)]
+pub struct LeaderboardNameInput;
+
+#[derive(Component)]
+pub struct LeaderboardText;
+
+fn forget_last_run(mut commands: Commands) {
+    commands.remove_resource::<SurvivalRun>();
+}
+
+fn start_run(mut commands: Commands) {
+    commands.insert_resource(SurvivalRun::default());
+}
+
+fn count_returns(match_sim: Res<MatchSim>, mut run: ResMut<SurvivalRun>) {
+    let sim = &match_sim.0;
+    let returns = match sim.wall {
+        Some(DiceKind::Left) => sim.score_right,
+        _ => sim.score_left,
+    } as u32;
+    if run.returns != returns {
+        run.returns = returns;
+    }
+}
+
+fn leaderboard_lines(leaderboard: &Leaderboard) -> String {
+    if leaderboard.entries.is_empty() {
+        return "No runs yet".to_string();
+    }
+    let lines: Vec<String> = leaderboard
+        .entries
+        .iter()
+        .enumerate()
+        .map(|(rank, entry)| format!("{:>2}. {:<16} {:>4}", rank + 1, entry.name, entry.returns))
+        .collect();
+    lines.join("\n")
+}
+
+/// Leaderboard for game over screen, with name field when `run` qualifies
+pub fn spawn_leaderboard_panel(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    theme: &Theme,
+    leaderboard: &Leaderboard,
+    run: &SurvivalRun,
+    profiles: &Profiles,
+) -> Entity {
+    let name_input = (!run.submitted && leaderboard.qualifies(run.returns)).then(|| {
+        let name = &profiles.active().name;
+        let input = spawn_text_input(commands, asset_server, theme, name, MAX_NAME_LENGTH);
+        commands.entity(input).insert(LeaderboardNameInput);
+        input
+    });
+
+    let text_style = TextStyle {
+        font: asset_server.load(FONT_PATH),
+        font_size: 28.0 * theme.font_scale,
+        color: theme.text,
+    };
+    let hint = if name_input.is_some() {
+        "New high score! Type your name, Return saves"
+    } else {
+        "Top 10"
+    };
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                align_self: AlignSelf::Center,
+                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::Column,
+                width: Val::Percent(65.0),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|commands| {
+            commands.spawn(TextBundle::from_section(hint, text_style.clone()));
+        })
+        .push_children(&Vec::from_iter(name_input))
+        .with_children(|commands| {
+            commands.spawn((
+                TextBundle {
+                    style: Style {
+                        margin: UiRect::all(Val::Percent(1.0)),
+                        ..default()
+                    },
+                    text: Text::from_section(leaderboard_lines(leaderboard), text_style),
+                    ..default()
+                },
+                LeaderboardText,
+            ));
+        })
+        .id()
+}
+
+fn add_to_leaderboard(leaderboard: &mut Leaderboard, run: &mut SurvivalRun, name: &str) {
+    let name = match sanitize_name(name) {
+        name if name.is_empty() => "Player".to_string(),
+        name => name,
+    };
+    let finished_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    leaderboard.insert(LeaderboardEntry {
+        name,
+        returns: run.returns,
+        finished_at,
+    });
+    run.submitted = true;
+
+    let path = leaderboard_path();
+    if let Err(e) = leaderboard.save(&path) {
+        error!("failed to save leaderboard {}: {}", path.display(), e);
+    }
+}
+
+fn submit_name(
+    mut commands: Commands,
+    mut submitted: EventReader<TextInputSubmitted>,
+    name_inputs: Query<Entity, With<LeaderboardNameInput>>,
+    mut leaderboard_texts: Query<&mut Text, With<LeaderboardText>>,
+    mut leaderboard: ResMut<Leaderboard>,
+    run: Option<ResMut<SurvivalRun>>,
+) {
+    let Some(mut run) = run else {
+        return;
+    };
+    for event in submitted.read() {
+        if run.submitted || !name_inputs.contains(event.entity) {
+            continue;
+        }
+        add_to_leaderboard(&mut leaderboard, &mut run, &event.value);
+        commands.entity(event.entity).despawn_recursive();
+        for mut text in &mut leaderboard_texts {
+            text.sections[0].value = leaderboard_lines(&leaderboard);
+        }
+    }
+}
+
+fn submit_pending_name(
+    name_inputs: Query<&TextInput, With<LeaderboardNameInput>>,
+    mut leaderboard: ResMut<Leaderboard>,
+    mut run: ResMut<SurvivalRun>,
+) {
+    if let (Ok(input), false) = (name_inputs.get_single(), run.submitted) {
+        add_to_leaderboard(&mut leaderboard, &mut run, &input.value);
+    }
+}
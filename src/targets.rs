@@ -0,0 +1,243 @@
+use std::fs;
+use std::path::Path;
+
+use bevy::asset::io::file::FileAssetReader;
+use bevy::prelude::*;
+
+use crate::gameplay::{MatchSim, TargetBonus, TargetHit, TargetLayout};
+use crate::sim::DiceKind;
+use crate::theme::Theme;
+use crate::utils::*;
+use crate::GameState;
+
+/// Target zones on the opponent's half, passing the ball through them scores bonus points.
+///
+/// Layouts are read from `assets/targets` and picked in settings, the chosen one is
+/// used by every local match. Bonus is shown apart from the score.
+pub struct TargetsPlugin;
+
+impl Plugin for TargetsPlugin {
+    fn build(&self, app: &mut App) {
+        let library = TargetLibrary::load(&FileAssetReader::get_base_path().join(TARGETS_DIR));
+
+        app.insert_resource(library)
+            // replays and netplay bring their own layout, main menu goes back to chosen one
+            .add_systems(OnEnter(GameState::MainMenu), apply_selected_targets)
+            .add_systems(OnExit(GameState::Gameplay), despawn_targets)
+            .add_systems(
+                Update,
+                (
+                    apply_selected_targets.run_if(resource_changed::<TargetLibrary>()),
+                    spawn_targets.run_if(resource_added::<MatchSim>()),
+                    (flash_targets, fade_targets)
+                        .chain()
+                        .run_if(in_state(GameState::Gameplay)),
+                    update_bonus_text.run_if(resource_exists_and_changed::<TargetBonus>()),
+                ),
+            );
+    }
+}
+
+/// directory with target layout files, relative to asset base path
+const TARGETS_DIR: &str = "assets/targets";
+/// more zones would not fit the lobby handshake
+pub const MAX_ZONES: usize = 8;
+/// opacity of a zone at rest
+const ZONE_ALPHA: f32 = 0.2;
+/// opacity of a zone right after the ball passed it
+const ZONE_FLASH_ALPHA: f32 = 0.7;
+/// opacity lost per sec after a flash
+const ZONE_FADE_SPEED: f32 = 1.5;
+
+/// All target layouts available in settings, none is chosen on startup
+#[derive(Resource)]
+pub struct TargetLibrary {
+    layouts: Vec<TargetLayout>,
+    selected: Option<usize>,
+}
+
+impl TargetLibrary {
+    /// Reads every `*.ron` file from `dir`, unusable ones are skipped
+    pub fn load(dir: &Path) -> Self {
+        Self {
+            layouts: load_ron_dir(dir, "target layout", load_layout_file),
+            selected: None,
+        }
+    }
+
+    pub fn selected(&self) -> Option<&TargetLayout> {
+        self.selected.map(|index| &self.layouts[index])
+    }
+
+    /// Cycles through layouts, off comes after the last one
+    pub fn select_next(&mut self) {
+        self.selected = select_next_or_off(self.selected, self.layouts.len());
+    }
+}
+
+/// Reads a layout and checks it with `check_layout`
+pub fn load_layout_file(path: &Path) -> Result<TargetLayout, String> {
+    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let layout: TargetLayout = ron::from_str(&content).map_err(|e| e.to_string())?;
+    check_layout(&layout)?;
+    Ok(layout)
+}
+
+/// Errs unless sizes and points are positive and zones stay on the half board,
+/// also used on layouts received from the network
+pub fn check_layout(layout: &TargetLayout) -> Result<(), String> {
+    if layout.zones.is_empty() || layout.zones.len() > MAX_ZONES {
+        return Err(format!("layout needs 1 to {} zones", MAX_ZONES));
+    }
+    for (index, zone) in layout.zones.iter().enumerate() {
+        // comparisons with NaN fail, so non-finite zones never fit
+        let fits = zone.width > 0.
+            && zone.height > 0.
+            && zone.x - zone.width / 2. >= 0.
+            && zone.x + zone.width / 2. <= 1.
+            && (zone.y - zone.height / 2.) >= -1.
+            && (zone.y + zone.height / 2.) <= 1.;
+        if !fits {
+            return Err(format!("zone {} is outside of the half board", index));
+        }
+        if zone.points == 0 {
+            return Err(format!("zone {} awards no points", index));
+        }
+    }
+    Ok(())
+}
+
+#[derive(Component)]
+struct TargetTag;
+
+/// Zone sprite awarding bonus to `kind`
+#[derive(Component)]
+struct TargetSprite {
+    kind: DiceKind,
+    zone: usize,
+    alpha: f32,
+}
+
+#[derive(Component)]
+struct BonusText;
+
+fn apply_selected_targets(library: Res<TargetLibrary>, mut targets: ResMut<TargetLayout>) {
+    *targets = library.selected().cloned().unwrap_or_default();
+}
+
+fn spawn_targets(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    theme: Res<Theme>,
+    match_sim: Res<MatchSim>,
+    bonus: Option<Res<TargetBonus>>,
+) {
+    let sim = &match_sim.0;
+    for kind in [DiceKind::Left, DiceKind::Right] {
+        // wall side never hits a ball
+        if sim.wall == Some(kind) {
+            continue;
+        }
+        let color = match kind {
+            DiceKind::Left => theme.left_trail,
+            DiceKind::Right => theme.right_trail,
+        };
+        for (zone, target) in sim.targets.iter().enumerate() {
+            let (x, y) = target.centre(&sim.board, kind);
+            let (width, height) = target.size(&sim.board);
+            commands
+                .spawn((
+                    SpriteBundle {
+                        sprite: Sprite {
+                            color: color.with_a(ZONE_ALPHA),
+                            custom_size: Some(Vec2::new(width, height)),
+                            ..default()
+                        },
+                        // below ball and dices
+                        transform: Transform::from_xyz(x, y, -0.5),
+                        ..default()
+                    },
+                    TargetSprite {
+                        kind,
+                        zone,
+                        alpha: ZONE_ALPHA,
+                    },
+                    TargetTag,
+                ))
+                .with_children(|parent| {
+                    parent.spawn(Text2dBundle {
+                        text: Text::from_section(
+                            format!("+{}", target.points),
+                            TextStyle {
+                                font: asset_server.load(FONT_PATH),
+                                font_size: 28.0 * theme.font_scale,
+                                color: theme.score_text,
+                            },
+                        ),
+                        transform: Transform::from_xyz(0., 0., 0.1),
+                        ..default()
+                    });
+                });
+        }
+    }
+
+    let Some(bonus) = bonus else {
+        return;
+    };
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Percent(2.0),
+                    right: Val::Percent(3.0),
+                    ..default()
+                },
+                ..default()
+            },
+            TargetTag,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    bonus.as_text(),
+                    TextStyle {
+                        font: asset_server.load(FONT_PATH),
+                        font_size: 32.0 * theme.font_scale,
+                        color: theme.score_text,
+                    },
+                ),
+                BonusText,
+            ));
+        });
+}
+
+fn despawn_targets(mut commands: Commands, entities: Query<Entity, With<TargetTag>>) {
+    for entity in &entities {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn flash_targets(mut hits: EventReader<TargetHit>, mut sprites: Query<&mut TargetSprite>) {
+    for hit in hits.read() {
+        for mut sprite in &mut sprites {
+            if sprite.kind == hit.kind && sprite.zone == hit.zone {
+                sprite.alpha = ZONE_FLASH_ALPHA;
+            }
+        }
+    }
+}
+
+fn fade_targets(time: Res<Time>, mut sprites: Query<(&mut TargetSprite, &mut Sprite)>) {
+    let fade = ZONE_FADE_SPEED * time.delta_seconds();
+    for (mut target, mut sprite) in &mut sprites {
+        target.alpha = (target.alpha - fade).max(ZONE_ALPHA);
+        sprite.color.set_a(target.alpha);
+    }
+}
+
+fn update_bonus_text(bonus: Res<TargetBonus>, mut texts: Query<&mut Text, With<BonusText>>) {
+    for mut text in &mut texts {
+        text.sections[0].value = bonus.as_text();
+    }
+}
@@ -0,0 +1,112 @@
+use bevy::prelude::*;
+
+use crate::theme::Theme;
+use crate::utils::*;
+
+/// Single line text fields on UI screens.
+///
+/// Typing goes to every `TextInput` on screen, screens show at most one.
+/// Return sends `TextInputSubmitted`.
+pub struct TextInputPlugin;
+
+impl Plugin for TextInputPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<TextInputSubmitted>()
+            .add_systems(Update, (type_into_inputs, update_input_texts).chain());
+    }
+}
+
+#[derive(Component)]
+pub struct TextInput {
+    pub value: String,
+    pub max_length: usize,
+}
+
+#[derive(Clone, Debug, Event)]
+pub struct TextInputSubmitted {
+    pub entity: Entity,
+    pub value: String,
+}
+
+pub fn spawn_text_input(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    theme: &Theme,
+    value: &str,
+    max_length: usize,
+) -> Entity {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(65.0),
+                align_self: AlignSelf::Center,
+                justify_content: JustifyContent::Center,
+                margin: UiRect::all(Val::Percent(1.0)),
+                border: UiRect::all(Val::Px(3.0)),
+                ..default()
+            },
+            border_color: theme.text.into(),
+            ..default()
+        })
+        .insert(TextInput {
+            value: value.to_string(),
+            max_length,
+        })
+        .with_children(|commands| {
+            commands.spawn(TextBundle {
+                style: Style {
+                    align_self: AlignSelf::Center,
+                    margin: UiRect::all(Val::Percent(1.0)),
+                    ..default()
+                },
+                text: Text::from_section(
+                    format!("{}_", value),
+                    TextStyle {
+                        font: asset_server.load(FONT_PATH),
+                        font_size: 40.0 * theme.font_scale,
+                        color: theme.text,
+                    },
+                ),
+                ..default()
+            });
+        })
+        .id()
+}
+
+fn type_into_inputs(
+    mut characters: EventReader<ReceivedCharacter>,
+    keyboard: Res<Input<KeyCode>>,
+    mut inputs: Query<(Entity, &mut TextInput)>,
+    mut submitted: EventWriter<TextInputSubmitted>,
+) {
+    let typed: Vec<char> = characters
+        .read()
+        .map(|event| event.char)
+        .filter(|c| !c.is_control())
+        .collect();
+    for (entity, mut input) in &mut inputs {
+        for &c in &typed {
+            if input.value.chars().count() < input.max_length {
+                input.value.push(c);
+            }
+        }
+        if keyboard.just_pressed(KeyCode::Back) {
+            input.value.pop();
+        }
+        if keyboard.just_pressed(KeyCode::Return) {
+            submitted.send(TextInputSubmitted {
+                entity,
+                value: input.value.clone(),
+            });
+        }
+    }
+}
+
+fn update_input_texts(
+    inputs: Query<(&TextInput, &Children), Changed<TextInput>>,
+    mut texts: Query<&mut Text>,
+) {
+    for (input, children) in &inputs {
+        set_button_text(children, &mut texts, format!("{}_", input.value));
+    }
+}
@@ -0,0 +1,211 @@
+use std::fs;
+use std::path::Path;
+
+use bevy::asset::io::file::FileAssetReader;
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::accessibility::AccessibilitySettings;
+use crate::utils::load_ron_dir;
+
+pub struct ThemePlugin;
+
+impl Plugin for ThemePlugin {
+    fn build(&self, app: &mut App) {
+        let library = ThemeLibrary::load(&FileAssetReader::get_base_path().join(THEMES_DIR));
+        let theme = library.selected().clone();
+
+        app.insert_resource(ClearColor(theme.background))
+            .insert_resource(theme)
+            .insert_resource(library)
+            .add_systems(
+                Update,
+                apply_selected_theme.run_if(
+                    resource_changed::<ThemeLibrary>()
+                        .or_else(resource_changed::<AccessibilitySettings>()),
+                ),
+            );
+    }
+}
+
+/// directory with theme files, relative to asset base path
+const THEMES_DIR: &str = "assets/themes";
+/// file stem of theme selected on startup
+const DEFAULT_THEME: &str = "classic";
+
+/// Colours used by all spawned entities and UI
+#[derive(Clone, Debug, Resource)]
+pub struct Theme {
+    pub name: String,
+    pub background: Color,
+    pub border: Color,
+    pub ball: Color,
+    pub left_dice: Color,
+    pub right_dice: Color,
+    pub left_trail: Color,
+    pub right_trail: Color,
+    pub score_text: Color,
+    pub text: Color,
+    pub button_text: Color,
+    /// main action button, e.g. start of game
+    pub button_primary: Color,
+    pub button: Color,
+    /// leaving or quitting button
+    pub button_secondary: Color,
+    /// contrast outline around ball and dices, set from accessibility settings
+    pub outline: Option<Color>,
+    /// multiplier for all font sizes, set from accessibility settings
+    pub font_scale: f32,
+}
+
+impl Theme {
+    /// Theme adjusted to accessibility settings
+    pub fn with_accessibility(&self, settings: &AccessibilitySettings) -> Self {
+        let vision = settings.color_vision;
+        let background = vision.remap(self.background);
+        // outline picks whichever extreme stands out more against background
+        let outline = if background.l() > 0.5 {
+            Color::BLACK
+        } else {
+            Color::WHITE
+        };
+        Self {
+            name: self.name.clone(),
+            background,
+            border: vision.remap(self.border),
+            ball: vision.remap(self.ball),
+            left_dice: vision.remap(self.left_dice),
+            right_dice: vision.remap(self.right_dice),
+            left_trail: vision.remap(self.left_trail),
+            right_trail: vision.remap(self.right_trail),
+            score_text: vision.remap(self.score_text),
+            text: vision.remap(self.text),
+            button_text: vision.remap(self.button_text),
+            button_primary: vision.remap(self.button_primary),
+            button: vision.remap(self.button),
+            button_secondary: vision.remap(self.button_secondary),
+            outline: settings.outlines.then_some(outline),
+            font_scale: settings.text_scale(),
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            name: "Classic".to_string(),
+            background: Color::rgb(0.6, 0.6, 0.6),
+            border: Color::BLACK,
+            ball: Color::RED,
+            left_dice: Color::rgb(0.25, 0.25, 0.75),
+            right_dice: Color::rgb(0.25, 0.25, 0.75),
+            left_trail: Color::rgb(0.25, 0.55, 0.95),
+            right_trail: Color::rgb(0.95, 0.55, 0.15),
+            score_text: Color::DARK_GRAY,
+            text: Color::BLACK,
+            button_text: Color::BLACK,
+            button_primary: Color::LIME_GREEN,
+            button: Color::GRAY,
+            button_secondary: Color::DARK_GRAY,
+            outline: None,
+            font_scale: 1.0,
+        }
+    }
+}
+
+/// On-disk representation of `Theme`, colours are hex strings like "#FF0000"
+#[derive(Deserialize)]
+struct ThemeFile {
+    name: String,
+    background: String,
+    border: String,
+    ball: String,
+    left_dice: String,
+    right_dice: String,
+    left_trail: String,
+    right_trail: String,
+    score_text: String,
+    text: String,
+    button_text: String,
+    button_primary: String,
+    button: String,
+    button_secondary: String,
+}
+
+impl TryFrom<ThemeFile> for Theme {
+    type Error = String;
+
+    fn try_from(file: ThemeFile) -> Result<Self, Self::Error> {
+        let parse = |field: &str, value: &str| {
+            Color::hex(value).map_err(|e| format!("invalid colour of `{}`: {:?}", field, e))
+        };
+        Ok(Self {
+            background: parse("background", &file.background)?,
+            border: parse("border", &file.border)?,
+            ball: parse("ball", &file.ball)?,
+            left_dice: parse("left_dice", &file.left_dice)?,
+            right_dice: parse("right_dice", &file.right_dice)?,
+            left_trail: parse("left_trail", &file.left_trail)?,
+            right_trail: parse("right_trail", &file.right_trail)?,
+            score_text: parse("score_text", &file.score_text)?,
+            text: parse("text", &file.text)?,
+            button_text: parse("button_text", &file.button_text)?,
+            button_primary: parse("button_primary", &file.button_primary)?,
+            button: parse("button", &file.button)?,
+            button_secondary: parse("button_secondary", &file.button_secondary)?,
+            outline: None,
+            font_scale: 1.0,
+            name: file.name,
+        })
+    }
+}
+
+/// All themes available for selection in settings
+#[derive(Resource)]
+pub struct ThemeLibrary {
+    themes: Vec<Theme>,
+    selected: usize,
+}
+
+impl ThemeLibrary {
+    /// Reads every `*.ron` file from `dir`, falls back to built-in theme if none is usable
+    pub fn load(dir: &Path) -> Self {
+        let loaded = load_ron_dir(dir, "theme", |path| {
+            let is_default = path.file_stem().is_some_and(|stem| stem == DEFAULT_THEME);
+            load_theme_file(path).map(|theme| (is_default, theme))
+        });
+        let selected = loaded
+            .iter()
+            .position(|(is_default, _)| *is_default)
+            .unwrap_or(0);
+        let mut themes: Vec<Theme> = loaded.into_iter().map(|(_, theme)| theme).collect();
+        if themes.is_empty() {
+            themes.push(Theme::default());
+        }
+        Self { themes, selected }
+    }
+
+    pub fn selected(&self) -> &Theme {
+        &self.themes[self.selected]
+    }
+
+    pub fn select_next(&mut self) {
+        self.selected = (self.selected + 1) % self.themes.len();
+    }
+}
+
+fn load_theme_file(path: &Path) -> Result<Theme, String> {
+    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let file: ThemeFile = ron::from_str(&content).map_err(|e| e.to_string())?;
+    Theme::try_from(file)
+}
+
+fn apply_selected_theme(
+    library: Res<ThemeLibrary>,
+    accessibility: Res<AccessibilitySettings>,
+    mut theme: ResMut<Theme>,
+    mut clear_color: ResMut<ClearColor>,
+) {
+    *theme = library.selected().with_accessibility(&accessibility);
+    clear_color.0 = theme.background;
+}
@@ -0,0 +1,944 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use bevy::asset::io::file::FileAssetReader;
+use bevy::prelude::*;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::ai::tracking_ai;
+use crate::gameplay::{
+    AiDices, BoardConfig, GameMode, MatchSim, SeedSequence, SimulationDriver, SIMULATION_HZ,
+};
+use crate::protocol::{sanitize_name, MAX_NAME_LENGTH};
+use crate::sim::PongSim;
+use crate::text_input::{spawn_text_input, TextInput, TextInputSubmitted};
+use crate::theme::Theme;
+use crate::utils::*;
+use crate::GameState;
+
+/// Local tournaments of 4 to 16 human or AI players, kept in `tournament.ron`
+/// until a new one is started.
+///
+/// Matches are played one after another with the usual gameplay, AI against AI
+/// is simulated right away. Bracket screen is shown between matches.
+pub struct TournamentPlugin;
+
+impl Plugin for TournamentPlugin {
+    fn build(&self, app: &mut App) {
+        if let Some(tournament) = Tournament::load(&tournament_path()) {
+            app.insert_resource(tournament);
+        }
+
+        app.insert_resource(TournamentSetup::default())
+            .add_systems(OnEnter(GameState::TournamentSetup), spawn_setup)
+            .add_systems(OnExit(GameState::TournamentSetup), despawn_setup)
+            .add_systems(
+                Update,
+                (
+                    setup_button_clicked,
+                    add_typed_player,
+                    update_setup_texts.run_if(resource_changed::<TournamentSetup>()),
+                )
+                    .chain()
+                    .run_if(in_state(GameState::TournamentSetup)),
+            )
+            .add_systems(
+                OnEnter(GameState::Bracket),
+                (forget_match, spawn_bracket).chain(),
+            )
+            .add_systems(OnExit(GameState::Bracket), despawn_bracket)
+            .add_systems(
+                Update,
+                (
+                    bracket_button_clicked,
+                    // results of simulated matches show up right away
+                    (despawn_bracket, spawn_bracket).chain().run_if(
+                        resource_exists::<Tournament>().and_then(
+                            resource_changed::<Tournament>().or_else(resource_changed::<Theme>()),
+                        ),
+                    ),
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Bracket)),
+            )
+            .add_systems(
+                OnEnter(GameState::Gameplay),
+                control_ai_dices.run_if(resource_exists::<TournamentMatch>()),
+            )
+            .add_systems(
+                OnExit(GameState::Gameplay),
+                record_result.run_if(
+                    resource_equals(SimulationDriver::Local)
+                        .and_then(resource_exists::<TournamentMatch>())
+                        .and_then(resource_exists::<MatchSim>()),
+                ),
+            )
+            // result is recorded once gameplay is left, suspended match keeps it for later
+            .add_systems(OnExit(GameState::GameOver), forget_match)
+            .add_systems(OnEnter(GameState::MainMenu), forget_match)
+            .add_systems(Update, back_to_bracket_clicked);
+    }
+}
+
+/// tournament file, relative to asset base path
+const TOURNAMENT_FILE: &str = "tournament.ron";
+pub const MIN_PLAYERS: usize = 4;
+pub const MAX_PLAYERS: usize = 16;
+/// AI against AI ends after this many ticks, the leader wins
+const AI_MATCH_TICKS: u32 = 60 * 60 * 10;
+/// rounds shown side by side on bracket screen, older ones scroll out
+const BRACKET_COLUMNS: usize = 4;
+
+fn tournament_path() -> PathBuf {
+    FileAssetReader::get_base_path().join(TOURNAMENT_FILE)
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Entrant {
+    pub name: String,
+    pub ai: bool,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Elimination {
+    #[default]
+    Single,
+    /// players are out after their second loss
+    Double,
+}
+
+impl Elimination {
+    fn lives(&self) -> u32 {
+        match self {
+            Elimination::Single => 1,
+            Elimination::Double => 2,
+        }
+    }
+
+    fn other(&self) -> Self {
+        match self {
+            Elimination::Single => Elimination::Double,
+            Elimination::Double => Elimination::Single,
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            Elimination::Single => "Single Elimination",
+            Elimination::Double => "Double Elimination",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Bracket {
+    /// players without a loss
+    Winners,
+    /// players with one loss in double elimination
+    Losers,
+    /// last two players
+    Final,
+}
+
+impl Bracket {
+    fn label(&self) -> &'static str {
+        match self {
+            Bracket::Winners => "W",
+            Bracket::Losers => "L",
+            Bracket::Final => "Final",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MatchResult {
+    pub winner: DiceKind,
+    /// (left, right)
+    pub score: (usize, usize),
+}
+
+/// Two entrants meeting in one round, left one plays left side
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Pairing {
+    pub round: u32,
+    pub bracket: Bracket,
+    pub left: usize,
+    /// `None` is a bye, left advances without playing
+    pub right: Option<usize>,
+    pub result: Option<MatchResult>,
+}
+
+impl Pairing {
+    pub fn winner(&self) -> Option<usize> {
+        match (self.result?.winner, self.right) {
+            (DiceKind::Right, Some(right)) => Some(right),
+            _ => Some(self.left),
+        }
+    }
+
+    pub fn loser(&self) -> Option<usize> {
+        match self.result?.winner {
+            DiceKind::Left => self.right,
+            DiceKind::Right => Some(self.left),
+        }
+    }
+}
+
+/// Tournament in progress or finished, present until a new one is started.
+///
+/// Rounds are drawn once the previous one is complete: players with as many losses
+/// meet each other, odd one out gets a bye. Last player of winners bracket and last
+/// one of losers bracket meet in the final.
+#[derive(Clone, Debug, PartialEq, Resource, Serialize, Deserialize)]
+pub struct Tournament {
+    /// tells this tournament apart from later ones
+    pub seed: u64,
+    pub elimination: Elimination,
+    /// in drawn order, neighbours meet in the first round
+    entrants: Vec<Entrant>,
+    pairings: Vec<Pairing>,
+}
+
+impl Tournament {
+    /// Draws `entrants` into the first round
+    pub fn new(mut entrants: Vec<Entrant>, elimination: Elimination, seed: u64) -> Self {
+        assert!((MIN_PLAYERS..=MAX_PLAYERS).contains(&entrants.len()));
+        entrants.shuffle(&mut ChaCha8Rng::seed_from_u64(seed));
+        let mut tournament = Self {
+            seed,
+            elimination,
+            entrants,
+            pairings: Vec::new(),
+        };
+        tournament.draw_round();
+        tournament
+    }
+
+    /// Reads `path`, `None` when there is no tournament or file is broken
+    pub fn load(path: &Path) -> Option<Self> {
+        if !path.exists() {
+            return None;
+        }
+        match Self::read(path) {
+            Ok(tournament) => Some(tournament),
+            Err(e) => {
+                warn!("failed to load tournament {}: {}", path.display(), e);
+                None
+            }
+        }
+    }
+
+    fn read(path: &Path) -> Result<Self, String> {
+        let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let tournament: Self = ron::from_str(&content).map_err(|e| e.to_string())?;
+        tournament.check()?;
+        Ok(tournament)
+    }
+
+    /// Errs when entrants or pairings could not come from `new`, e.g. in edited file
+    fn check(&self) -> Result<(), String> {
+        let count = self.entrants.len();
+        if !(MIN_PLAYERS..=MAX_PLAYERS).contains(&count) {
+            return Err(format!(
+                "tournament has {} entrants, expected {} to {}",
+                count, MIN_PLAYERS, MAX_PLAYERS
+            ));
+        }
+        for (index, pairing) in self.pairings.iter().enumerate() {
+            let sides_exist = pairing.left < count && pairing.right.iter().all(|&r| r < count);
+            if !sides_exist || pairing.right == Some(pairing.left) {
+                return Err(format!("pairing {} has unknown entrants", index));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let content = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|e| e.to_string())?;
+        fs::write(path, content).map_err(|e| e.to_string())
+    }
+
+    pub fn entrants(&self) -> &[Entrant] {
+        &self.entrants
+    }
+
+    pub fn pairings(&self) -> &[Pairing] {
+        &self.pairings
+    }
+
+    pub fn losses(&self, entrant: usize) -> u32 {
+        self.pairings
+            .iter()
+            .filter(|pairing| pairing.loser() == Some(entrant))
+            .count() as u32
+    }
+
+    fn byes(&self, entrant: usize) -> usize {
+        self.pairings
+            .iter()
+            .filter(|pairing| pairing.left == entrant && pairing.right.is_none())
+            .count()
+    }
+
+    /// Entrants not out yet, in drawn order
+    fn alive(&self) -> Vec<usize> {
+        (0..self.entrants.len())
+            .filter(|&entrant| self.losses(entrant) < self.elimination.lives())
+            .collect()
+    }
+
+    /// Index of the pairing to be played next
+    pub fn next(&self) -> Option<usize> {
+        self.pairings
+            .iter()
+            .position(|pairing| pairing.result.is_none())
+    }
+
+    pub fn champion(&self) -> Option<usize> {
+        match self.alive()[..] {
+            [champion] => Some(champion),
+            _ => None,
+        }
+    }
+
+    /// Result of the next pairing, draws the following round once this one is complete
+    pub fn report(&mut self, result: MatchResult) {
+        let Some(next) = self.next() else {
+            return;
+        };
+        self.pairings[next].result = Some(result);
+        if self.next().is_none() && self.champion().is_none() {
+            self.draw_round();
+        }
+    }
+
+    fn draw_round(&mut self) {
+        let round = self.pairings.last().map_or(0, |pairing| pairing.round + 1);
+        let alive = self.alive();
+        let groups: Vec<Vec<usize>> = (0..self.elimination.lives())
+            .map(|losses| {
+                alive
+                    .iter()
+                    .copied()
+                    .filter(|&entrant| self.losses(entrant) == losses)
+                    .collect()
+            })
+            .filter(|group: &Vec<usize>| !group.is_empty())
+            .collect();
+
+        if let [winners, losers] = &groups[..] {
+            if winners.len() == 1 && losers.len() == 1 {
+                self.pairings.push(Pairing {
+                    round,
+                    bracket: Bracket::Final,
+                    left: winners[0],
+                    right: Some(losers[0]),
+                    result: None,
+                });
+                return;
+            }
+        }
+
+        for group in groups {
+            // last one of a bracket waits for the other bracket
+            if group.len() < 2 {
+                continue;
+            }
+            let losses = self.losses(group[0]);
+            let bracket = if alive.len() == 2 {
+                Bracket::Final
+            } else if losses == 0 {
+                Bracket::Winners
+            } else {
+                Bracket::Losers
+            };
+            let mut players = group.clone();
+            if players.len() % 2 == 1 {
+                // fewest byes so far, later drawn ones first
+                let bye = players
+                    .iter()
+                    .copied()
+                    .rev()
+                    .min_by_key(|&entrant| self.byes(entrant))
+                    .unwrap();
+                players.retain(|&entrant| entrant != bye);
+                self.pairings.push(Pairing {
+                    round,
+                    bracket,
+                    left: bye,
+                    right: None,
+                    result: Some(MatchResult {
+                        winner: DiceKind::Left,
+                        score: (0, 0),
+                    }),
+                });
+            }
+            for pair in players.chunks(2) {
+                self.pairings.push(Pairing {
+                    round,
+                    bracket,
+                    left: pair[0],
+                    right: Some(pair[1]),
+                    result: None,
+                });
+            }
+        }
+    }
+
+    /// AI sides of pairing `index`
+    pub fn ai_dices(&self, index: usize) -> Vec<DiceKind> {
+        let pairing = &self.pairings[index];
+        let mut dices = Vec::new();
+        if self.entrants[pairing.left].ai {
+            dices.push(DiceKind::Left);
+        }
+        if pairing.right.is_some_and(|right| self.entrants[right].ai) {
+            dices.push(DiceKind::Right);
+        }
+        dices
+    }
+}
+
+/// Plays AI against AI on `board` without gameplay
+pub fn play_ai_match(board: &BoardConfig, seed: u64) -> MatchResult {
+    let mut sim = PongSim::new(board.clone());
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let dt = (1.0 / SIMULATION_HZ) as f32;
+    for _ in 0..AI_MATCH_TICKS {
+        if sim.winner().is_some() {
+            break;
+        }
+        let inputs = (
+            tracking_ai(&sim, DiceKind::Left),
+            tracking_ai(&sim, DiceKind::Right),
+        );
+        sim.step(dt, inputs, &mut rng);
+    }
+    let winner = sim.winner().unwrap_or(if sim.score_right > sim.score_left {
+        DiceKind::Right
+    } else {
+        DiceKind::Left
+    });
+    MatchResult {
+        winner,
+        score: (sim.score_left, sim.score_right),
+    }
+}
+
+/// Tournament pairing being played, kept with a suspended match
+#[derive(Clone, Debug, PartialEq, Resource, Serialize, Deserialize)]
+pub struct TournamentMatch {
+    /// `Tournament::seed` the pairing belongs to
+    pub seed: u64,
+    pub pairing: usize,
+}
+
+fn save(tournament: &Tournament) {
+    let path = tournament_path();
+    if let Err(e) = tournament.save(&path) {
+        error!("failed to save tournament {}: {}", path.display(), e);
+    }
+}
+
+fn forget_match(mut commands: Commands) {
+    commands.remove_resource::<TournamentMatch>();
+}
+
+fn control_ai_dices(
+    mut commands: Commands,
+    tournament_match: Res<TournamentMatch>,
+    tournament: Option<Res<Tournament>>,
+) {
+    let Some(tournament) = tournament.filter(|t| t.seed == tournament_match.seed) else {
+        return;
+    };
+    commands.insert_resource(AiDices(tournament.ai_dices(tournament_match.pairing)));
+}
+
+fn record_result(
+    match_sim: Res<MatchSim>,
+    tournament_match: Res<TournamentMatch>,
+    tournament: Option<ResMut<Tournament>>,
+) {
+    let sim = &match_sim.0;
+    let (Some(winner), Some(mut tournament)) = (sim.winner(), tournament) else {
+        return;
+    };
+    // tournament was replaced while the match was suspended
+    if tournament.seed != tournament_match.seed
+        || tournament.next() != Some(tournament_match.pairing)
+    {
+        warn!("tournament match result does not belong to current tournament");
+        return;
+    }
+    tournament.report(MatchResult {
+        winner,
+        score: (sim.score_left, sim.score_right),
+    });
+    save(&tournament);
+}
+
+/// Players entered so far on setup screen
+#[derive(Clone, Debug, Default, Resource)]
+pub struct TournamentSetup {
+    entrants: Vec<Entrant>,
+    elimination: Elimination,
+}
+
+impl TournamentSetup {
+    fn add(&mut self, name: &str, ai: bool) {
+        if self.entrants.len() >= MAX_PLAYERS {
+            return;
+        }
+        let name = match sanitize_name(name) {
+            name if name.is_empty() && ai => format!("CPU {}", self.entrants.len() + 1),
+            name if name.is_empty() => format!("Player {}", self.entrants.len() + 1),
+            name => name,
+        };
+        self.entrants.push(Entrant { name, ai });
+    }
+}
+
+#[derive(Component)]
+pub struct SetupUIRoot;
+
+#[derive(Component)]
+pub struct EntrantsText;
+
+#[derive(Component)]
+pub struct PlayerNameInput;
+
+#[derive(Clone, Copy, Component)]
+pub enum SetupButton {
+    AddPlayer,
+    AddAi,
+    RemoveLast,
+    Elimination,
+    Start,
+    Back,
+}
+
+impl SetupButton {
+    fn label(&self, setup: &TournamentSetup) -> String {
+        match self {
+            SetupButton::AddPlayer => "Add Player".to_string(),
+            SetupButton::AddAi => "Add AI".to_string(),
+            SetupButton::RemoveLast => "Remove Last".to_string(),
+            SetupButton::Elimination => setup.elimination.label().to_string(),
+            SetupButton::Start => "Start".to_string(),
+            SetupButton::Back => "Back".to_string(),
+        }
+    }
+}
+
+fn entrants_lines(setup: &TournamentSetup) -> String {
+    let names: Vec<String> = setup
+        .entrants
+        .iter()
+        .map(|entrant| {
+            if entrant.ai {
+                format!("{} (AI)", entrant.name)
+            } else {
+                entrant.name.clone()
+            }
+        })
+        .collect();
+    let mut lines: Vec<String> = names.chunks(4).map(|names| names.join(", ")).collect();
+    lines.push(format!(
+        "{}/{} players, {} needed",
+        setup.entrants.len(),
+        MAX_PLAYERS,
+        MIN_PLAYERS
+    ));
+    lines.join("\n")
+}
+
+fn spawn_setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    theme: Res<Theme>,
+    setup: Res<TournamentSetup>,
+) {
+    let name_input = spawn_text_input(&mut commands, &asset_server, &theme, "", MAX_NAME_LENGTH);
+    commands.entity(name_input).insert(PlayerNameInput);
+
+    let mut spawn = |button: SetupButton, color: Color| {
+        let entity = spawn_sized_button(
+            &mut commands,
+            &asset_server,
+            &theme,
+            &button.label(&setup),
+            color,
+            ButtonSize::Compact,
+        );
+        commands.entity(entity).insert(button);
+        entity
+    };
+    let buttons = [
+        spawn(SetupButton::AddPlayer, theme.button),
+        spawn(SetupButton::AddAi, theme.button),
+        spawn(SetupButton::RemoveLast, theme.button),
+        spawn(SetupButton::Elimination, theme.button),
+    ];
+    let start_button = spawn(SetupButton::Start, theme.button_primary);
+    let back_button = spawn(SetupButton::Back, theme.button_secondary);
+    let grid = spawn_button_grid(&mut commands, &buttons);
+
+    let text_style = |font_size: f32| TextStyle {
+        font: asset_server.load(FONT_PATH),
+        font_size: font_size * theme.font_scale,
+        color: theme.text,
+    };
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            ..default()
+        })
+        .insert(SetupUIRoot)
+        .with_children(|commands| {
+            commands.spawn(TextBundle {
+                style: Style {
+                    align_self: AlignSelf::Center,
+                    margin: UiRect::all(Val::Percent(2.0)),
+                    ..default()
+                },
+                text: Text::from_section("TOURNAMENT", text_style(96.0)),
+                ..default()
+            });
+            commands.spawn((
+                TextBundle {
+                    style: Style {
+                        align_self: AlignSelf::Center,
+                        margin: UiRect::all(Val::Percent(1.0)),
+                        ..default()
+                    },
+                    text: Text::from_section(entrants_lines(&setup), text_style(28.0)),
+                    ..default()
+                },
+                EntrantsText,
+            ));
+        })
+        .add_child(name_input)
+        .add_child(grid)
+        .add_child(start_button)
+        .add_child(back_button);
+}
+
+fn despawn_setup(mut commands: Commands, menu_root: Query<Entity, With<SetupUIRoot>>) {
+    let root_entity = menu_root.single();
+    commands.entity(root_entity).despawn_recursive();
+}
+
+fn setup_button_clicked(
+    mut commands: Commands,
+    interactions: Query<(&Interaction, &SetupButton), Changed<Interaction>>,
+    mut name_inputs: Query<&mut TextInput, With<PlayerNameInput>>,
+    mut setup: ResMut<TournamentSetup>,
+    mut seeds: ResMut<SeedSequence>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for (interaction, button) in &interactions {
+        if !matches!(interaction, Interaction::Pressed) {
+            continue;
+        }
+        match button {
+            SetupButton::AddPlayer => {
+                if let Ok(mut input) = name_inputs.get_single_mut() {
+                    setup.add(&input.value, false);
+                    input.value.clear();
+                }
+            }
+            SetupButton::AddAi => setup.add("", true),
+            SetupButton::RemoveLast => {
+                setup.entrants.pop();
+            }
+            SetupButton::Elimination => setup.elimination = setup.elimination.other(),
+            SetupButton::Start => {
+                if setup.entrants.len() < MIN_PLAYERS {
+                    continue;
+                }
+                let entrants = std::mem::take(&mut setup.entrants);
+                let tournament = Tournament::new(entrants, setup.elimination, seeds.next_seed());
+                save(&tournament);
+                commands.insert_resource(tournament);
+                next_state.set(GameState::Bracket);
+            }
+            SetupButton::Back => next_state.set(GameState::MainMenu),
+        }
+    }
+}
+
+/// Return in name field adds a human player
+fn add_typed_player(
+    mut submitted: EventReader<TextInputSubmitted>,
+    mut name_inputs: Query<&mut TextInput, With<PlayerNameInput>>,
+    mut setup: ResMut<TournamentSetup>,
+) {
+    for event in submitted.read() {
+        if let Ok(mut input) = name_inputs.get_mut(event.entity) {
+            setup.add(&event.value, false);
+            input.value.clear();
+        }
+    }
+}
+
+fn update_setup_texts(
+    setup: Res<TournamentSetup>,
+    buttons: Query<(&SetupButton, &Children)>,
+    entrants_texts: Query<Entity, With<EntrantsText>>,
+    mut texts: Query<&mut Text>,
+) {
+    for entity in &entrants_texts {
+        if let Ok(mut text) = texts.get_mut(entity) {
+            text.sections[0].value = entrants_lines(&setup);
+        }
+    }
+    for (button, children) in &buttons {
+        set_button_text(children, &mut texts, button.label(&setup));
+    }
+}
+
+#[derive(Component)]
+pub struct BracketUIRoot;
+
+#[derive(Clone, Copy, Component)]
+pub enum BracketButton {
+    /// plays or simulates the next pairing
+    Play,
+    NewTournament,
+    MainMenu,
+}
+
+/// Game over button of a tournament match, leads back to the bracket
+#[derive(Component)]
+pub struct BackToBracketButton;
+
+fn pairing_lines(tournament: &Tournament, pairing: &Pairing) -> String {
+    let name = |entrant: usize| &tournament.entrants[entrant].name;
+    let Some(right) = pairing.right else {
+        return format!("{} {}\n  bye", pairing.bracket.label(), name(pairing.left));
+    };
+    let (left_score, right_score) = match pairing.result {
+        Some(result) => (result.score.0.to_string(), result.score.1.to_string()),
+        None => ("-".to_string(), "-".to_string()),
+    };
+    let mark = |entrant: usize| {
+        if pairing.winner() == Some(entrant) && pairing.result.is_some() {
+            ">"
+        } else {
+            " "
+        }
+    };
+    format!(
+        "{}\n{}{} {}\n{}{} {}",
+        pairing.bracket.label(),
+        mark(pairing.left),
+        name(pairing.left),
+        left_score,
+        mark(right),
+        name(right),
+        right_score,
+    )
+}
+
+fn spawn_bracket(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    theme: Res<Theme>,
+    tournament: Option<Res<Tournament>>,
+) {
+    let Some(tournament) = tournament else {
+        return;
+    };
+    let text_style = |font_size: f32| TextStyle {
+        font: asset_server.load(FONT_PATH),
+        font_size: font_size * theme.font_scale,
+        color: theme.text,
+    };
+
+    let (title, next_line) = match (tournament.champion(), tournament.next()) {
+        (Some(champion), _) => (
+            format!("{} Is Champion", tournament.entrants[champion].name),
+            String::new(),
+        ),
+        (None, Some(next)) => {
+            let pairing = &tournament.pairings[next];
+            let right = pairing
+                .right
+                .map_or("-", |right| &tournament.entrants[right].name);
+            (
+                "BRACKET".to_string(),
+                format!(
+                    "Next: {} vs {}",
+                    tournament.entrants[pairing.left].name, right
+                ),
+            )
+        }
+        (None, None) => ("BRACKET".to_string(), String::new()),
+    };
+
+    let mut buttons = Vec::new();
+    let mut spawn = |button: BracketButton, label: &str, color: Color| {
+        let entity = spawn_sized_button(
+            &mut commands,
+            &asset_server,
+            &theme,
+            label,
+            color,
+            ButtonSize::Compact,
+        );
+        commands.entity(entity).insert(button);
+        buttons.push(entity);
+    };
+    match tournament.next() {
+        Some(next) if tournament.ai_dices(next).len() == 2 => {
+            spawn(BracketButton::Play, "Simulate", theme.button_primary)
+        }
+        Some(_) => spawn(BracketButton::Play, "Play", theme.button_primary),
+        None => spawn(
+            BracketButton::NewTournament,
+            "New Tournament",
+            theme.button_primary,
+        ),
+    }
+    spawn(BracketButton::MainMenu, "Main Menu", theme.button_secondary);
+
+    // latest rounds, oldest left
+    let last_round = tournament
+        .pairings
+        .last()
+        .map_or(0, |pairing| pairing.round);
+    let first_round = (last_round + 1).saturating_sub(BRACKET_COLUMNS as u32);
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            ..default()
+        })
+        .insert(BracketUIRoot)
+        .with_children(|commands| {
+            commands.spawn(TextBundle {
+                style: Style {
+                    align_self: AlignSelf::Center,
+                    margin: UiRect::all(Val::Percent(1.0)),
+                    ..default()
+                },
+                text: Text::from_section(title, text_style(60.0)),
+                ..default()
+            });
+            commands
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Percent(90.0),
+                        align_self: AlignSelf::Center,
+                        justify_content: JustifyContent::SpaceEvenly,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|columns| {
+                    for round in first_round..=last_round {
+                        let lines: Vec<String> = tournament
+                            .pairings
+                            .iter()
+                            .filter(|pairing| pairing.round == round)
+                            .map(|pairing| pairing_lines(&tournament, pairing))
+                            .collect();
+                        columns.spawn(TextBundle::from_section(
+                            format!("Round {}\n\n{}", round + 1, lines.join("\n\n")),
+                            text_style(20.0),
+                        ));
+                    }
+                });
+            commands.spawn(TextBundle {
+                style: Style {
+                    align_self: AlignSelf::Center,
+                    margin: UiRect::all(Val::Percent(1.0)),
+                    ..default()
+                },
+                text: Text::from_section(next_line, text_style(32.0)),
+                ..default()
+            });
+        })
+        .push_children(&buttons);
+}
+
+fn despawn_bracket(mut commands: Commands, menu_root: Query<Entity, With<BracketUIRoot>>) {
+    if let Ok(root_entity) = menu_root.get_single() {
+        commands.entity(root_entity).despawn_recursive();
+    }
+}
+
+fn bracket_button_clicked(
+    mut commands: Commands,
+    interactions: Query<(&Interaction, &BracketButton), Changed<Interaction>>,
+    board: Res<BoardConfig>,
+    tournament: Option<ResMut<Tournament>>,
+    mut mode: ResMut<GameMode>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let Some(mut tournament) = tournament else {
+        return;
+    };
+    for (interaction, button) in &interactions {
+        if !matches!(interaction, Interaction::Pressed) {
+            continue;
+        }
+        match button {
+            BracketButton::Play => {
+                let Some(next) = tournament.next() else {
+                    continue;
+                };
+                if tournament.ai_dices(next).len() == 2 {
+                    let seed = tournament.seed.wrapping_add(next as u64);
+                    tournament.report(play_ai_match(&board, seed));
+                    save(&tournament);
+                } else {
+                    commands.insert_resource(TournamentMatch {
+                        seed: tournament.seed,
+                        pairing: next,
+                    });
+                    *mode = GameMode::Versus;
+                    next_state.set(GameState::Gameplay);
+                }
+            }
+            BracketButton::NewTournament => {
+                commands.remove_resource::<Tournament>();
+                let path = tournament_path();
+                if let Err(e) = fs::remove_file(&path) {
+                    warn!("failed to remove tournament {}: {}", path.display(), e);
+                }
+                next_state.set(GameState::TournamentSetup);
+            }
+            BracketButton::MainMenu => next_state.set(GameState::MainMenu),
+        }
+    }
+}
+
+fn back_to_bracket_clicked(
+    interactions: Query<&Interaction, (With<BackToBracketButton>, Changed<Interaction>)>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for interaction in &interactions {
+        if matches!(interaction, Interaction::Pressed) {
+            next_state.set(GameState::Bracket);
+        }
+    }
+}
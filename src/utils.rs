@@ -1,46 +1,81 @@
-use std::f32::consts::PI;
+use std::fs;
+use std::path::Path;
 
 use bevy::prelude::*;
-use rand::distributions::{Distribution, Uniform};
-use rand::Rng;
 
-#[derive(Clone, Debug, Copy, PartialEq, Eq)]
-pub enum DiceKind {
-    Left,
-    Right,
-}
+pub use crate::sim::{DiceKind, MAX_BOUNCE_ANGLE};
+use crate::theme::Theme;
 
 pub static FONT_PATH: &str = "fonts/Minimal5x7.ttf";
 
-pub const MAX_BOUNCE_ANGLE: f32 = 5. * PI / 12.;
+/// Window pixels per world unit when `world_size` is fit into the window
+pub fn world_scale(window: &Window, world_size: Vec2) -> f32 {
+    (window.width() / world_size.x).min(window.height() / world_size.y)
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum ButtonSize {
+    Large,
+    /// for screens with many buttons, like settings
+    Compact,
+}
+
+impl ButtonSize {
+    fn height_percent(&self) -> f32 {
+        match self {
+            ButtonSize::Large => 15.0,
+            ButtonSize::Compact => 8.0,
+        }
+    }
+
+    fn margin_percent(&self) -> f32 {
+        match self {
+            ButtonSize::Large => 2.0,
+            ButtonSize::Compact => 1.0,
+        }
+    }
 
-pub fn get_random_starting_angle() -> Vec2 {
-    let step = Uniform::new(-1.0, 1.0);
-    let mut rng = rand::thread_rng();
-    let swing = step.sample(&mut rng);
-    let is_right = rng.gen::<bool>();
-    let bounce_angle = swing * MAX_BOUNCE_ANGLE;
-    if is_right {
-        return Vec2::new(-bounce_angle.cos(), -bounce_angle.sin());
-    } else {
-        return Vec2::new(bounce_angle.cos(), -bounce_angle.sin());
+    fn font_size(&self) -> f32 {
+        match self {
+            ButtonSize::Large => 64.0,
+            ButtonSize::Compact => 40.0,
+        }
     }
 }
 
 pub fn spawn_button(
     commands: &mut Commands,
     asset_server: &AssetServer,
+    theme: &Theme,
     text: &str,
     color: Color,
+) -> Entity {
+    spawn_sized_button(
+        commands,
+        asset_server,
+        theme,
+        text,
+        color,
+        ButtonSize::Large,
+    )
+}
+
+pub fn spawn_sized_button(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    theme: &Theme,
+    text: &str,
+    color: Color,
+    size: ButtonSize,
 ) -> Entity {
     commands
         .spawn(ButtonBundle {
             style: Style {
-                height: Val::Percent(15.0),
+                height: Val::Percent(size.height_percent()),
                 width: Val::Percent(65.0),
                 align_self: AlignSelf::Center,
                 justify_content: JustifyContent::Center,
-                margin: UiRect::all(Val::Percent(2.0)),
+                margin: UiRect::all(Val::Percent(size.margin_percent())),
                 ..default()
             },
             background_color: color.into(),
@@ -57,8 +92,8 @@ pub fn spawn_button(
                     text,
                     TextStyle {
                         font: asset_server.load(FONT_PATH),
-                        font_size: 64.0,
-                        color: Color::BLACK,
+                        font_size: size.font_size() * theme.font_scale,
+                        color: theme.button_text,
                     },
                 ),
                 ..default()
@@ -66,3 +101,80 @@ pub fn spawn_button(
         })
         .id()
 }
+
+/// Replaces label of a button spawned with `spawn_button`
+pub fn set_button_text(children: &Children, texts: &mut Query<&mut Text>, value: String) {
+    for &child in children.iter() {
+        if let Ok(mut text) = texts.get_mut(child) {
+            text.sections[0].value = value;
+            return;
+        }
+    }
+}
+
+/// Lays out `buttons` spawned with `spawn_sized_button` in two columns, for menus
+/// with more entries than fit below each other
+pub fn spawn_button_grid(commands: &mut Commands, buttons: &[Entity]) -> Entity {
+    for &button in buttons {
+        commands.entity(button).insert(Style {
+            width: Val::Percent(48.0),
+            height: Val::Vh(8.0),
+            justify_content: JustifyContent::Center,
+            margin: UiRect::vertical(Val::Vh(1.0)),
+            ..default()
+        });
+    }
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(65.0),
+                align_self: AlignSelf::Center,
+                flex_wrap: FlexWrap::Wrap,
+                justify_content: JustifyContent::SpaceBetween,
+                ..default()
+            },
+            ..default()
+        })
+        .push_children(buttons)
+        .id()
+}
+
+/// Loads every `*.ron` file from `dir` in name order with `load`, unusable ones are
+/// skipped with a warning naming `what` they should have been
+pub fn load_ron_dir<T>(
+    dir: &Path,
+    what: &str,
+    load: impl Fn(&Path) -> Result<T, String>,
+) -> Vec<T> {
+    let mut entries: Vec<_> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "ron"))
+            .collect(),
+        Err(e) => {
+            warn!("failed to read {} from {}: {}", what, dir.display(), e);
+            Vec::new()
+        }
+    };
+    entries.sort();
+
+    entries
+        .into_iter()
+        .filter_map(|path| match load(&path) {
+            Ok(item) => Some(item),
+            Err(e) => {
+                warn!("skipping {} {}: {}", what, path.display(), e);
+                None
+            }
+        })
+        .collect()
+}
+
+/// Next of `count` library entries that can also be off, off comes after the last one
+pub fn select_next_or_off(selected: Option<usize>, count: usize) -> Option<usize> {
+    match selected {
+        None if count > 0 => Some(0),
+        Some(index) if index + 1 < count => Some(index + 1),
+        _ => None,
+    }
+}
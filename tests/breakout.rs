@@ -0,0 +1,144 @@
+use std::path::Path;
+
+use bevy_pong::breakout::BrickLibrary;
+use bevy_pong::sim::{brick_collision, Ball, BoardConfig, Brick, DiceKind, PongSim, SimEvent};
+
+mod common;
+
+use common::{ball_to_right, both_ai, rng, run_until_winner, DT};
+
+fn brick(hp: u32) -> Brick {
+    Brick {
+        x: 0.2,
+        y: 0.,
+        width: 0.08,
+        height: 0.3,
+        hp,
+        points: 2,
+    }
+}
+
+/// Ball heading right from the middle towards a single brick
+fn sim_with_brick(hp: u32, last_hit: Option<DiceKind>) -> PongSim {
+    let mut sim = PongSim::breakout(BoardConfig::default(), vec![brick(hp)]);
+    sim.ball = Ball {
+        last_hit,
+        ..ball_to_right()
+    };
+    sim
+}
+
+/// Steps until the ball meets the brick
+fn step_to_brick(sim: &mut PongSim) -> Vec<SimEvent> {
+    let mut rng = rng(1);
+    for _ in 0..60 {
+        let events = sim.step(DT, (0., 0.), &mut rng);
+        if events
+            .iter()
+            .any(|event| matches!(event, SimEvent::BrickHit { .. }))
+        {
+            return events;
+        }
+    }
+    panic!("ball never reached the brick");
+}
+
+#[test]
+fn collision_picks_touched_side() {
+    let centre = (100., 0.);
+    let size = (40., 100.);
+    assert_eq!(brick_collision(70., 0., 20., centre, size), Some((-1., 0.)));
+    assert_eq!(
+        brick_collision(100., 60., 20., centre, size),
+        Some((0., 1.))
+    );
+    assert_eq!(brick_collision(59., 0., 20., centre, size), None);
+    // corner is missed by a ball just outside its radius
+    assert_eq!(brick_collision(66., 66., 20., centre, size), None);
+    // centre already inside leaves by the closer edge
+    assert_eq!(
+        brick_collision(85., 10., 20., centre, size),
+        Some((-1., 0.))
+    );
+}
+
+#[test]
+fn breaking_brick_scores_for_last_hitter() {
+    let mut sim = sim_with_brick(2, Some(DiceKind::Left));
+    let events = step_to_brick(&mut sim);
+    assert!(events.contains(&SimEvent::BrickHit {
+        kind: Some(DiceKind::Left),
+        brick: 0,
+        broken: false,
+    }));
+    assert!(sim.ball.velocity_x < 0., "ball bounces back");
+    assert_eq!(sim.bricks[0].hp, 1);
+    assert_eq!((sim.score_left, sim.score_right), (0, 0));
+
+    // second hit breaks the last brick and ends the round
+    sim.ball = Ball {
+        velocity_x: 1.,
+        last_hit: Some(DiceKind::Left),
+        ..Ball::default()
+    };
+    let events = step_to_brick(&mut sim);
+    assert!(events.contains(&SimEvent::BricksCleared));
+    assert_eq!((sim.score_left, sim.score_right), (2, 0));
+    assert!(sim.ball.serve_delay.is_some());
+    assert_eq!(sim.bricks, sim.brick_layout);
+}
+
+#[test]
+fn serve_breaks_bricks_without_scoring() {
+    let mut sim = sim_with_brick(1, None);
+    let events = step_to_brick(&mut sim);
+    assert!(events.contains(&SimEvent::BrickHit {
+        kind: None,
+        brick: 0,
+        broken: true,
+    }));
+    assert_eq!((sim.score_left, sim.score_right), (0, 0));
+}
+
+#[test]
+fn goal_restores_bricks() {
+    let mut sim = PongSim::breakout(BoardConfig::default(), vec![brick(1), brick(1)]);
+    sim.bricks[0].hp = 0;
+    sim.ball = Ball {
+        x: 300.,
+        ..ball_to_right()
+    };
+    let mut rng = rng(1);
+    // right dice far away at the bottom misses
+    sim.right_dice_y = sim.board.max_dice_position(false);
+    sim.ball.y = sim.board.max_dice_position(true);
+    while sim.score_left == 0 {
+        sim.step(DT, (0., 0.), &mut rng);
+    }
+    assert_eq!(sim.bricks, sim.brick_layout);
+}
+
+#[test]
+fn shipped_layouts_play_to_a_winner() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/bricks");
+    let mut library = BrickLibrary::load(&dir);
+    let mut names = Vec::new();
+    for _ in 0..2 {
+        let layout = library.selected().unwrap().clone();
+        names.push(layout.name.clone());
+        library.select_next();
+
+        let mut sim = PongSim::breakout(BoardConfig::default(), layout.bricks);
+        let mut broken = 0;
+        run_until_winner(&mut sim, 9, both_ai, |_, events| {
+            for event in events {
+                if let SimEvent::BrickHit { broken: true, .. } = event {
+                    broken += 1;
+                }
+            }
+        });
+        assert!(sim.winner().is_some(), "{} never ended", layout.name);
+        assert!(broken > 0);
+    }
+    assert_eq!(names, ["Columns", "Diamond"]);
+}
@@ -0,0 +1,56 @@
+//! Setup shared by simulation tests, every test crate uses only part of it
+#![allow(dead_code)]
+
+use bevy_pong::ai::tracking_ai;
+use bevy_pong::sim::{Ball, DiceKind, PongSim, SimEvent};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+pub const DT: f32 = 1. / 60.;
+/// 20 minutes of play, matches running longer are considered stuck
+pub const MAX_MATCH_TICKS: u32 = 60 * 60 * 20;
+
+/// Serves and launches of `PongSim::step` from `seed`
+pub fn rng(seed: u64) -> ChaCha8Rng {
+    ChaCha8Rng::seed_from_u64(seed)
+}
+
+/// Ball in flight from the middle towards the right dice
+pub fn ball_to_right() -> Ball {
+    Ball {
+        velocity_x: 1.,
+        ..Ball::default()
+    }
+}
+
+/// Tracking AI on the left against a dice standing still on the right
+pub fn left_ai(sim: &PongSim) -> (f32, f32) {
+    (tracking_ai(sim, DiceKind::Left), 0.)
+}
+
+/// Tracking AI on both sides
+pub fn both_ai(sim: &PongSim) -> (f32, f32) {
+    (
+        tracking_ai(sim, DiceKind::Left),
+        tracking_ai(sim, DiceKind::Right),
+    )
+}
+
+/// Steps `sim` with serves from `seed` until it has a winner, at most `MAX_MATCH_TICKS`.
+/// `inputs` picks dice inputs every tick, `observe` sees the sim after each step with its events.
+pub fn run_until_winner(
+    sim: &mut PongSim,
+    seed: u64,
+    mut inputs: impl FnMut(&PongSim) -> (f32, f32),
+    mut observe: impl FnMut(&PongSim, &[SimEvent]),
+) {
+    let mut rng = rng(seed);
+    for _ in 0..MAX_MATCH_TICKS {
+        let inputs = inputs(sim);
+        let events = sim.step(DT, inputs, &mut rng);
+        observe(sim, &events);
+        if sim.winner().is_some() {
+            return;
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use bevy_pong::ai::tracking_ai;
+use bevy_pong::env::PongEnv;
+use bevy_pong::sim::{BoardConfig, DiceKind};
+
+const SEED: u64 = 3;
+
+/// Plays one episode, `agent` decides every action
+fn play(env: &mut PongEnv, seed: u64, agent: impl Fn(&PongEnv) -> f32) -> (f32, u32) {
+    env.reset(seed);
+    let (mut total_reward, mut steps) = (0., 0);
+    loop {
+        let (_, reward, done) = env.step(agent(env));
+        total_reward += reward;
+        steps += 1;
+        if done {
+            return (total_reward, steps);
+        }
+    }
+}
+
+#[test]
+fn reset_starts_serving_in_the_middle() {
+    let mut env = PongEnv::new(BoardConfig::default(), DiceKind::Left);
+    let observation = env.reset(SEED);
+    assert_eq!(observation.ball_x, 0.);
+    assert_eq!(observation.ball_velocity_x, 0.);
+    assert_eq!(observation.dice_y, 0.);
+}
+
+#[test]
+fn same_seed_and_actions_give_same_episode() {
+    let episode = || {
+        let mut env = PongEnv::new(BoardConfig::default(), DiceKind::Left);
+        env.reset(SEED);
+        (0..3000)
+            .map(|tick| env.step(((tick / 50) % 3) as f32 - 1.))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(episode(), episode());
+}
+
+#[test]
+fn idle_agent_loses_the_match() {
+    let mut env = PongEnv::new(BoardConfig::default(), DiceKind::Right);
+    let (total_reward, _) = play(&mut env, SEED, |_| 0.);
+
+    let sim = env.sim();
+    assert_eq!(sim.winner(), Some(DiceKind::Left));
+    let expected = sim.score_right as f32 - sim.score_left as f32;
+    assert_eq!(total_reward, expected);
+}
+
+#[test]
+fn undecided_episode_is_cut_off() {
+    let mut env = PongEnv::new(BoardConfig::default(), DiceKind::Left);
+    env.max_ticks = 600;
+    let (_, steps) = play(&mut env, SEED, |env| tracking_ai(env.sim(), env.side()));
+    assert_eq!(steps, 600);
+    assert!(env.sim().winner().is_none());
+}
+
+#[test]
+fn built_in_ai_can_be_beaten() {
+    // full speed tracker with steep shots outruns the slower built-in AI
+    let mut env = PongEnv::new(BoardConfig::default(), DiceKind::Left);
+    let (total_reward, _) = play(&mut env, SEED, |env| {
+        let sim = env.sim();
+        let aim = sim.board.dice_length() * 0.45;
+        let target_y = sim.ball.y + aim.copysign(sim.right_dice_y);
+        ((target_y - sim.left_dice_y) / 10.).clamp(-1., 1.)
+    });
+    assert!(total_reward > 0.);
+}
+
+#[test]
+fn observation_is_mirrored_for_right_side() {
+    let mut left = PongEnv::new(BoardConfig::default(), DiceKind::Left);
+    let mut right = PongEnv::new(BoardConfig::default(), DiceKind::Right);
+    left.reset(SEED);
+    right.reset(SEED);
+    for _ in 0..200 {
+        let (left_view, _, _) = left.step(0.);
+        let (right_view, _, _) = right.step(0.);
+        assert_eq!(left.sim().ball, right.sim().ball);
+        assert_eq!(left_view.ball_x, -right_view.ball_x);
+        assert_eq!(left_view.ball_velocity_x, -right_view.ball_velocity_x);
+        assert!(left_view.ball_x.abs() <= 1.);
+        assert!(left_view.ball_y.abs() <= 1.);
+    }
+}
@@ -0,0 +1,160 @@
+use std::path::Path;
+
+use bevy_pong::forces::{check_forces, ForcesLibrary, MAX_STRENGTH};
+use bevy_pong::sim::{BoardConfig, Forces, PongSim, Well, Wind};
+
+mod common;
+
+use common::{ball_to_right, left_ai, rng, run_until_winner, DT};
+
+/// Ball in flight from the middle towards right dice
+fn sim_with_forces(forces: Forces) -> PongSim {
+    let mut sim = PongSim::new(BoardConfig::default());
+    sim.forces = forces;
+    sim.ball = ball_to_right();
+    sim
+}
+
+#[test]
+fn gravity_pulls_ball_down() {
+    let mut sim = sim_with_forces(Forces {
+        gravity: 0.6,
+        ..Forces::default()
+    });
+    let mut rng = rng(1);
+    for _ in 0..30 {
+        sim.step(DT, (0., 0.), &mut rng);
+    }
+    assert!((sim.ball.velocity_y + 0.3).abs() < 1e-3);
+    assert_eq!(sim.ball.velocity_x, 1.);
+    assert!(sim.ball.y < 0.);
+}
+
+#[test]
+fn headwind_never_stalls_ball() {
+    let mut sim = sim_with_forces(Forces {
+        // blows towards left dice for the first half of each cycle
+        wind: Some(Wind {
+            strength: -20.,
+            period: 4.,
+        }),
+        ..Forces::default()
+    });
+    let mut rng = rng(1);
+    for _ in 0..60 {
+        sim.step(DT, (0., 0.), &mut rng);
+        assert!(sim.ball.velocity_x >= 0.3 - 1e-6);
+        assert!(sim.ball.velocity_x.hypot(sim.ball.velocity_y) <= 1.6 + 1e-3);
+    }
+}
+
+#[test]
+fn wind_swings_with_its_period() {
+    let forces = Forces {
+        wind: Some(Wind {
+            strength: 2.,
+            period: 8.,
+        }),
+        ..Forces::default()
+    };
+    assert!((forces.wind_at(2.) - 2.).abs() < 1e-4);
+    assert!((forces.wind_at(6.) + 2.).abs() < 1e-4);
+    assert!(forces.wind_at(8.).abs() < 1e-4);
+}
+
+#[test]
+fn wells_pull_in_and_push_away() {
+    let board = BoardConfig::default();
+    let well = |strength| Well {
+        x: 0.,
+        y: 0.,
+        radius: 0.5,
+        strength,
+    };
+    let attractor = Forces {
+        wells: vec![well(1.)],
+        ..Forces::default()
+    };
+    let (x, y) = attractor.acceleration(&board, 0., 50., 0.);
+    assert!(x < 0. && y == 0.);
+    // fades to nothing at the edge of its reach
+    let reach = 0.5 * board.height() / 2.;
+    assert_eq!(attractor.acceleration(&board, 0., reach + 1., 0.), (0., 0.));
+    assert_eq!(attractor.acceleration(&board, 0., 0., 0.), (0., 0.));
+
+    let repeller = Forces {
+        wells: vec![well(-1.)],
+        ..Forces::default()
+    };
+    let (x, y) = repeller.acceleration(&board, 0., 0., -50.);
+    assert!(x == 0. && y < 0.);
+}
+
+#[test]
+fn broken_presets_are_rejected() {
+    let well = Well {
+        x: 0.,
+        y: 0.,
+        radius: 0.5,
+        strength: 1.,
+    };
+    let wells = |well: Well| Forces {
+        wells: vec![well],
+        ..Forces::default()
+    };
+    assert!(check_forces(&wells(well.clone())).is_ok());
+    assert!(check_forces(&wells(Well {
+        x: f32::NAN,
+        ..well.clone()
+    }))
+    .is_err());
+    assert!(check_forces(&wells(Well {
+        radius: f32::NAN,
+        ..well.clone()
+    }))
+    .is_err());
+    assert!(check_forces(&wells(Well {
+        strength: f32::INFINITY,
+        ..well.clone()
+    }))
+    .is_err());
+
+    let gravity = |gravity| Forces {
+        gravity,
+        ..Forces::default()
+    };
+    assert!(check_forces(&gravity(-MAX_STRENGTH)).is_ok());
+    assert!(check_forces(&gravity(f32::NAN)).is_err());
+    assert!(check_forces(&gravity(MAX_STRENGTH * 2.)).is_err());
+
+    let wind = |strength, period| Forces {
+        wind: Some(Wind { strength, period }),
+        ..Forces::default()
+    };
+    assert!(check_forces(&wind(1., 4.)).is_ok());
+    assert!(check_forces(&wind(f32::NAN, 4.)).is_err());
+    assert!(check_forces(&wind(1., f32::INFINITY)).is_err());
+    assert!(
+        check_forces(&Forces::default()).is_err(),
+        "calm is no preset"
+    );
+}
+
+#[test]
+fn shipped_presets_play_to_a_winner() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/forces");
+    let mut library = ForcesLibrary::load(&dir);
+    assert!(library.selected().is_none(), "forces are off on startup");
+    library.select_next();
+    let mut names = Vec::new();
+    while let Some(forces) = library.selected() {
+        names.push(forces.name.clone());
+        let mut sim = sim_with_forces(forces.clone());
+        // two tracking AIs may rally forever along the floor under plain gravity,
+        // the one on the right stands still instead
+        run_until_winner(&mut sim, 4, left_ai, |_, _| {});
+        assert!(sim.winner().is_some(), "{} never ended", forces.name);
+        library.select_next();
+    }
+    assert_eq!(names, ["Gravity", "Gusts", "Wells"]);
+}
@@ -0,0 +1,173 @@
+use bevy_pong::gameplay::BoardState;
+use bevy_pong::harness::HeadlessGame;
+use bevy_pong::utils::DiceKind;
+use bevy_pong::GameState;
+
+const SEED: u64 = 7;
+
+/// Puts ball in play at given position and direction
+fn put_ball(game: &mut HeadlessGame, x: f32, y: f32, velocity_x: f32, velocity_y: f32) {
+    let board = BoardState {
+        ball_x: x,
+        ball_y: y,
+        ball_velocity_x: velocity_x,
+        ball_velocity_y: velocity_y,
+        serving: false,
+        ..game.board().unwrap()
+    };
+    game.set_board(&board);
+}
+
+#[test]
+fn starts_serving_with_empty_score() {
+    let game = HeadlessGame::new(SEED);
+    assert_eq!(game.state(), GameState::Gameplay);
+
+    let board = game.board().unwrap();
+    assert!(board.serving);
+    assert_eq!((board.ball_x, board.ball_y), (0.0, 0.0));
+    assert_eq!((board.score_left, board.score_right), (0, 0));
+}
+
+#[test]
+fn ball_is_served_after_start_delay() {
+    let mut game = HeadlessGame::new(SEED);
+    game.run(30, 0.0, 0.0);
+    assert!(game.board().unwrap().serving);
+
+    // start delay is 1.5 secs
+    game.run(70, 0.0, 0.0);
+    let board = game.board().unwrap();
+    assert!(!board.serving);
+    assert_ne!(board.ball_velocity_x, 0.0);
+}
+
+#[test]
+fn same_seed_serves_same_direction() {
+    let serve = |seed| {
+        let mut game = HeadlessGame::new(seed);
+        game.run(100, 0.0, 0.0);
+        let board = game.board().unwrap();
+        (board.ball_velocity_x, board.ball_velocity_y)
+    };
+    assert_eq!(serve(SEED), serve(SEED));
+}
+
+#[test]
+fn ball_bounces_off_top_wall() {
+    let mut game = HeadlessGame::new(SEED);
+    let top_y = game.board_config().height() / 2. - game.board_config().ball_radius();
+    put_ball(&mut game, 0.0, top_y - 5.0, 0.6, 0.8);
+
+    game.run(5, 0.0, 0.0);
+    let board = game.board().unwrap();
+    assert!(board.ball_velocity_y < 0.0);
+    assert!(board.ball_velocity_x > 0.0);
+    assert!(board.ball_y < game.board_config().height() / 2.);
+}
+
+#[test]
+fn ball_bounces_off_bottom_wall() {
+    let mut game = HeadlessGame::new(SEED);
+    let bottom_y = -game.board_config().height() / 2. + game.board_config().ball_radius();
+    put_ball(&mut game, 0.0, bottom_y + 5.0, -0.6, -0.8);
+
+    game.run(5, 0.0, 0.0);
+    let board = game.board().unwrap();
+    assert!(board.ball_velocity_y > 0.0);
+    assert!(board.ball_velocity_x < 0.0);
+}
+
+#[test]
+fn dice_returns_ball() {
+    let mut game = HeadlessGame::new(SEED);
+    let axis_x = game.board_config().dice_axis_x(DiceKind::Right);
+    put_ball(&mut game, axis_x - 3.0, 0.0, 1.0, 0.0);
+
+    game.step(0.0, 0.0);
+    let board = game.board().unwrap();
+    assert!(board.ball_velocity_x < 0.0);
+    assert_eq!(board.last_hit, Some(DiceKind::Right));
+    assert_eq!(game.hit_events().len(), 1);
+    assert_eq!(game.hit_events()[0].kind, DiceKind::Right);
+    assert!(game.lost_events().is_empty());
+}
+
+#[test]
+fn dice_hit_off_center_angles_ball() {
+    let mut game = HeadlessGame::new(SEED);
+    let axis_x = game.board_config().dice_axis_x(DiceKind::Left);
+    // ball meets upper half of the dice
+    put_ball(&mut game, axis_x + 3.0, 50.0, -1.0, 0.0);
+
+    game.step(0.0, 0.0);
+    let board = game.board().unwrap();
+    assert!(board.ball_velocity_x > 0.0);
+    assert!(board.ball_velocity_y > 0.0);
+    assert!(game.hit_events()[0].bounce_angle > 0.0);
+}
+
+#[test]
+fn missed_ball_scores_for_opponent() {
+    let mut game = HeadlessGame::new(SEED);
+    let axis_x = game.board_config().dice_axis_x(DiceKind::Right);
+    put_ball(&mut game, axis_x - 3.0, -250.0, 1.0, 0.0);
+
+    let lost = game.run(60, 0.0, 0.0);
+    assert_eq!(lost.len(), 1);
+    assert!(lost[0].is_right);
+
+    let board = game.board().unwrap();
+    assert_eq!((board.score_left, board.score_right), (1, 0));
+    assert!(board.serving);
+    assert_eq!((board.ball_x, board.ball_y), (0.0, 0.0));
+    assert_eq!(game.state(), GameState::Gameplay);
+}
+
+#[test]
+fn reaching_winning_score_ends_match() {
+    let mut game = HeadlessGame::new(SEED);
+    let axis_x = game.board_config().dice_axis_x(DiceKind::Left);
+    let winning_score = game.board_config().winning_score() as u32;
+    let board = BoardState {
+        score_right: winning_score - 1,
+        ..game.board().unwrap()
+    };
+    game.set_board(&board);
+    put_ball(&mut game, axis_x + 3.0, 250.0, -1.0, 0.0);
+
+    let lost = game.run(60, 0.0, 0.0);
+    assert_eq!(lost.len(), 1);
+    assert!(!lost[0].is_right);
+    assert_eq!(game.state(), GameState::GameOver);
+    assert_eq!(game.winner(), Some(DiceKind::Right));
+    // board is gone with the gameplay state
+    assert!(game.board().is_none());
+}
+
+#[test]
+fn dice_stops_at_wall() {
+    let mut game = HeadlessGame::new(SEED);
+    game.run(60, 1.0, -1.0);
+
+    let board = game.board().unwrap();
+    let config = game.board_config();
+    assert_eq!(board.left_dice_y, config.max_dice_position(true));
+    assert_eq!(board.right_dice_y, config.max_dice_position(false));
+}
+
+#[test]
+fn same_seed_and_inputs_give_same_match() {
+    let play = || {
+        let mut game = HeadlessGame::new(SEED);
+        let mut boards = Vec::new();
+        for tick in 0..600 {
+            let left = ((tick / 40) % 3) as f32 - 1.0;
+            let right = ((tick / 25) % 3) as f32 - 1.0;
+            game.step(left, right);
+            boards.push(game.board());
+        }
+        boards
+    };
+    assert_eq!(play(), play());
+}
@@ -0,0 +1,86 @@
+use bevy_pong::history::{MatchHistory, MatchRecorder};
+use bevy_pong::sim::{BoardConfig, DiceKind, PongSim, SimEvent};
+
+mod common;
+
+use common::{left_ai, rng, run_until_winner, DT};
+
+fn recorder() -> MatchRecorder {
+    MatchRecorder::new(5, ("Left".to_string(), "Right".to_string()))
+}
+
+/// Plays AI against a dice standing still on the right
+fn record_match() -> (PongSim, MatchRecorder, u32) {
+    let mut sim = PongSim::new(BoardConfig::default());
+    let mut recorder = recorder();
+    let mut total_hits = 0;
+    run_until_winner(&mut sim, 5, left_ai, |sim, events| {
+        total_hits += events
+            .iter()
+            .filter(|event| matches!(event, SimEvent::DiceHit { .. }))
+            .count() as u32;
+        recorder.observe(sim, DT, events);
+    });
+    (sim, recorder, total_hits)
+}
+
+#[test]
+fn every_point_is_recorded() {
+    let (sim, recorder, total_hits) = record_match();
+    let record = &recorder.record;
+    assert!(sim.winner().is_some());
+    assert_eq!(record.winner, sim.winner());
+    assert_eq!(record.score(), (sim.score_left, sim.score_right));
+
+    let board = &sim.board;
+    for point in &record.points {
+        assert!(point.rally_secs > 0.);
+        assert!(point.serve_angle.abs() <= bevy_pong::sim::MAX_BOUNCE_ANGLE);
+        assert_eq!(point.final_speed, board.ball_speed());
+    }
+    let rally_hits: u32 = record.points.iter().map(|point| point.rally_hits).sum();
+    assert_eq!(rally_hits, total_hits);
+    assert_eq!(record.hits.0 + record.hits.1, total_hits);
+    assert!(record.longest_rally() <= total_hits);
+    for kind in [DiceKind::Left, DiceKind::Right] {
+        let offset = record.average_hit_offset(kind);
+        assert!(offset.is_none_or(|offset| (0. ..=1.).contains(&offset)));
+    }
+    // dice standing still on the right returns some serves
+    assert!(record.hits.1 > 0);
+    let played: f32 = record.points.iter().map(|point| point.rally_secs).sum();
+    assert!(record.duration_secs > played);
+}
+
+#[test]
+fn hit_offsets_come_from_dice_contact() {
+    let mut sim = PongSim::new(BoardConfig::default());
+    let mut rng = rng(5);
+    let mut recorder = recorder();
+    let below_ball = sim.board.dice_length() / 4.;
+    while recorder.record.hits.1 == 0 {
+        // right dice meets the ball half way up its upper half
+        sim.right_dice_y = sim.ball.y - below_ball;
+        let events = sim.step(DT, (0., 0.), &mut rng);
+        recorder.observe(&sim, DT, &events);
+    }
+    let contact = sim.ball.hit_offset.unwrap();
+    assert!(contact > 0.3);
+    assert_eq!(
+        recorder.record.average_hit_offset(DiceKind::Right),
+        Some(contact)
+    );
+}
+
+#[test]
+fn history_keeps_newest_matches() {
+    let mut history = MatchHistory::default();
+    for seed in 0..150 {
+        let mut recorder = recorder();
+        recorder.record.seed = seed;
+        history.push(recorder.record);
+    }
+    assert_eq!(history.matches().len(), 100);
+    assert_eq!(history.matches()[0].seed, 50);
+    assert_eq!(history.matches()[99].seed, 149);
+}
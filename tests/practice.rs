@@ -0,0 +1,112 @@
+use bevy_pong::practice::{
+    describe_offset, Drill, LaunchPattern, PracticeOpponent, PracticeRun, PracticeSetup,
+    DRILL_BALLS, SURVIVE_RETURNS,
+};
+use bevy_pong::sim::{BoardConfig, DiceKind, Launcher, PongSim, SimEvent, MAX_BOUNCE_ANGLE};
+
+mod common;
+
+use common::{left_ai, rng, DT};
+
+/// Practices `drill` with the AI on the left until it is finished
+fn practice(setup: &PracticeSetup, ticks: u32) -> (PongSim, PracticeRun) {
+    let board = BoardConfig::default();
+    let mut sim = PongSim::practice(board.clone(), DiceKind::Right, setup.opponent().launcher());
+    let mut run = PracticeRun::new(setup.drill, &board);
+    let mut rng = rng(3);
+    for _ in 0..ticks {
+        let events = sim.step(DT, left_ai(&sim), &mut rng);
+        for event in &events {
+            if let SimEvent::DiceHit { kind, .. } = event {
+                assert_eq!(*kind, DiceKind::Left);
+                // outgoing angle follows the offset like `dice_collision` bounces it
+                let offset = sim.ball.hit_offset.unwrap();
+                assert!((-1. ..=1.).contains(&offset));
+                let angle = sim.ball.velocity_y.asin();
+                assert!((angle - offset * MAX_BOUNCE_ANGLE).abs() < 1e-3);
+            }
+        }
+        run.observe(&sim, &events);
+        assert_eq!(sim.winner(), None, "practice never ends by score");
+        if run.finished() {
+            break;
+        }
+    }
+    (sim, run)
+}
+
+#[test]
+fn steep_angle_drill_fires_steep_balls() {
+    let setup = PracticeSetup {
+        drill: Drill::SteepAngles,
+        ..Default::default()
+    };
+    assert_eq!(
+        setup.opponent(),
+        PracticeOpponent::Launcher {
+            pattern: LaunchPattern::Steep,
+            speed_percent: 100,
+        }
+    );
+    let (sim, run) = practice(&setup, 60 * 60 * 5);
+    assert!(run.finished());
+    assert_eq!(run.attempts, DRILL_BALLS);
+    assert_eq!(run.returns + run.misses, DRILL_BALLS);
+    // serve and every return are fired by the launcher
+    let launcher = sim.launcher.unwrap();
+    assert_eq!(launcher.shots as u32, DRILL_BALLS + 1);
+    assert!(launcher
+        .angles
+        .iter()
+        .all(|angle| angle.abs() >= 0.85 * MAX_BOUNCE_ANGLE - 1e-6));
+}
+
+#[test]
+fn target_zone_drill_counts_zone_returns() {
+    let setup = PracticeSetup {
+        drill: Drill::TargetZone,
+        launcher: true,
+        pattern: LaunchPattern::Flat,
+        ..Default::default()
+    };
+    let (_, run) = practice(&setup, 60 * 60 * 5);
+    assert!(run.finished());
+    assert_eq!(run.attempts, DRILL_BALLS);
+    assert!(run.zone_hits <= run.returns);
+    let offset = run.average_offset().unwrap();
+    assert!((0. ..=1.).contains(&offset));
+}
+
+#[test]
+fn survive_drill_ends_at_first_miss_or_goal() {
+    let setup = PracticeSetup {
+        drill: Drill::Survive,
+        ..Default::default()
+    };
+    let (_, run) = practice(&setup, 60 * 60 * 10);
+    assert!(run.finished());
+    assert!(run.misses == 1 || run.returns == SURVIVE_RETURNS);
+    assert!(run.misses <= 1);
+}
+
+#[test]
+fn offsets_are_described_from_dice_centre() {
+    assert_eq!(describe_offset(0.05), "centre");
+    assert_eq!(describe_offset(0.35), "35% above centre");
+    assert_eq!(describe_offset(-0.8), "80% below centre");
+}
+
+#[test]
+fn launcher_without_angles_fires_straight() {
+    let launcher = Launcher {
+        angles: Vec::new(),
+        speed: 1.,
+        shots: 0,
+    };
+    let mut sim = PongSim::practice(BoardConfig::default(), DiceKind::Right, Some(launcher));
+    let mut rng = rng(3);
+    while sim.ball.serve_delay.is_some() {
+        sim.step(DT, (0., 0.), &mut rng);
+    }
+    assert_eq!((sim.ball.velocity_x, sim.ball.velocity_y), (-1., 0.));
+}
@@ -0,0 +1,64 @@
+use bevy_pong::profile::{Controls, MatchTracker, PlayerStats, Profiles};
+use bevy_pong::sim::DiceKind;
+
+fn won_match() -> MatchTracker {
+    MatchTracker {
+        points_left: 10,
+        points_right: 4,
+        winner: Some(DiceKind::Left),
+        longest_rally: 7,
+        fastest_ball: 400.,
+        rallies: 14,
+        rally_hits: 42,
+        ..MatchTracker::default()
+    }
+}
+
+#[test]
+fn stats_add_up_over_matches() {
+    let mut stats = PlayerStats::default();
+    assert_eq!(stats.average_rally(), 0.);
+
+    stats.record(&won_match(), DiceKind::Left);
+    stats.record(&won_match(), DiceKind::Right);
+    assert_eq!(stats.matches_played, 2);
+    assert_eq!(stats.matches_won, 1);
+    assert_eq!(stats.points_scored, 14);
+    assert_eq!(stats.points_conceded, 14);
+    assert_eq!(stats.longest_rally, 7);
+    assert_eq!(stats.fastest_ball, 400.);
+    assert_eq!(stats.average_rally(), 3.);
+}
+
+#[test]
+fn guest_gets_controls_left_by_active_profile() {
+    let mut profiles = Profiles::default();
+    assert_eq!(profiles.controls(DiceKind::Left), Controls::WS);
+    assert_eq!(profiles.controls(DiceKind::Right), Controls::Arrows);
+
+    let profile = profiles.active_mut();
+    profile.side = DiceKind::Right;
+    profile.controls = Controls::WS;
+    assert_eq!(profiles.controls(DiceKind::Left), Controls::Arrows);
+    assert_eq!(profiles.controls(DiceKind::Right), Controls::WS);
+}
+
+#[test]
+fn profiles_survive_save_and_load() {
+    let mut profiles = Profiles::default();
+    profiles
+        .active_mut()
+        .stats
+        .record(&won_match(), DiceKind::Left);
+    profiles.add();
+    profiles.active_mut().side = DiceKind::Right;
+
+    let path = std::env::temp_dir().join(format!("pong-profiles-{}.ron", std::process::id()));
+    profiles.save(&path).unwrap();
+    let loaded = Profiles::load(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded, profiles);
+    assert_eq!(loaded.profiles().len(), 2);
+    assert_eq!(loaded.active().name, "Player 2");
+}
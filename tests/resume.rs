@@ -0,0 +1,49 @@
+use bevy_pong::harness::HeadlessGame;
+use bevy_pong::resume::SavedMatch;
+
+/// Inputs of tick `tick`, varied so dices move around
+fn inputs(tick: u32) -> (f32, f32) {
+    let phase = tick as f32 / 40.;
+    (phase.sin(), (phase * 1.3).cos())
+}
+
+#[test]
+fn resumed_match_continues_exactly() {
+    let mut game = HeadlessGame::new(21);
+    let mut tick = 0;
+    // play past the first point, then into the next rally
+    while game.board().unwrap().score_left + game.board().unwrap().score_right == 0 {
+        let (left, right) = inputs(tick);
+        game.step(left, right);
+        tick += 1;
+    }
+    for _ in 0..150 {
+        let (left, right) = inputs(tick);
+        game.step(left, right);
+        tick += 1;
+    }
+    let board = game.board().unwrap();
+    assert!(!board.serving, "ball is in flight when saved");
+
+    let path = std::env::temp_dir().join(format!("bevy_pong_resume_{}.save", std::process::id()));
+    game.save().unwrap().save(&path).unwrap();
+    let saved = SavedMatch::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let mut resumed = HeadlessGame::resume(saved);
+    assert_eq!(resumed.board(), Some(board), "score is not reset");
+
+    for _ in 0..60 * 30 {
+        let (left, right) = inputs(tick);
+        game.step(left, right);
+        resumed.step(left, right);
+        tick += 1;
+        assert_eq!(resumed.board(), game.board());
+        assert_eq!(resumed.lost_events(), game.lost_events());
+        assert_eq!(resumed.hit_events(), game.hit_events());
+        if game.winner().is_some() {
+            break;
+        }
+    }
+    assert_eq!(resumed.winner(), game.winner());
+}
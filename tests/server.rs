@@ -0,0 +1,168 @@
+use std::net::{SocketAddr, UdpSocket};
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_pong::gameplay::{BoardState, GameplayPlugin};
+use bevy_pong::protocol::{decode, encode, ClientMessage, ServerMessage, MAX_PACKET_SIZE};
+use bevy_pong::server::{GameServer, ServerPlugin};
+use bevy_pong::sim::DiceKind;
+use bevy_pong::{GameState, LastWinner};
+
+/// Server app like `pong-server` on a free port, updated manually
+fn server() -> (App, SocketAddr) {
+    let server = GameServer::bind(0).unwrap();
+    let addr = SocketAddr::from(([127, 0, 0, 1], server.local_port()));
+    let mut app = App::new();
+    app.add_state::<GameState>()
+        .insert_resource(LastWinner::default())
+        .insert_resource(server)
+        .add_plugins((MinimalPlugins, GameplayPlugin, ServerPlugin));
+    (app, addr)
+}
+
+/// Updates often enough for fixed updates to run in real time
+fn update(app: &mut App) {
+    app.update();
+    std::thread::sleep(Duration::from_millis(2));
+}
+
+struct Client {
+    socket: UdpSocket,
+    server: SocketAddr,
+}
+
+impl Client {
+    fn new(server: SocketAddr) -> Self {
+        let socket = UdpSocket::bind(("127.0.0.1", 0)).unwrap();
+        socket.set_nonblocking(true).unwrap();
+        Self { socket, server }
+    }
+
+    fn send(&self, message: &ClientMessage) {
+        self.socket.send_to(&encode(message), self.server).unwrap();
+    }
+
+    fn receive(&self) -> Vec<ServerMessage> {
+        let mut buf = [0u8; MAX_PACKET_SIZE];
+        let mut messages = Vec::new();
+        while let Ok((len, _)) = self.socket.recv_from(&mut buf) {
+            messages.extend(decode(&buf[..len]));
+        }
+        messages
+    }
+
+    fn join(&self, app: &mut App, name: &str) -> DiceKind {
+        self.send(&ClientMessage::Join { name: name.into() });
+        for _ in 0..100 {
+            update(app);
+            for message in self.receive() {
+                if let ServerMessage::Welcome { side } = message {
+                    return side;
+                }
+            }
+        }
+        panic!("{} was not welcomed", name);
+    }
+
+    /// Updates server until this client got `count` more snapshots, gives the last one
+    fn snapshots(&self, app: &mut App, count: usize) -> (u32, BoardState) {
+        let mut received = Vec::new();
+        for _ in 0..1000 {
+            update(app);
+            received.extend(
+                self.receive()
+                    .into_iter()
+                    .filter_map(|message| match message {
+                        ServerMessage::Snapshot { tick, board } => Some((tick, board)),
+                        _ => None,
+                    }),
+            );
+            if received.len() >= count {
+                return received.pop().unwrap();
+            }
+        }
+        panic!("server sent no snapshots");
+    }
+}
+
+/// Two joined players, match already running
+fn running_match() -> (App, Client, Client) {
+    let (mut app, addr) = server();
+    let left = Client::new(addr);
+    let right = Client::new(addr);
+    assert_eq!(left.join(&mut app, "Left"), DiceKind::Left);
+    assert_eq!(right.join(&mut app, "Right"), DiceKind::Right);
+    update(&mut app);
+    assert_eq!(
+        *app.world.resource::<State<GameState>>().get(),
+        GameState::Gameplay
+    );
+    (app, left, right)
+}
+
+#[test]
+fn joined_players_get_snapshots() {
+    let (mut app, left, right) = running_match();
+    left.send(&ClientMessage::Input { seq: 0, axis: 1. });
+    let (first_tick, _) = left.snapshots(&mut app, 1);
+    let (tick, _) = right.snapshots(&mut app, 5);
+    assert!(tick > first_tick);
+}
+
+#[test]
+fn third_player_is_turned_away() {
+    let (mut app, left, _right) = running_match();
+    let late = Client::new(left.server);
+    late.send(&ClientMessage::Join {
+        name: "Late".into(),
+    });
+    let mut answers = Vec::new();
+    for _ in 0..20 {
+        update(&mut app);
+        answers.extend(late.receive());
+    }
+    assert_eq!(answers, vec![ServerMessage::Full]);
+}
+
+#[test]
+fn stale_and_broken_inputs_are_ignored() {
+    let (mut app, left, _right) = running_match();
+    left.send(&ClientMessage::Input { seq: 5, axis: 1. });
+    let (_, before) = left.snapshots(&mut app, 3);
+
+    left.send(&ClientMessage::Input { seq: 3, axis: -1. });
+    left.send(&ClientMessage::Input {
+        seq: 6,
+        axis: f32::NAN,
+    });
+    let (_, after) = left.snapshots(&mut app, 3);
+    assert!(after.left_dice_y.is_finite());
+    assert!(after.left_dice_y > before.left_dice_y);
+}
+
+#[test]
+fn player_left_alone_wins() {
+    let (mut app, left, right) = running_match();
+    left.snapshots(&mut app, 1);
+    right.send(&ClientMessage::Leave);
+
+    let mut over = Vec::new();
+    for _ in 0..20 {
+        update(&mut app);
+        over.extend(
+            left.receive()
+                .into_iter()
+                .filter(|message| matches!(message, ServerMessage::MatchOver { .. })),
+        );
+    }
+    assert_eq!(
+        over,
+        vec![ServerMessage::MatchOver {
+            winner: Some(DiceKind::Left)
+        }]
+    );
+    assert_eq!(
+        app.world.resource::<LastWinner>().player(),
+        Some(DiceKind::Left)
+    );
+}
@@ -0,0 +1,304 @@
+use bevy_pong::forces::{check_forces, MAX_STRENGTH, MAX_WELLS};
+use bevy_pong::sim::*;
+use proptest::prelude::*;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+const EPSILON: f32 = 1e-4;
+
+fn board_config() -> impl Strategy<Value = BoardConfig> {
+    (
+        400f32..2000.,
+        300f32..1200.,
+        0.1f32..0.6,
+        0.05f32..0.25,
+        5f32..40.,
+        100f32..900.,
+    )
+        .prop_map(
+            |(width, height, dice_share, offset_share, ball_radius, ball_speed)| {
+                BoardConfig::new(
+                    width,
+                    height,
+                    height * dice_share,
+                    width * offset_share,
+                    ball_radius,
+                    ball_speed,
+                )
+            },
+        )
+}
+
+/// Forces within preset limits, `check_forces` still has to accept them
+fn forces() -> impl Strategy<Value = Forces> {
+    let strength = -MAX_STRENGTH..=MAX_STRENGTH;
+    let wind =
+        (strength.clone(), 0.5f32..20.).prop_map(|(strength, period)| Wind { strength, period });
+    let well = (-1f32..=1., -1f32..=1., 0.05f32..1., strength.clone()).prop_map(
+        |(x, y, radius, strength)| Well {
+            x,
+            y,
+            radius,
+            strength,
+        },
+    );
+    (
+        strength,
+        prop::option::of(wind),
+        prop::collection::vec(well, 0..=MAX_WELLS),
+    )
+        .prop_map(|(gravity, wind, wells)| Forces {
+            name: String::new(),
+            gravity,
+            wind,
+            wells,
+        })
+}
+
+/// `(dt, left input, right input)` of consecutive frames
+fn frames() -> impl Strategy<Value = Vec<(f32, f32, f32)>> {
+    let input = prop_oneof![Just(-1f32), Just(0f32), Just(1f32), -1f32..=1.];
+    prop::collection::vec((1f32 / 240. ..1. / 20., input.clone(), input), 1..600)
+}
+
+/// Match with ball in play somewhere between dice axes, position and
+/// direction given as shares of the board
+fn sim_in_play(board: BoardConfig, x: f32, y: f32, angle: f32, to_right: bool) -> PongSim {
+    let mut sim = PongSim::new(board);
+    let board = &sim.board;
+    let axis_x = board.dice_axis_x(DiceKind::Right);
+    let top_y = board.height() / 2. - board.ball_radius();
+    let bounce_angle = angle * MAX_BOUNCE_ANGLE;
+    let direction = if to_right { 1. } else { -1. };
+    sim.ball = Ball {
+        x: x * axis_x,
+        y: y * top_y,
+        velocity_x: direction * bounce_angle.cos(),
+        velocity_y: bounce_angle.sin(),
+        ..Ball::default()
+    };
+    sim
+}
+
+/// Checks invariants of one `step` going from `before` to `after`
+fn check_step(
+    before: &PongSim,
+    after: &PongSim,
+    events: &[SimEvent],
+    dt: f32,
+) -> Result<(), TestCaseError> {
+    let board = &after.board;
+    let half_width = board.width() / 2.;
+    let top_y = board.height() / 2. - board.ball_radius();
+    let axis_left = board.dice_axis_x(DiceKind::Left);
+    let axis_right = board.dice_axis_x(DiceKind::Right);
+    // ball moves with the speed it had before a hit in this step
+    let step = board.ball_speed() * before.ball_speed_factor * (1. + before.ball.speed_bonus) * dt;
+    let ball = &after.ball;
+    let was_in_play = before.ball.serve_delay.is_none();
+    // forces bend the ball before it moves and may speed it up
+    let forced = !after.forces.is_calm();
+    let (moving_x, moving_y) = if forced && was_in_play {
+        forced_velocity(&after.forces, board, after.elapsed_secs, &before.ball, dt)
+    } else {
+        (before.ball.velocity_x, before.ball.velocity_y)
+    };
+    let max_velocity = if forced { 1.6 } else { 1. };
+
+    // ball overshoots a wall by at most one step and then heads back,
+    // beyond goal line it may only be when bouncing back from a dice
+    if ball.y.abs() > top_y {
+        let crossed_now =
+            before.ball.y.abs() <= top_y && ball.y.abs() <= top_y + step * max_velocity;
+        prop_assert!(
+            crossed_now || ball.y.abs() < before.ball.y.abs(),
+            "ball left through wall"
+        );
+    }
+    prop_assert!(
+        ball.x.abs() <= half_width || ball.is_colliding_x,
+        "ball left through goal line without round ending"
+    );
+
+    // velocity is a direction, speed comes from board, forces only bend it within limits
+    let speed = (ball.velocity_x.powi(2) + ball.velocity_y.powi(2)).sqrt();
+    if ball.serve_delay.is_some() {
+        prop_assert_eq!(speed, 0.);
+    } else if forced {
+        prop_assert!(speed <= max_velocity + EPSILON, "velocity length {}", speed);
+        // steepest dice hit may leave less until forces act on it again
+        let least_crossing = MAX_BOUNCE_ANGLE.cos().min(0.3);
+        prop_assert!(
+            ball.velocity_x.abs() >= least_crossing - EPSILON,
+            "ball stalled"
+        );
+    } else {
+        prop_assert!((speed - 1.).abs() < EPSILON, "velocity length {}", speed);
+    }
+
+    // latch is only held while ball is beyond dice axis and moving back
+    if ball.is_colliding_x {
+        let beyond_right = ball.x >= axis_right;
+        prop_assert!(
+            beyond_right || ball.x <= axis_left,
+            "x latch held between axes"
+        );
+        prop_assert!(
+            (ball.velocity_x < 0.) == beyond_right,
+            "x latch held while moving outwards"
+        );
+    }
+    // ball beyond a wall is always on its way back
+    if ball.y > top_y {
+        prop_assert!(ball.velocity_y <= 0., "ball moving out past top wall");
+    }
+    if ball.y < -top_y {
+        prop_assert!(ball.velocity_y >= 0., "ball moving out past bottom wall");
+    }
+
+    // ball crossing dice axis within reach of the dice has to bounce off it
+    if was_in_play && !before.ball.is_colliding_x && !before.ball.is_lost {
+        let moved_x = before.ball.x + moving_x * step;
+        let moved_y = before.ball.y + moving_y * step;
+        let reach = board.dice_length() / 2. + board.ball_radius() / 2.;
+        for (kind, crossed) in [
+            (DiceKind::Left, moved_x < axis_left),
+            (DiceKind::Right, moved_x > axis_right),
+        ] {
+            if crossed && after.wall == Some(kind) {
+                prop_assert!(ball.is_colliding_x, "ball passed through wall");
+                continue;
+            }
+            // keep clear of rounding at the dice edges
+            if crossed && (moved_y - after.dice_y(kind)).abs() < reach - EPSILON {
+                let hit = events
+                    .iter()
+                    .any(|event| matches!(event, SimEvent::DiceHit { kind: k, .. } if *k == kind));
+                prop_assert!(hit, "ball passed through {:?} dice", kind);
+            }
+        }
+    }
+    Ok(())
+}
+
+proptest! {
+    #[test]
+    fn ball_simulation_keeps_invariants(
+        board in board_config(),
+        x in -0.9f32..0.9,
+        y in -0.9f32..0.9,
+        angle in -1f32..1.,
+        to_right: bool,
+        seed: u64,
+        frames in frames(),
+    ) {
+        let mut sim = sim_in_play(board, x, y, angle, to_right);
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        for (dt, left, right) in frames {
+            let before = sim.clone();
+            let events = sim.step(dt, (left, right), &mut rng);
+            check_step(&before, &sim, &events, dt)?;
+        }
+    }
+
+    #[test]
+    fn served_match_keeps_invariants(
+        board in board_config(),
+        seed: u64,
+        frames in frames(),
+    ) {
+        prop_assert!(board.check().is_ok());
+        let mut sim = PongSim::new(board);
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        for (dt, left, right) in frames {
+            let before = sim.clone();
+            let events = sim.step(dt, (left, right), &mut rng);
+            check_step(&before, &sim, &events, dt)?;
+        }
+    }
+
+    #[test]
+    fn served_match_with_forces_keeps_invariants(
+        board in board_config(),
+        forces in forces(),
+        seed: u64,
+        frames in frames(),
+    ) {
+        prop_assume!(check_forces(&forces).is_ok());
+        let mut sim = PongSim::new(board);
+        sim.forces = forces;
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        for (dt, left, right) in frames {
+            let before = sim.clone();
+            let events = sim.step(dt, (left, right), &mut rng);
+            check_step(&before, &sim, &events, dt)?;
+        }
+    }
+
+    #[test]
+    fn survival_keeps_invariants(
+        board in board_config(),
+        seed: u64,
+        frames in frames(),
+    ) {
+        let mut sim = PongSim::survival(board, DiceKind::Right);
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        for (dt, left, right) in frames {
+            // game ends the match once decided
+            if sim.winner().is_some() {
+                break;
+            }
+            let before = sim.clone();
+            let events = sim.step(dt, (left, right), &mut rng);
+            check_step(&before, &sim, &events, dt)?;
+            // wall never misses and the first miss ends the run
+            prop_assert_eq!(sim.score_right > 0, sim.winner() == Some(DiceKind::Right));
+        }
+    }
+
+    #[test]
+    fn dice_collision_velocity_is_normalized(
+        ball_y in -500f32..500.,
+        quarter_ball in 1f32..10.,
+        dice_y in -500f32..500.,
+        half_dice in 20f32..300.,
+        is_right: bool,
+    ) {
+        let kind = if is_right { DiceKind::Right } else { DiceKind::Left };
+        let reach = half_dice + quarter_ball;
+        let collision = dice_collision(ball_y, quarter_ball, dice_y, half_dice, kind);
+        prop_assert_eq!(collision.is_some(), (ball_y - dice_y).abs() <= reach);
+
+        if let Some((velocity_x, velocity_y)) = collision {
+            let speed = (velocity_x.powi(2) + velocity_y.powi(2)).sqrt();
+            prop_assert!((speed - 1.).abs() < EPSILON);
+            // always back towards the opponent, never steeper than allowed
+            prop_assert_eq!(velocity_x < 0., is_right);
+            prop_assert!(velocity_y.abs().asin() <= MAX_BOUNCE_ANGLE + EPSILON);
+        }
+    }
+
+    #[test]
+    fn serve_velocity_is_normalized(seed: u64) {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let (velocity_x, velocity_y) = random_starting_angle(&mut rng);
+        let speed = (velocity_x.powi(2) + velocity_y.powi(2)).sqrt();
+        prop_assert!((speed - 1.).abs() < EPSILON);
+        prop_assert!(velocity_y.abs().asin() <= MAX_BOUNCE_ANGLE + EPSILON);
+    }
+}
+
+#[test]
+fn broken_boards_are_rejected() {
+    assert!(BoardConfig::default().check().is_ok());
+    let board = |width: f32, height: f32, dice_length: f32, ball_speed: f32| {
+        BoardConfig::new(width, height, dice_length, 100., 20., ball_speed)
+    };
+    assert!(board(1000., 700., 150., 400.).check().is_ok());
+    assert!(board(f32::NAN, 700., 150., 400.).check().is_err());
+    assert!(board(1000., f32::INFINITY, 150., 400.).check().is_err());
+    assert!(board(1000., 700., 150., 0.).check().is_err());
+    assert!(board(1000., 700., -150., 400.).check().is_err());
+    assert!(board(1000., 700., 700., 400.).check().is_err());
+    assert!(board(150., 700., 150., 400.).check().is_err());
+}
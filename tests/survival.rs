@@ -0,0 +1,63 @@
+use bevy_pong::sim::{BoardConfig, DiceKind, PongSim, SimEvent};
+use bevy_pong::survival::{Leaderboard, LeaderboardEntry};
+
+mod common;
+
+use common::{left_ai, run_until_winner};
+
+fn entry(name: &str, returns: u32) -> LeaderboardEntry {
+    LeaderboardEntry {
+        name: name.to_string(),
+        returns,
+        finished_at: 0,
+    }
+}
+
+#[test]
+fn leaderboard_keeps_best_ten() {
+    let mut leaderboard = Leaderboard::default();
+    assert!(!leaderboard.qualifies(0));
+    for returns in 1..=10 {
+        assert!(leaderboard.insert(entry("run", returns)).is_some());
+    }
+    assert!(!leaderboard.qualifies(1));
+    assert_eq!(leaderboard.insert(entry("late", 1)), None);
+
+    // ties stay behind older runs
+    assert_eq!(leaderboard.insert(entry("tie", 5)), Some(6));
+    let returns: Vec<u32> = leaderboard.entries().iter().map(|e| e.returns).collect();
+    assert_eq!(returns, [10, 9, 8, 7, 6, 5, 5, 4, 3, 2]);
+    assert_eq!(leaderboard.entries()[6].name, "tie");
+}
+
+#[test]
+fn survival_run_speeds_up_until_first_miss() {
+    let board = BoardConfig::default();
+    let mut sim = PongSim::survival(board.clone(), DiceKind::Right);
+    let mut hits = 0;
+    let mut fastest_bonus: f32 = 0.;
+    let mut fastest_speed: f32 = 0.;
+    run_until_winner(&mut sim, 11, left_ai, |sim, events| {
+        for event in events {
+            match event {
+                SimEvent::DiceHit { kind, .. } => {
+                    assert_eq!(*kind, DiceKind::Left, "wall is no dice");
+                    hits += 1;
+                }
+                SimEvent::PlayerLost { is_right } => assert!(!is_right, "wall never misses"),
+                SimEvent::TargetHit { .. }
+                | SimEvent::BrickHit { .. }
+                | SimEvent::BricksCleared => unreachable!("survival has no targets or bricks"),
+            }
+        }
+        fastest_bonus = fastest_bonus.max(sim.ball.speed_bonus);
+        fastest_speed = fastest_speed.max(sim.ball_speed());
+    });
+    assert_eq!(sim.winner(), Some(DiceKind::Right));
+    assert_eq!(sim.score_left, hits);
+    assert!(hits > 0);
+    // a miss serves a fresh ball at base speed
+    assert!(fastest_bonus > 0.);
+    assert!(fastest_speed > board.ball_speed());
+    assert_eq!(sim.ball.speed_bonus, 0.);
+}
@@ -0,0 +1,157 @@
+use std::path::Path;
+
+use bevy_pong::sim::{Ball, BoardConfig, DiceKind, PongSim, SimEvent, TargetLayout, TargetZone};
+use bevy_pong::targets::{check_layout, TargetLibrary, MAX_ZONES};
+
+mod common;
+
+use common::{ball_to_right, both_ai, rng, run_until_winner, DT};
+
+/// Whole opponent's half as one zone
+fn half_zone(points: usize) -> TargetZone {
+    TargetZone {
+        x: 0.5,
+        y: 0.,
+        width: 1.,
+        height: 2.,
+        points,
+    }
+}
+
+fn sim_with_ball(ball: Ball) -> PongSim {
+    let mut sim = PongSim::new(BoardConfig::default());
+    sim.targets = vec![half_zone(2)];
+    sim.ball = ball;
+    sim
+}
+
+fn target_hits(events: &[SimEvent]) -> Vec<(DiceKind, usize)> {
+    events
+        .iter()
+        .filter_map(|event| match event {
+            SimEvent::TargetHit { kind, points, .. } => Some((*kind, *points)),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn zone_awards_bonus_once_per_hit() {
+    let mut sim = sim_with_ball(Ball {
+        x: 10.,
+        last_hit: Some(DiceKind::Left),
+        ..ball_to_right()
+    });
+    let mut rng = rng(1);
+    let events = sim.step(DT, (0., 0.), &mut rng);
+    assert_eq!(target_hits(&events), [(DiceKind::Left, 2)]);
+    for _ in 0..10 {
+        let events = sim.step(DT, (0., 0.), &mut rng);
+        assert!(target_hits(&events).is_empty());
+    }
+    assert_eq!((sim.bonus_left, sim.bonus_right), (2, 0));
+    assert_eq!((sim.score_left, sim.score_right), (0, 0));
+}
+
+#[test]
+fn only_balls_leaving_a_dice_score() {
+    let mut rng = rng(1);
+    // serve was not hit by anybody
+    let mut serve = sim_with_ball(Ball {
+        x: 10.,
+        ..ball_to_right()
+    });
+    assert!(target_hits(&serve.step(DT, (0., 0.), &mut rng)).is_empty());
+    // ball coming back off the wall or the other dice
+    let mut back = sim_with_ball(Ball {
+        x: 10.,
+        velocity_x: -1.,
+        last_hit: Some(DiceKind::Left),
+        ..Ball::default()
+    });
+    assert!(target_hits(&back.step(DT, (0., 0.), &mut rng)).is_empty());
+    // own half of the right dice
+    let mut own_half = sim_with_ball(Ball {
+        x: 10.,
+        velocity_x: -1.,
+        last_hit: Some(DiceKind::Right),
+        ..Ball::default()
+    });
+    assert!(target_hits(&own_half.step(DT, (0., 0.), &mut rng)).is_empty());
+}
+
+#[test]
+fn zones_are_mirrored_for_right_dice() {
+    let board = BoardConfig::default();
+    let zone = TargetZone {
+        x: 0.5,
+        y: 0.5,
+        width: 0.2,
+        height: 0.2,
+        points: 1,
+    };
+    let (left_x, left_y) = zone.centre(&board, DiceKind::Left);
+    let (right_x, right_y) = zone.centre(&board, DiceKind::Right);
+    assert_eq!(left_x, board.width() / 4.);
+    assert_eq!(right_x, -left_x);
+    assert_eq!(left_y, right_y);
+    assert!(zone.contains(&board, DiceKind::Left, left_x, left_y));
+    assert!(!zone.contains(&board, DiceKind::Right, left_x, left_y));
+}
+
+#[test]
+fn rally_scores_bonus_for_both_sides() {
+    let mut sim = PongSim::new(BoardConfig::default());
+    sim.targets = vec![half_zone(1)];
+    let (mut left, mut right) = (0, 0);
+    run_until_winner(&mut sim, 5, both_ai, |_, events| {
+        for (kind, points) in target_hits(events) {
+            match kind {
+                DiceKind::Left => left += points,
+                DiceKind::Right => right += points,
+            }
+        }
+    });
+    assert!(sim.winner().is_some());
+    // every return crosses the opponent's half
+    assert!(left > 0 && right > 0);
+    assert_eq!((sim.bonus_left, sim.bonus_right), (left, right));
+}
+
+#[test]
+fn broken_layouts_are_rejected() {
+    let layout = |zones: Vec<TargetZone>| TargetLayout {
+        name: "Broken".to_string(),
+        zones,
+    };
+    assert!(check_layout(&layout(vec![half_zone(1)])).is_ok());
+    assert!(check_layout(&layout(Vec::new())).is_err());
+    assert!(check_layout(&layout(vec![half_zone(1); MAX_ZONES + 1])).is_err());
+    assert!(check_layout(&layout(vec![half_zone(0)])).is_err());
+
+    let zone = |x: f32, width: f32| TargetZone {
+        x,
+        width,
+        height: 0.5,
+        ..half_zone(1)
+    };
+    assert!(check_layout(&layout(vec![zone(0.5, 0.2)])).is_ok());
+    assert!(check_layout(&layout(vec![zone(f32::NAN, 0.2)])).is_err());
+    assert!(check_layout(&layout(vec![zone(0.5, f32::INFINITY)])).is_err());
+    assert!(check_layout(&layout(vec![zone(0.5, 0.)])).is_err());
+    assert!(check_layout(&layout(vec![zone(0.95, 0.2)])).is_err());
+}
+
+#[test]
+fn shipped_layouts_load() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/targets");
+    let mut library = TargetLibrary::load(&dir);
+    assert!(library.selected().is_none(), "targets are off on startup");
+    let mut names = Vec::new();
+    library.select_next();
+    while let Some(layout) = library.selected() {
+        names.push(layout.name.clone());
+        library.select_next();
+    }
+    assert_eq!(names, ["Bullseye", "Corners", "Ladder"]);
+}
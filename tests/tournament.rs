@@ -0,0 +1,149 @@
+use bevy_pong::sim::{BoardConfig, DiceKind};
+use bevy_pong::tournament::{
+    play_ai_match, Bracket, Elimination, Entrant, MatchResult, Tournament, MAX_PLAYERS, MIN_PLAYERS,
+};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+fn entrants(count: usize) -> Vec<Entrant> {
+    (0..count)
+        .map(|i| Entrant {
+            name: format!("Player {}", i + 1),
+            ai: i % 2 == 1,
+        })
+        .collect()
+}
+
+/// Reports random results until there is a champion, gives played pairings
+fn play_out(tournament: &mut Tournament, seed: u64) -> usize {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let mut played = 0;
+    while let Some(next) = tournament.next() {
+        assert!(tournament.champion().is_none());
+        let round = tournament.pairings()[next].round;
+        let in_round: Vec<usize> = tournament
+            .pairings()
+            .iter()
+            .filter(|pairing| pairing.round == round)
+            .flat_map(|pairing| [Some(pairing.left), pairing.right])
+            .flatten()
+            .collect();
+        let mut unique = in_round.clone();
+        unique.sort();
+        unique.dedup();
+        assert_eq!(unique.len(), in_round.len(), "nobody plays twice a round");
+
+        let winner = if rng.gen() {
+            DiceKind::Left
+        } else {
+            DiceKind::Right
+        };
+        tournament.report(MatchResult {
+            winner,
+            score: (5, 3),
+        });
+        played += 1;
+        assert!(played < 100, "tournament ends");
+    }
+    played
+}
+
+#[test]
+fn single_elimination_leaves_one_champion() {
+    for count in MIN_PLAYERS..=MAX_PLAYERS {
+        let mut tournament = Tournament::new(entrants(count), Elimination::Single, count as u64);
+        let played = play_out(&mut tournament, count as u64);
+        assert_eq!(played, count - 1);
+        let champion = tournament.champion().unwrap();
+        for entrant in 0..count {
+            let losses = if entrant == champion { 0 } else { 1 };
+            assert_eq!(tournament.losses(entrant), losses);
+        }
+        assert_eq!(
+            tournament.pairings().last().unwrap().bracket,
+            Bracket::Final
+        );
+    }
+}
+
+#[test]
+fn double_elimination_takes_two_losses() {
+    for count in MIN_PLAYERS..=MAX_PLAYERS {
+        for seed in 0..5 {
+            let mut tournament = Tournament::new(entrants(count), Elimination::Double, seed);
+            let played = play_out(&mut tournament, seed);
+            // champion lost at most once, everyone else twice
+            assert!(played == 2 * count - 2 || played == 2 * count - 1);
+            let champion = tournament.champion().unwrap();
+            for entrant in 0..count {
+                if entrant == champion {
+                    assert!(tournament.losses(entrant) <= 1);
+                } else {
+                    assert_eq!(tournament.losses(entrant), 2);
+                }
+            }
+            assert_eq!(
+                tournament.pairings().last().unwrap().bracket,
+                Bracket::Final
+            );
+        }
+    }
+}
+
+#[test]
+fn tournament_resumes_from_file() {
+    let mut tournament = Tournament::new(entrants(6), Elimination::Double, 3);
+    for _ in 0..4 {
+        tournament.report(MatchResult {
+            winner: DiceKind::Right,
+            score: (2, 5),
+        });
+    }
+    let path =
+        std::env::temp_dir().join(format!("bevy_pong_tournament_{}.ron", std::process::id()));
+    tournament.save(&path).unwrap();
+    let loaded = Tournament::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded, tournament);
+    assert_eq!(loaded.next(), tournament.next());
+}
+
+#[test]
+fn broken_tournament_files_are_not_loaded() {
+    let path = std::env::temp_dir().join(format!(
+        "bevy_pong_broken_tournament_{}.ron",
+        std::process::id()
+    ));
+    let load_edited = |tournament: &Tournament, edit: &dyn Fn(String) -> String| {
+        tournament.save(&path).unwrap();
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(&path, edit(content)).unwrap();
+        let loaded = Tournament::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        loaded
+    };
+
+    let tournament = Tournament::new(entrants(6), Elimination::Single, 5);
+    assert!(load_edited(&tournament, &|content| content).is_some());
+    let unknown_entrant = |content: String| content.replacen("left: ", "left: 9", 1);
+    assert!(load_edited(&tournament, &unknown_entrant).is_none());
+
+    let full = Tournament::new(entrants(MAX_PLAYERS), Elimination::Single, 5);
+    let extra_entrant = |content: String| {
+        content.replacen("entrants: [", "entrants: [(name: \"Extra\", ai: false),", 1)
+    };
+    assert!(load_edited(&full, &extra_entrant).is_none());
+}
+
+#[test]
+fn ai_match_has_a_winner() {
+    let board = BoardConfig::default();
+    let result = play_ai_match(&board, 7);
+    let (left, right) = result.score;
+    let winner_score = match result.winner {
+        DiceKind::Left => left,
+        DiceKind::Right => right,
+    };
+    assert!(winner_score >= left.max(right));
+    assert_eq!(play_ai_match(&board, 7), result, "same seed plays the same");
+}